@@ -0,0 +1,142 @@
+use evm_tool::address::{self, Prefix};
+use evm_tool::hex;
+use evm_tool::interpreter::Address;
+use evm_tool::keccak::keccak256;
+use evm_tool::u256::U256;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+enum Opts {
+    /// Address of a contract deployed with `CREATE` or by a transaction.
+    Create {
+        /// Deploying account, as hex.
+        #[structopt(short = "s", long = "sender")]
+        sender: String,
+        /// Nonce of the deploying account.
+        #[structopt(short = "n", long = "nonce", default_value = "0")]
+        nonce: u64,
+        /// Also print the addresses of the following deployments.
+        #[structopt(short = "c", long = "count", default_value = "1")]
+        count: u64,
+    },
+    /// Address of a contract deployed with `CREATE2`.
+    Create2 {
+        #[structopt(flatten)]
+        init: Create2Args,
+        #[structopt(long = "salt", default_value = "0")]
+        salt: U256,
+    },
+    /// Search for a `CREATE2` salt giving an address that starts with a prefix.
+    Mine {
+        #[structopt(flatten)]
+        init: Create2Args,
+        /// Hex digits the address must start with.
+        #[structopt(short = "p", long = "prefix")]
+        prefix: Prefix,
+        /// First salt tried.
+        #[structopt(long = "start", default_value = "0")]
+        start: U256,
+        /// Number of threads, all available cores by default.
+        #[structopt(short = "t", long = "threads")]
+        threads: Option<usize>,
+    },
+}
+
+#[derive(StructOpt)]
+struct Create2Args {
+    /// Deploying contract, e.g. a factory, as hex.
+    #[structopt(short = "s", long = "sender")]
+    sender: String,
+    /// Init code, as hex.
+    #[structopt(short = "i", long = "input", required_unless = "init-code-hash")]
+    input: Option<String>,
+    /// Keccak-256 of the init code, as hex, instead of the code itself.
+    #[structopt(long = "init-code-hash", conflicts_with = "input")]
+    init_code_hash: Option<String>,
+}
+
+impl Create2Args {
+    fn sender(&self) -> Address {
+        parse_address("sender", &self.sender)
+    }
+
+    fn init_code_hash(&self) -> [u8; 32] {
+        match (&self.input, &self.init_code_hash) {
+            (Some(input), _) => keccak256(&parse_hex("input", input)),
+            (None, Some(hash)) => {
+                parse_hex("init code hash", hash)
+                    .try_into()
+                    .unwrap_or_else(|_| {
+                        eprintln!("error: init code hash is not 32 bytes");
+                        std::process::exit(1);
+                    })
+            }
+            (None, None) => unreachable!("structopt requires one of them"),
+        }
+    }
+}
+
+fn main() {
+    match Opts::from_args() {
+        Opts::Create {
+            sender,
+            nonce,
+            count,
+        } => {
+            let sender = parse_address("sender", &sender);
+            for nonce in nonce..nonce.saturating_add(count) {
+                let address = address::create_address(&sender, nonce);
+                if count == 1 {
+                    println!("{}", address::to_checksum(&address));
+                } else {
+                    println!("{:>6} {}", nonce, address::to_checksum(&address));
+                }
+            }
+        }
+        Opts::Create2 { init, salt } => {
+            let address =
+                address::create2_address_from_hash(&init.sender(), salt, &init.init_code_hash());
+            println!("{}", address::to_checksum(&address));
+        }
+        Opts::Mine {
+            init,
+            prefix,
+            start,
+            threads,
+        } => {
+            let threads = threads.unwrap_or_else(|| {
+                std::thread::available_parallelism().map_or(1, |threads| threads.get())
+            });
+            let started = std::time::Instant::now();
+            let mined = address::mine_create2(
+                &init.sender(),
+                &init.init_code_hash(),
+                &prefix,
+                start,
+                threads,
+            );
+            println!("salt: {:#066x}", mined.salt);
+            println!("address: {}", address::to_checksum(&mined.address));
+            eprintln!(
+                "{} salts tried in {:.1?} on {} threads",
+                mined.attempts,
+                started.elapsed(),
+                threads
+            );
+        }
+    }
+}
+
+fn parse_address(name: &str, input: &str) -> Address {
+    parse_hex(name, input).try_into().unwrap_or_else(|_| {
+        eprintln!("error: {} is not a 20-byte address", name);
+        std::process::exit(1);
+    })
+}
+
+fn parse_hex(name: &str, input: &str) -> Vec<u8> {
+    hex::decode(input).unwrap_or_else(|| {
+        eprintln!("error: {} is not valid hex", name);
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,35 @@
+use evm_tool::asm;
+use evm_tool::hex;
+use std::io::Read;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Listing to assemble, read from stdin when omitted.
+    #[structopt(short = "i", long = "input", parse(from_os_str))]
+    input: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Opts::from_args();
+
+    let source = match args.input {
+        Some(path) => std::fs::read_to_string(path).unwrap(),
+        None => {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source).unwrap();
+            source
+        }
+    };
+
+    match asm::assemble(&source) {
+        Ok(code) => {
+            println!("0x{}", hex::encode(&code));
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
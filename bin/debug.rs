@@ -0,0 +1,392 @@
+use evm_tool::disasm::{Instruction, Opcode};
+use evm_tool::hardfork::Hardfork;
+use evm_tool::hex;
+use evm_tool::interpreter::{Env, Interpreter, Message, State};
+use evm_tool::u256::U256;
+use std::collections::{BTreeSet, HashSet};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Address the code runs at.
+const CONTRACT: [u8; 20] = [0x0c; 20];
+
+const HELP: &str = "\
+step [n]            execute one or n instructions (s)
+next                execute until the next instruction, stepping over internal calls (n)
+continue            run until a breakpoint or the end of execution (c)
+break [pc|opcode]   add a breakpoint on a hex offset or a mnemonic, or list them (b)
+delete <pc|opcode>  remove a breakpoint (d)
+list [n]            show n instructions around the current one (l)
+stack               show the stack, top first
+memory [off [len]]  hexdump memory, offset and length in hex (m)
+storage             show the storage of the contract
+returndata          show the return data of the last call and the output
+help                show this help (h)
+quit                exit (q)";
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Code to debug, as hex.
+    #[structopt(short = "i", long = "input")]
+    input: String,
+    /// Call data, as hex.
+    #[structopt(short = "d", long = "data", default_value = "")]
+    data: String,
+    /// Value sent with the call.
+    #[structopt(long = "value", default_value = "0")]
+    value: U256,
+    #[structopt(long = "gas", default_value = "30000000")]
+    gas: u64,
+    /// Hardfork whose rules are used, e.g. `london` or `cancun`.
+    #[structopt(long = "fork", default_value = "prague")]
+    fork: Hardfork,
+    /// Genesis-style `alloc` file with the accounts to start from.
+    #[structopt(long = "prestate", parse(from_os_str))]
+    prestate: Option<PathBuf>,
+}
+
+#[derive(Default)]
+struct Breakpoints {
+    pcs: BTreeSet<usize>,
+    opcodes: HashSet<Opcode>,
+}
+
+impl Breakpoints {
+    fn hit(&self, interpreter: &Interpreter) -> bool {
+        let frame = &interpreter.frame;
+        self.pcs.contains(&frame.pc) || self.opcodes.contains(&frame.opcode(interpreter.fork))
+    }
+}
+
+enum Breakpoint {
+    Pc(usize),
+    Opcode(Opcode),
+}
+
+impl std::str::FromStr for Breakpoint {
+    type Err = String;
+
+    /// Mnemonics take precedence, so `add` is an opcode and `0xadd` an offset.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(opcode) = s.parse::<Opcode>() {
+            return Ok(Breakpoint::Opcode(opcode));
+        }
+        parse_offset(s)
+            .map(Breakpoint::Pc)
+            .ok_or_else(|| format!("`{}` is neither an offset nor an opcode", s))
+    }
+}
+
+struct Debugger {
+    interpreter: Interpreter,
+    /// Disassembly of `code`, the code of the current frame.
+    listing: Vec<Instruction>,
+    code: Vec<u8>,
+    breakpoints: Breakpoints,
+}
+
+impl Debugger {
+    /// Handles one command line. Returns `false` when the debugger should exit.
+    fn command(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return true,
+        };
+        match command {
+            "step" | "s" => {
+                let count = match args.first().map(|arg| arg.parse::<usize>()) {
+                    None => 1,
+                    Some(Ok(count)) => count,
+                    Some(Err(_)) => {
+                        println!("step takes a number of instructions");
+                        return true;
+                    }
+                };
+                for _ in 0..count {
+                    if !self.step() {
+                        break;
+                    }
+                }
+                self.show_position();
+            }
+            "next" | "n" => {
+                self.next();
+                self.show_position();
+            }
+            "continue" | "c" => {
+                while self.step() {
+                    if self.breakpoints.hit(&self.interpreter) {
+                        println!("breakpoint at {:04x}", self.interpreter.frame.pc);
+                        break;
+                    }
+                }
+                self.show_position();
+            }
+            "break" | "b" => match args.first().map(|arg| arg.parse::<Breakpoint>()) {
+                None => self.show_breakpoints(),
+                Some(Ok(Breakpoint::Pc(pc))) => {
+                    self.breakpoints.pcs.insert(pc);
+                }
+                Some(Ok(Breakpoint::Opcode(opcode))) => {
+                    self.breakpoints.opcodes.insert(opcode);
+                }
+                Some(Err(err)) => println!("{}", err),
+            },
+            "delete" | "d" => match args.first().map(|arg| arg.parse::<Breakpoint>()) {
+                Some(Ok(Breakpoint::Pc(pc))) => {
+                    self.breakpoints.pcs.remove(&pc);
+                }
+                Some(Ok(Breakpoint::Opcode(opcode))) => {
+                    self.breakpoints.opcodes.remove(&opcode);
+                }
+                Some(Err(err)) => println!("{}", err),
+                None => println!("delete takes an offset or an opcode"),
+            },
+            "list" | "l" => {
+                let count = args.first().and_then(|arg| arg.parse().ok()).unwrap_or(9);
+                self.show_listing(count);
+            }
+            "stack" => self.show_stack(),
+            "memory" | "m" => {
+                let memory = self.interpreter.frame.memory.as_slice();
+                let offset = args.first().and_then(|arg| parse_offset(arg)).unwrap_or(0);
+                let len = args
+                    .get(1)
+                    .and_then(|arg| parse_offset(arg))
+                    .unwrap_or(memory.len().saturating_sub(offset));
+                let end = offset.saturating_add(len).min(memory.len());
+                hexdump(offset.min(end), &memory[offset.min(end)..end]);
+            }
+            "storage" => {
+                let address = self.interpreter.frame.message.address;
+                match self.interpreter.state.account(&address) {
+                    Some(account) if !account.storage.is_empty() => {
+                        for (key, value) in &account.storage {
+                            println!("{:#x}: {:#x}", key, value);
+                        }
+                    }
+                    _ => println!("storage is empty"),
+                }
+            }
+            "returndata" => {
+                println!(
+                    "return data: 0x{}",
+                    hex::encode(&self.interpreter.frame.return_data)
+                );
+                if let Some(outcome) = self.interpreter.outcome() {
+                    println!("output: 0x{}", hex::encode(&outcome.output));
+                }
+            }
+            "help" | "h" => println!("{}", HELP),
+            "quit" | "q" | "exit" => return false,
+            _ => println!("unknown command `{}`, try `help`", command),
+        }
+        true
+    }
+
+    /// Executes one instruction. Returns `false` once execution has halted.
+    fn step(&mut self) -> bool {
+        if let Some(outcome) = self.interpreter.step() {
+            println!("halted: {}, gas used {}", outcome.status, outcome.gas_used);
+            return false;
+        }
+        let message = &self.interpreter.frame.message;
+        if message.code != self.code {
+            self.code = message.code.clone();
+            self.listing = Opcode::disasm_for(&self.code, self.interpreter.fork);
+            println!(
+                "now in {:?} at depth {} of 0x{}",
+                message.kind,
+                message.depth,
+                hex::encode(&message.address)
+            );
+        }
+        true
+    }
+
+    /// Runs until the instruction after the current one is reached in the same or an
+    /// outer call, so that a `JUMP` into an internal function runs until it returns.
+    /// Other jumps never come back, so they are only stepped.
+    fn next(&mut self) {
+        let frame = &self.interpreter.frame;
+        let depth = frame.message.depth;
+        let target = match self.current() {
+            Some(instruction) => {
+                let target = instruction.next_pc();
+                // An internal call pushes its return address before the destination.
+                let arguments = match instruction.opcode {
+                    Opcode::JUMP => 1,
+                    Opcode::JUMPI => 2,
+                    _ => 0,
+                };
+                let stack = frame.stack.as_slice();
+                let below = &stack[..stack.len().saturating_sub(arguments)];
+                if arguments > 0 && !below.contains(&U256::from(target)) {
+                    self.step();
+                    return;
+                }
+                target
+            }
+            None => frame.pc + 1,
+        };
+        while self.step() {
+            let frame = &self.interpreter.frame;
+            if frame.message.depth <= depth && frame.pc == target {
+                break;
+            }
+            if self.breakpoints.hit(&self.interpreter) {
+                println!("breakpoint at {:04x}", frame.pc);
+                break;
+            }
+        }
+    }
+
+    fn current(&self) -> Option<&Instruction> {
+        let pc = self.interpreter.frame.pc;
+        self.listing
+            .binary_search_by_key(&pc, |instruction| instruction.pc)
+            .ok()
+            .map(|index| &self.listing[index])
+    }
+
+    fn show_position(&self) {
+        if self.interpreter.outcome().is_some() {
+            return;
+        }
+        let frame = &self.interpreter.frame;
+        match self.current() {
+            Some(instruction) => println!("=> {}", instruction),
+            None => println!("=> {:04x}: STOP (end of code)", frame.pc),
+        }
+        let top: Vec<String> = frame
+            .stack
+            .as_slice()
+            .iter()
+            .rev()
+            .take(4)
+            .map(|item| format!("{:#x}", item))
+            .collect();
+        println!(
+            "   gas {}, stack [{}{}]",
+            frame.gas_left,
+            top.join(", "),
+            if frame.stack.len() > 4 { ", .." } else { "" }
+        );
+    }
+
+    fn show_listing(&self, count: usize) {
+        let pc = self.interpreter.frame.pc;
+        let index = self
+            .listing
+            .partition_point(|instruction| instruction.pc < pc);
+        let start = index.saturating_sub(count / 2);
+        for instruction in self.listing.iter().skip(start).take(count) {
+            let marker = if instruction.pc == pc { "=>" } else { "  " };
+            let breakpoint = if self.breakpoints.pcs.contains(&instruction.pc) {
+                "*"
+            } else {
+                " "
+            };
+            println!("{}{} {}", marker, breakpoint, instruction);
+        }
+    }
+
+    fn show_stack(&self) {
+        let stack = self.interpreter.frame.stack.as_slice();
+        if stack.is_empty() {
+            println!("stack is empty");
+        }
+        for (depth, item) in stack.iter().rev().enumerate() {
+            println!("{:4}: {:#x}", depth, item);
+        }
+    }
+
+    fn show_breakpoints(&self) {
+        if self.breakpoints.pcs.is_empty() && self.breakpoints.opcodes.is_empty() {
+            println!("no breakpoints");
+        }
+        for pc in &self.breakpoints.pcs {
+            println!("{:04x}", pc);
+        }
+        let mut opcodes: Vec<String> = self
+            .breakpoints
+            .opcodes
+            .iter()
+            .map(|opcode| opcode.to_string())
+            .collect();
+        opcodes.sort();
+        for opcode in opcodes {
+            println!("{}", opcode);
+        }
+    }
+}
+
+fn main() {
+    let args = Opts::from_args();
+    let code = parse_hex("input", &args.input);
+    let data = parse_hex("data", &args.data);
+
+    let listing = Opcode::disasm_for(&code, args.fork);
+    let mut state = match &args.prestate {
+        Some(path) => State::load_alloc(path).unwrap_or_else(|err| {
+            eprintln!("error: {}: {}", path.display(), err);
+            std::process::exit(1);
+        }),
+        None => State::new(),
+    };
+    state.set_code(CONTRACT, code.clone());
+    let message = Message {
+        address: CONTRACT,
+        code_address: CONTRACT,
+        value: args.value,
+        data,
+        code: code.clone(),
+        gas: args.gas,
+        ..Message::default()
+    };
+    let mut debugger = Debugger {
+        interpreter: Interpreter::new(args.fork, Env::default(), state, message),
+        listing,
+        code,
+        breakpoints: Breakpoints::default(),
+    };
+    debugger.show_position();
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(debug) ");
+        std::io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        if !debugger.command(&line) {
+            break;
+        }
+    }
+}
+
+/// Hex offset, with or without `0x`.
+fn parse_offset(s: &str) -> Option<usize> {
+    usize::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+}
+
+fn hexdump(offset: usize, bytes: &[u8]) {
+    if bytes.is_empty() {
+        println!("memory is empty");
+    }
+    for (index, chunk) in bytes.chunks(32).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+        println!("{:04x}: {}", offset + index * 32, hex.join(" "));
+    }
+}
+
+fn parse_hex(name: &str, input: &str) -> Vec<u8> {
+    hex::decode(input).unwrap_or_else(|| {
+        eprintln!("error: {} is not valid hex", name);
+        std::process::exit(1);
+    })
+}
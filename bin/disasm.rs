@@ -1,25 +1,188 @@
-use evm_tool::disasm::Opcode;
+use evm_tool::cfg::Cfg;
+use evm_tool::creation;
+use evm_tool::disasm::{Instruction, Opcode};
+use evm_tool::hardfork::Hardfork;
+use evm_tool::hex;
+use evm_tool::metadata;
+use evm_tool::selectors;
+use evm_tool::signatures::SignatureDb;
+use std::path::PathBuf;
 use structopt::StructOpt;
 
+enum Format {
+    /// One instruction per line: `0004: PUSH1 0x80`.
+    Listing,
+    /// `Debug` representation of the decoded instructions.
+    Debug,
+    /// Control-flow graph in Graphviz DOT format.
+    Dot,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "listing" => Ok(Format::Listing),
+            "debug" => Ok(Format::Debug),
+            "dot" => Ok(Format::Dot),
+            _ => Err(format!("unknown format `{}`", s)),
+        }
+    }
+}
+
 #[derive(StructOpt)]
 struct Opts {
     #[structopt(short = "i", long = "input")]
     input: String,
+    /// Output format: `listing`, `debug` or `dot`.
+    #[structopt(short = "f", long = "format", default_value = "listing")]
+    format: Format,
+    /// Hardfork whose opcode table is used, e.g. `london` or `cancun`.
+    #[structopt(long = "fork", default_value = "prague")]
+    fork: Hardfork,
+    /// Treat the input as creation code and disassemble the constructor, the
+    /// deployed runtime code and the trailing data separately. Not available with the
+    /// dot format.
+    #[structopt(long = "creation")]
+    creation: bool,
+    /// List the function selectors found in the dispatcher instead of the code.
+    #[structopt(long = "selectors")]
+    selectors: bool,
+    /// Extra signature file used to annotate selectors and event topics, in addition
+    /// to the bundled database.
+    #[structopt(long = "signatures", parse(from_os_str))]
+    signatures: Option<PathBuf>,
 }
 
 fn main() {
     let args = Opts::from_args();
 
-    let mut input = args.input;
-    if input.starts_with("0x") {
-        input = input.replace("0x", "")
+    let bytes = parse_hex("input", &args.input);
+
+    let mut db = SignatureDb::bundled();
+    if let Some(path) = &args.signatures {
+        if let Err(err) = db.load(path) {
+            eprintln!("error: {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+
+    if args.selectors {
+        let runtime = match creation::split_for(&bytes, args.fork) {
+            Some(creation) if args.creation => creation.runtime,
+            _ => &bytes,
+        };
+        let (code, _) = metadata::split(runtime);
+        let instructions = Opcode::disasm_for(code, args.fork);
+        for (selector, pc) in selectors::selectors(&instructions) {
+            let names = db.function(selector);
+            if names.is_empty() {
+                println!("0x{} {:04x}", hex::encode(&selector), pc);
+            } else {
+                println!(
+                    "0x{} {:04x} {}",
+                    hex::encode(&selector),
+                    pc,
+                    names.join(" | ")
+                );
+            }
+        }
+        return;
+    }
+
+    if args.creation {
+        // A graph per section would not form a single valid DOT document.
+        if let Format::Dot = args.format {
+            eprintln!("error: --creation cannot be combined with the dot format");
+            std::process::exit(1);
+        }
+        // Section headers are listing comments; the debug format only gets the code.
+        let listing = matches!(args.format, Format::Listing);
+        match creation::split_for(&bytes, args.fork) {
+            Some(creation) => {
+                if listing {
+                    println!("; constructor");
+                }
+                print_code(creation.constructor, &args, &db);
+                if listing {
+                    println!(
+                        "; runtime, {} bytes at {:04x}",
+                        creation.runtime.len(),
+                        creation.runtime_offset
+                    );
+                }
+                print_code(creation.runtime, &args, &db);
+                if listing && !creation.data.is_empty() {
+                    let offset = creation.runtime_offset + creation.runtime.len();
+                    println!("; data, {} bytes", creation.data.len());
+                    println!("{:04x}: DATA 0x{}", offset, hex::encode(creation.data));
+                }
+                return;
+            }
+            None if listing => {
+                println!("; no deploy sequence found, disassembling as runtime code")
+            }
+            None => eprintln!("warning: no deploy sequence found, disassembling as runtime code"),
+        }
+    }
+    print_code(&bytes, &args, &db);
+}
+
+/// Prints one section of code. Offsets are relative to the start of the section.
+fn print_code(bytes: &[u8], args: &Opts, db: &SignatureDb) {
+    let (code, metadata) = metadata::split(bytes);
+    let instructions = Opcode::disasm_for(code, args.fork);
+    match args.format {
+        Format::Listing => {
+            print_listing(&instructions, db);
+            if let Some(metadata) = metadata {
+                println!("; metadata: {}", metadata);
+                println!(
+                    "{:04x}: DATA 0x{}",
+                    code.len(),
+                    hex::encode(&bytes[code.len()..])
+                );
+            }
+        }
+        Format::Debug => {
+            println!("{:?}", instructions);
+            if let Some(metadata) = metadata {
+                println!("{:?}", metadata);
+            }
+        }
+        Format::Dot => print!("{}", Cfg::from_instructions(instructions).to_dot()),
     }
+}
 
-    let mut bytes = Vec::new();
-    for i in 0..input.len() / 2 {
-        let byte = u8::from_str_radix(&input[i * 2..i * 2 + 2], 16).unwrap();
-        bytes.push(byte);
+fn print_listing(instructions: &[Instruction], db: &SignatureDb) {
+    for instruction in instructions {
+        let mut comments = Vec::new();
+        let signatures = db.lookup(&instruction.immediate);
+        if !signatures.is_empty() {
+            comments.push(signatures.join(" | "));
+        }
+        let hidden: Vec<String> = instruction
+            .push_data_jumpdests()
+            .map(|pc| format!("{:04x}", pc))
+            .collect();
+        if !hidden.is_empty() {
+            comments.push(format!(
+                "JUMPDEST byte at {} is push data, not a valid destination",
+                hidden.join(", ")
+            ));
+        }
+        if comments.is_empty() {
+            println!("{}", instruction);
+        } else {
+            println!("{} ; {}", instruction, comments.join("; "));
+        }
     }
+}
 
-    println!("{:?}", Opcode::disasm(bytes));
+fn parse_hex(name: &str, input: &str) -> Vec<u8> {
+    hex::decode(input).unwrap_or_else(|| {
+        eprintln!("error: {} is not valid hex", name);
+        std::process::exit(1);
+    })
 }
@@ -0,0 +1,61 @@
+use evm_tool::hex;
+use evm_tool::rlp::{self, Item};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// RLP encoded data, as hex.
+    #[structopt(short = "i", long = "input")]
+    input: String,
+}
+
+fn main() {
+    let args = Opts::from_args();
+    let bytes = parse_hex("input", &args.input);
+    match rlp::decode_item(&bytes) {
+        Ok(item) => print(&item, 0),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints one item per line, list elements indented below their list.
+fn print(item: &Item, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match item {
+        Item::List(items) => {
+            println!("{}list, {} items", indent, items.len());
+            for item in items {
+                print(item, depth + 1);
+            }
+        }
+        Item::Bytes(bytes) => {
+            let mut line = format!("{}0x{}", indent, hex::encode(bytes));
+            // Hints for what short strings and integers may hold.
+            if bytes.len() >= 2
+                && bytes
+                    .iter()
+                    .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+            {
+                line += &format!(" {:?}", String::from_utf8_lossy(bytes));
+            } else if (1..=8).contains(&bytes.len()) && bytes[0] != 0 {
+                let value = bytes
+                    .iter()
+                    .fold(0u64, |value, &byte| (value << 8) | byte as u64);
+                line += &format!(" ({})", value);
+            } else if bytes.len() > 8 {
+                line += &format!(" ({} bytes)", bytes.len());
+            }
+            println!("{}", line);
+        }
+    }
+}
+
+fn parse_hex(name: &str, input: &str) -> Vec<u8> {
+    hex::decode(input).unwrap_or_else(|| {
+        eprintln!("error: {} is not valid hex", name);
+        std::process::exit(1);
+    })
+}
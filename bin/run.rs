@@ -0,0 +1,170 @@
+use evm_tool::hardfork::Hardfork;
+use evm_tool::hex;
+use evm_tool::interpreter::{Env, Interpreter, JsonTracer, Message, State};
+use evm_tool::u256::U256;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Address the code runs at.
+const CONTRACT: [u8; 20] = [0x0c; 20];
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Code to execute, as hex.
+    #[structopt(short = "i", long = "input")]
+    input: String,
+    /// Call data, as hex.
+    #[structopt(short = "d", long = "data", default_value = "")]
+    data: String,
+    /// Value sent with the call.
+    #[structopt(long = "value", default_value = "0")]
+    value: U256,
+    #[structopt(long = "gas", default_value = "30000000")]
+    gas: u64,
+    /// Hardfork whose rules are used, e.g. `london` or `cancun`.
+    #[structopt(long = "fork", default_value = "prague")]
+    fork: Hardfork,
+    /// Print the gas spent per opcode and the most expensive program counters.
+    #[structopt(long = "gas-report")]
+    gas_report: bool,
+    /// Print an EIP-3155 JSON trace instead of the result.
+    #[structopt(long = "trace")]
+    trace: bool,
+    /// Write an EIP-3155 JSON trace to this file.
+    #[structopt(long = "trace-file", parse(from_os_str))]
+    trace_file: Option<PathBuf>,
+    /// Genesis-style `alloc` file with the accounts to start from.
+    #[structopt(long = "prestate", parse(from_os_str))]
+    prestate: Option<PathBuf>,
+    /// Write the accounts after execution to this file, in the `alloc` format.
+    #[structopt(long = "dump", parse(from_os_str))]
+    dump: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Opts::from_args();
+    let code = parse_hex("input", &args.input);
+    let data = parse_hex("data", &args.data);
+
+    let mut state = match &args.prestate {
+        Some(path) => State::load_alloc(path).unwrap_or_else(|err| {
+            eprintln!("error: {}: {}", path.display(), err);
+            std::process::exit(1);
+        }),
+        None => State::new(),
+    };
+    state.set_code(CONTRACT, code.clone());
+    let message = Message {
+        address: CONTRACT,
+        code_address: CONTRACT,
+        value: args.value,
+        data,
+        code,
+        gas: args.gas,
+        ..Message::default()
+    };
+    let mut interpreter = Interpreter::new(args.fork, Env::default(), state, message);
+    let outcome = match &args.trace_file {
+        _ if args.trace => {
+            let mut tracer = JsonTracer::new(std::io::stdout().lock());
+            let outcome = interpreter.run_with(&mut tracer);
+            if let Err(err) = tracer.finish() {
+                eprintln!("error: {}", err);
+                std::process::exit(1);
+            }
+            outcome
+        }
+        Some(path) => {
+            let mut tracer = match File::create(path) {
+                Ok(file) => JsonTracer::new(BufWriter::new(file)),
+                Err(err) => {
+                    eprintln!("error: {}: {}", path.display(), err);
+                    std::process::exit(1);
+                }
+            };
+            let outcome = interpreter.run_with(&mut tracer);
+            if let Err(err) = tracer.finish() {
+                eprintln!("error: {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+            outcome
+        }
+        None => interpreter.run(),
+    };
+    interpreter.state.finalize(args.fork);
+    if let Some(path) = &args.dump {
+        if let Err(err) = interpreter.state.dump_alloc(path) {
+            eprintln!("error: {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+    // The trace on stdout replaces the result.
+    if args.trace {
+        std::process::exit(if outcome.status.is_success() { 0 } else { 1 });
+    }
+
+    println!("status: {}", outcome.status);
+    println!("gas used: {}", outcome.gas_used);
+    if outcome.refund > 0 {
+        println!("refund: {}", outcome.refund);
+    }
+    println!("output: 0x{}", hex::encode(&outcome.output));
+    for log in &outcome.logs {
+        let topics: Vec<String> = log
+            .topics
+            .iter()
+            .map(|topic| format!("{:#x}", topic))
+            .collect();
+        println!("log [{}] 0x{}", topics.join(", "), hex::encode(&log.data));
+    }
+    for (address, account) in &interpreter.state.world().accounts {
+        for (key, value) in &account.storage {
+            println!(
+                "storage 0x{} {:#x}: {:#x}",
+                hex::encode(address),
+                key,
+                value
+            );
+        }
+    }
+    if args.gas_report {
+        let report = &interpreter.gas_report;
+        println!("gas by opcode:");
+        for (opcode, gas) in report.opcodes() {
+            println!(
+                "  {:<14} {:>6} {:>10}",
+                opcode.to_string(),
+                gas.count,
+                gas.gas
+            );
+        }
+        println!("gas by pc:");
+        let hotspots = report.hotspots(10);
+        // Only name the contract when more than one ran.
+        let single = hotspots.iter().all(|hotspot| hotspot.address == CONTRACT);
+        for hotspot in hotspots {
+            let location = match single {
+                true => format!("{:04x}", hotspot.pc),
+                false => format!("0x{}:{:04x}", hex::encode(&hotspot.address), hotspot.pc),
+            };
+            println!(
+                "  {} {:<14} {:>10}",
+                location,
+                hotspot.opcode.to_string(),
+                hotspot.gas
+            );
+        }
+    }
+    if !outcome.status.is_success() {
+        std::process::exit(1);
+    }
+}
+
+fn parse_hex(name: &str, input: &str) -> Vec<u8> {
+    hex::decode(input).unwrap_or_else(|| {
+        eprintln!("error: {} is not valid hex", name);
+        std::process::exit(1);
+    })
+}
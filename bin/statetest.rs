@@ -0,0 +1,90 @@
+use evm_tool::hardfork::Hardfork;
+use evm_tool::statetest::{self, Verdict};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// State test files, or directories searched for `.json` files.
+    #[structopt(parse(from_os_str), required = true)]
+    paths: Vec<PathBuf>,
+    /// Only run the expectations of this hardfork.
+    #[structopt(long = "fork")]
+    fork: Option<Hardfork>,
+    /// Print every case, not only failures.
+    #[structopt(short = "v", long = "verbose")]
+    verbose: bool,
+}
+
+fn main() {
+    let args = Opts::from_args();
+    let mut files = Vec::new();
+    for path in &args.paths {
+        if let Err(err) = collect(path, &mut files) {
+            eprintln!("error: {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    for file in &files {
+        let tests = match statetest::load(file) {
+            Ok(tests) => tests,
+            Err(err) => {
+                eprintln!("error: {}: {}", file.display(), err);
+                failed += 1;
+                continue;
+            }
+        };
+        for test in tests {
+            for result in test.run(args.fork) {
+                let case = format!("{} {} {}", result.test, result.fork, result.indexes);
+                match result.verdict {
+                    Verdict::Pass => {
+                        passed += 1;
+                        if args.verbose {
+                            println!("PASS {}", case);
+                        }
+                    }
+                    Verdict::Fail(reason) => {
+                        failed += 1;
+                        println!("FAIL {}: {}", case, reason);
+                    }
+                    Verdict::Skip(reason) => {
+                        skipped += 1;
+                        if args.verbose {
+                            println!("SKIP {}: {}", case, reason);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    println!("{} passed, {} failed, {} skipped", passed, failed, skipped);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Adds `path` if it is a file, or the `.json` files below it if it is a directory, in
+/// name order.
+fn collect(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir()
+            || entry
+                .extension()
+                .is_some_and(|extension| extension == "json")
+        {
+            collect(&entry, files)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use evm_tool::address;
+use evm_tool::creation;
+use evm_tool::disasm::Opcode;
+use evm_tool::hex;
+use evm_tool::tx::SignedTransaction;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Signed raw transaction, as hex.
+    #[structopt(short = "i", long = "input")]
+    input: String,
+}
+
+fn main() {
+    let args = Opts::from_args();
+    let bytes = parse_hex("input", &args.input);
+    let tx = SignedTransaction::decode(&bytes).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    });
+
+    println!("type: {}", tx.tx_type);
+    println!("hash: 0x{}", hex::encode(&tx.hash));
+    if let Some(chain_id) = tx.chain_id {
+        println!("chain id: {}", chain_id);
+    }
+    let sender = tx.sender();
+    match &sender {
+        Ok(sender) => println!("from: {}", address::to_checksum(sender)),
+        Err(err) => println!("from: {}", err),
+    }
+    match (&tx.to, &sender) {
+        (Some(to), _) => println!("to: {}", address::to_checksum(to)),
+        (None, Ok(sender)) => println!(
+            "to: none, creates {}",
+            address::to_checksum(&address::create_address(sender, tx.nonce))
+        ),
+        (None, Err(_)) => println!("to: none, contract creation"),
+    }
+    println!("nonce: {}", tx.nonce);
+    println!("value: {}", tx.value);
+    println!("gas limit: {}", tx.gas_limit);
+    let fees = [
+        ("gas price", tx.gas_price),
+        ("max fee per gas", tx.max_fee_per_gas),
+        ("max priority fee per gas", tx.max_priority_fee_per_gas),
+        ("max fee per blob gas", tx.max_fee_per_blob_gas),
+    ];
+    for (name, fee) in fees {
+        if let Some(fee) = fee {
+            println!("{}: {}", name, fee);
+        }
+    }
+    for (address, keys) in &tx.access_list {
+        println!("access list: {}", address::to_checksum(address));
+        for key in keys {
+            println!("  {:#066x}", key);
+        }
+    }
+    for hash in &tx.blob_hashes {
+        println!("blob hash: {:#066x}", hash);
+    }
+    for authorization in &tx.authorizations {
+        let authority = match authorization.authority() {
+            Ok(authority) => address::to_checksum(&authority),
+            Err(err) => err.to_string(),
+        };
+        println!(
+            "authorization: {} delegates to {}, chain id {}, nonce {}",
+            authority,
+            address::to_checksum(&authorization.address),
+            authorization.chain_id,
+            authorization.nonce
+        );
+    }
+    println!(
+        "signature: y parity {}, r {:#066x}, s {:#066x}",
+        tx.signature.y_parity as u8, tx.signature.r, tx.signature.s
+    );
+    println!("data: {} bytes", tx.data.len());
+
+    if !tx.is_create() {
+        if !tx.data.is_empty() {
+            println!("0x{}", hex::encode(&tx.data));
+        }
+        return;
+    }
+    match creation::split(&tx.data) {
+        Some(creation) => {
+            println!("; constructor");
+            print_code(creation.constructor);
+            println!(
+                "; runtime, {} bytes at {:04x}",
+                creation.runtime.len(),
+                creation.runtime_offset
+            );
+            print_code(creation.runtime);
+            if !creation.data.is_empty() {
+                println!("; data, {} bytes", creation.data.len());
+                println!("0x{}", hex::encode(creation.data));
+            }
+        }
+        None => print_code(&tx.data),
+    }
+}
+
+fn print_code(code: &[u8]) {
+    for instruction in Opcode::disasm(code) {
+        println!("{}", instruction);
+    }
+}
+
+fn parse_hex(name: &str, input: &str) -> Vec<u8> {
+    hex::decode(input).unwrap_or_else(|| {
+        eprintln!("error: {} is not valid hex", name);
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,266 @@
+//! Addresses of accounts controlled by keys and of contracts created with `CREATE`
+//! and `CREATE2`, and a search for `CREATE2` salts that give an address with a
+//! chosen prefix.
+
+use crate::hex;
+use crate::interpreter::Address;
+use crate::keccak::keccak256;
+use crate::rlp;
+use crate::u256::U256;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Address of a contract created with `CREATE` by `sender` when its nonce was `nonce`:
+/// the last 20 bytes of the hash of the RLP list of both.
+pub fn create_address(sender: &Address, nonce: u64) -> Address {
+    last_20_bytes(&keccak256(&rlp::encode(&(sender, nonce))))
+}
+
+/// Address of a contract created with `CREATE2` (EIP-1014).
+pub fn create2_address(sender: &Address, salt: U256, init_code: &[u8]) -> Address {
+    create2_address_from_hash(sender, salt, &keccak256(init_code))
+}
+
+/// Address of a contract created with `CREATE2`, given the hash of its init code.
+pub fn create2_address_from_hash(
+    sender: &Address,
+    salt: U256,
+    init_code_hash: &[u8; 32],
+) -> Address {
+    last_20_bytes(&keccak256(&create2_preimage(sender, salt, init_code_hash)))
+}
+
+/// `0xff ++ sender ++ salt ++ init_code_hash`.
+fn create2_preimage(sender: &Address, salt: U256, init_code_hash: &[u8; 32]) -> [u8; 85] {
+    let mut preimage = [0; 85];
+    preimage[0] = 0xff;
+    preimage[1..21].copy_from_slice(sender);
+    preimage[21..53].copy_from_slice(&salt.to_be_bytes());
+    preimage[53..].copy_from_slice(init_code_hash);
+    preimage
+}
+
+/// Address of an account controlled by a secp256k1 public key: the last 20 bytes of
+/// the hash of the uncompressed point, without its `0x04` tag.
+#[cfg(feature = "crypto")]
+pub fn from_public_key(key: &k256::ecdsa::VerifyingKey) -> Address {
+    let point = key.to_encoded_point(false);
+    last_20_bytes(&keccak256(&point.as_bytes()[1..]))
+}
+
+/// Address of the account controlled by a secret key, `None` if the key is zero or
+/// not below the curve order.
+#[cfg(feature = "crypto")]
+pub fn from_secret_key(key: &[u8; 32]) -> Option<Address> {
+    let key = k256::ecdsa::SigningKey::from_slice(key).ok()?;
+    Some(from_public_key(key.verifying_key()))
+}
+
+fn last_20_bytes(hash: &[u8; 32]) -> Address {
+    let mut address = [0; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+/// Address in the mixed-case checksum encoding of EIP-55, with `0x`.
+pub fn to_checksum(address: &Address) -> String {
+    let hex = hex::encode(address);
+    let hash = keccak256(hex.as_bytes());
+    let digits: String = hex
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", digits)
+}
+
+/// Hex digits an address must start with. Odd lengths are allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prefix {
+    nibbles: Vec<u8>,
+}
+
+impl Prefix {
+    pub fn matches(&self, address: &Address) -> bool {
+        self.nibbles.iter().enumerate().all(|(i, &nibble)| {
+            let byte = address[i / 2];
+            let actual = if i % 2 == 0 { byte >> 4 } else { byte & 0xf };
+            actual == nibble
+        })
+    }
+
+    /// Number of hex digits.
+    pub fn len(&self) -> usize {
+        self.nibbles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nibbles.is_empty()
+    }
+}
+
+impl std::str::FromStr for Prefix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        if digits.len() > 40 {
+            return Err(format!("prefix `{}` is longer than an address", s));
+        }
+        let nibbles = digits
+            .chars()
+            .map(|c| c.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("prefix `{}` is not hex", s))?;
+        Ok(Prefix { nibbles })
+    }
+}
+
+/// Salt found by [`mine_create2`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mined {
+    pub salt: U256,
+    pub address: Address,
+    /// Salts tried by all threads together.
+    pub attempts: u64,
+}
+
+/// Searches for a `CREATE2` salt whose address starts with `prefix`, trying `start`,
+/// `start + 1` and so on across `threads` threads. Each extra hex digit makes the
+/// search 16 times longer on average, and it does not stop until a salt is found.
+pub fn mine_create2(
+    sender: &Address,
+    init_code_hash: &[u8; 32],
+    prefix: &Prefix,
+    start: U256,
+    threads: usize,
+) -> Mined {
+    let threads = threads.max(1);
+    let found = AtomicBool::new(false);
+    let attempts = AtomicU64::new(0);
+    let result = Mutex::new(None);
+    std::thread::scope(|scope| {
+        for thread in 0..threads {
+            let (found, attempts, result) = (&found, &attempts, &result);
+            scope.spawn(move || {
+                let step = U256::from(threads as u64);
+                let mut salt = start + U256::from(thread as u64);
+                let mut preimage = create2_preimage(sender, salt, init_code_hash);
+                let mut tried = 0;
+                while !found.load(Ordering::Relaxed) {
+                    preimage[21..53].copy_from_slice(&salt.to_be_bytes());
+                    let address = last_20_bytes(&keccak256(&preimage));
+                    tried += 1;
+                    if prefix.matches(&address) {
+                        found.store(true, Ordering::Relaxed);
+                        result.lock().unwrap().get_or_insert((salt, address));
+                        break;
+                    }
+                    salt = salt + step;
+                }
+                attempts.fetch_add(tried, Ordering::Relaxed);
+            });
+        }
+    });
+    let (salt, address) = result.into_inner().unwrap().expect("a thread found a salt");
+    Mined {
+        salt,
+        address,
+        attempts: attempts.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn create_addresses() {
+        let sender = hex!("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        assert_eq!(
+            create_address(&sender, 0),
+            hex!("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d")
+        );
+        assert_eq!(
+            create_address(&sender, 1),
+            hex!("343c43a37d37dff08ae8c4a11544c718abb4fcf8")
+        );
+        assert_eq!(
+            create2_address(&[0; 20], U256::ZERO, &[0]),
+            hex!("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38")
+        );
+        assert_eq!(
+            create2_address(
+                &hex!("00000000000000000000000000000000deadbeef"),
+                U256::from(0xcafebabeu64),
+                &hex!("deadbeef")
+            ),
+            hex!("60f3f640a8508fc6a86d45df051962668e1e8ac7")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn secret_keys() {
+        assert_eq!(
+            from_secret_key(&hex!(
+                "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8"
+            )),
+            Some(hex!("a94f5374fce5edbc8e2a8697c15331677e6ebf0b"))
+        );
+        assert_eq!(
+            from_secret_key(&[0x46; 32]),
+            Some(hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"))
+        );
+        assert_eq!(from_secret_key(&[0; 32]), None);
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(
+            to_checksum(&hex!("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed")),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        assert_eq!(
+            to_checksum(&hex!("fb6916095ca1df60bb79ce92ce3ea74c37c5d359")),
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"
+        );
+    }
+
+    #[test]
+    fn prefixes() {
+        let prefix: Prefix = "0xab1".parse().unwrap();
+        assert_eq!(prefix.len(), 3);
+        assert!(prefix.matches(&hex!("ab1fffffffffffffffffffffffffffffffffffff")));
+        assert!(!prefix.matches(&hex!("ab2fffffffffffffffffffffffffffffffffffff")));
+        assert!("".parse::<Prefix>().unwrap().matches(&[0; 20]));
+        assert!("xyz".parse::<Prefix>().is_err());
+        assert!("0".repeat(41).parse::<Prefix>().is_err());
+    }
+
+    #[test]
+    fn mining() {
+        let sender = [0x11; 20];
+        let hash = keccak256(&[0]);
+        let prefix: Prefix = "00".parse().unwrap();
+        let mined = mine_create2(&sender, &hash, &prefix, U256::ZERO, 4);
+        assert!(prefix.matches(&mined.address));
+        assert_eq!(
+            create2_address_from_hash(&sender, mined.salt, &hash),
+            mined.address
+        );
+        assert!(mined.attempts > 0);
+
+        // A single thread finds the first matching salt.
+        let first = mine_create2(&sender, &hash, &prefix, U256::ZERO, 1);
+        assert_eq!(first.attempts, first.salt.to_u64().unwrap() + 1);
+    }
+}
@@ -0,0 +1,480 @@
+use crate::disasm::Opcode;
+use std::collections::HashMap;
+
+/// Error produced while assembling a listing. Line numbers start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    UnknownMnemonic { line: usize, mnemonic: String },
+    InvalidOperand { line: usize, operand: String },
+    MissingOperand { line: usize },
+    UnexpectedOperand { line: usize },
+    OperandTooLarge { line: usize },
+    DuplicateLabel { line: usize, label: String },
+    UndefinedLabel { line: usize, label: String },
+}
+
+impl std::fmt::Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic { line, mnemonic } => {
+                write!(f, "line {}: unknown mnemonic `{}`", line, mnemonic)
+            }
+            AsmError::InvalidOperand { line, operand } => {
+                write!(f, "line {}: invalid operand `{}`", line, operand)
+            }
+            AsmError::MissingOperand { line } => write!(f, "line {}: missing operand", line),
+            AsmError::UnexpectedOperand { line } => {
+                write!(f, "line {}: opcode takes no operand", line)
+            }
+            AsmError::OperandTooLarge { line } => {
+                write!(f, "line {}: operand does not fit the push width", line)
+            }
+            AsmError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label `@{}` defined twice", line, label)
+            }
+            AsmError::UndefinedLabel { line, label } => {
+                write!(f, "line {}: undefined label `@{}`", line, label)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+#[derive(Debug)]
+enum Operand {
+    Bytes(Vec<u8>),
+    Label(String),
+}
+
+#[derive(Debug)]
+enum Item {
+    Label {
+        line: usize,
+        name: String,
+    },
+    Op(Opcode),
+    /// Raw bytes copied into the output, e.g. a metadata trailer.
+    Data(Vec<u8>),
+    /// `PUSH` or `PUSHn`; `width` is `None` when the assembler picks it.
+    Push {
+        line: usize,
+        width: Option<usize>,
+        operand: Operand,
+        truncated: bool,
+    },
+}
+
+/// Assembles a mnemonic listing into bytecode.
+///
+/// The input is the format printed by the disassembler, one instruction per line.
+/// The leading `0004:` offsets are optional and ignored. Everything after `;` is a
+/// comment. `@name:` defines a label at the next instruction and `PUSH @name` pushes
+/// its offset using the smallest width that fits. `PUSH 0x..` without a width also
+/// picks the smallest width, `PUSHn` left-pads the operand to `n` bytes. `DATA 0x..`
+/// emits its operand verbatim.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let items = parse(source)?;
+    let labels = layout(&items)?;
+
+    let mut code = Vec::new();
+    for item in &items {
+        match item {
+            Item::Label { .. } => {}
+            Item::Op(opcode) => code.push(opcode.code()),
+            Item::Data(bytes) => code.extend(bytes),
+            Item::Push {
+                width,
+                operand,
+                truncated,
+                ..
+            } => {
+                let bytes = match operand {
+                    Operand::Bytes(bytes) => bytes.clone(),
+                    Operand::Label(label) => minimal_bytes(labels[label]),
+                };
+                let width = push_width(*width, &bytes);
+                code.push(Opcode::push(width).unwrap().code());
+                if !*truncated {
+                    code.extend(std::iter::repeat_n(0, width - bytes.len()));
+                }
+                code.extend(bytes);
+            }
+        }
+    }
+    Ok(code)
+}
+
+fn parse(source: &str) -> Result<Vec<Item>, AsmError> {
+    let mut items = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.split(';').next().unwrap_or_default();
+        let mut tokens: Vec<&str> = line.split_whitespace().collect();
+
+        // Offsets printed by the disassembler, e.g. `0004:`.
+        if let Some(offset) = tokens.first().and_then(|token| token.strip_suffix(':')) {
+            if !offset.is_empty() && offset.chars().all(|c| c.is_ascii_hexdigit()) {
+                tokens.remove(0);
+            }
+        }
+
+        let truncated = tokens.last() == Some(&"(truncated)");
+        if truncated {
+            tokens.pop();
+        }
+
+        let (mnemonic, operand) = match tokens.as_slice() {
+            [] => continue,
+            [mnemonic] => (*mnemonic, None),
+            [mnemonic, operand] => (*mnemonic, Some(*operand)),
+            [_, _, extra, ..] => {
+                return Err(AsmError::InvalidOperand {
+                    line: line_no,
+                    operand: extra.to_string(),
+                })
+            }
+        };
+
+        if let Some(label) = mnemonic
+            .strip_prefix('@')
+            .and_then(|label| label.strip_suffix(':'))
+        {
+            if operand.is_some() {
+                return Err(AsmError::UnexpectedOperand { line: line_no });
+            }
+            items.push(Item::Label {
+                line: line_no,
+                name: label.to_string(),
+            });
+            continue;
+        }
+
+        if mnemonic.eq_ignore_ascii_case("DATA") {
+            let operand = operand.ok_or(AsmError::MissingOperand { line: line_no })?;
+            match parse_operand(operand) {
+                Some(Operand::Bytes(bytes)) if operand.starts_with("0x") => {
+                    items.push(Item::Data(bytes))
+                }
+                _ => {
+                    return Err(AsmError::InvalidOperand {
+                        line: line_no,
+                        operand: operand.to_string(),
+                    })
+                }
+            }
+            continue;
+        }
+
+        let width = if mnemonic.eq_ignore_ascii_case("PUSH") {
+            None
+        } else {
+            let opcode: Opcode = mnemonic.parse().map_err(|_| AsmError::UnknownMnemonic {
+                line: line_no,
+                mnemonic: mnemonic.to_string(),
+            })?;
+            match opcode.immediate_size() {
+                0 => {
+                    if operand.is_some() {
+                        return Err(AsmError::UnexpectedOperand { line: line_no });
+                    }
+                    items.push(Item::Op(opcode));
+                    continue;
+                }
+                size => Some(size),
+            }
+        };
+
+        let operand = operand.ok_or(AsmError::MissingOperand { line: line_no })?;
+        let operand = match parse_operand(operand) {
+            Some(Operand::Bytes(bytes)) if !truncated && !bytes.is_empty() => {
+                Operand::Bytes(strip_zeroes(&bytes))
+            }
+            Some(operand) => operand,
+            None => {
+                return Err(AsmError::InvalidOperand {
+                    line: line_no,
+                    operand: operand.to_string(),
+                })
+            }
+        };
+        // Only a disassembled push cut short by the end of the code may have
+        // fewer bytes than its width without being padded.
+        let valid = match (&operand, truncated) {
+            (Operand::Bytes(bytes), false) => !bytes.is_empty(),
+            (Operand::Bytes(_), true) => width.is_some(),
+            (Operand::Label(_), truncated) => !truncated,
+        };
+        if !valid {
+            return Err(AsmError::InvalidOperand {
+                line: line_no,
+                operand: tokens[1].to_string(),
+            });
+        }
+        if let (Some(width), Operand::Bytes(bytes)) = (width, &operand) {
+            if bytes.len() > width {
+                return Err(AsmError::OperandTooLarge { line: line_no });
+            }
+        }
+        items.push(Item::Push {
+            line: line_no,
+            width,
+            operand,
+            truncated,
+        });
+    }
+    Ok(items)
+}
+
+fn parse_operand(operand: &str) -> Option<Operand> {
+    if let Some(label) = operand.strip_prefix('@') {
+        return Some(Operand::Label(label.to_string()));
+    }
+    if let Some(hex) = operand.strip_prefix("0x") {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let hex = if hex.len() % 2 == 1 {
+            format!("0{}", hex)
+        } else {
+            hex.to_string()
+        };
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        return Some(Operand::Bytes(bytes));
+    }
+    let value: u128 = operand.parse().ok()?;
+    Some(Operand::Bytes(strip_zeroes(&value.to_be_bytes())))
+}
+
+/// `bytes` without leading zeroes, at least one byte long.
+fn strip_zeroes(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(bytes.len() - 1);
+    bytes[start..].to_vec()
+}
+
+fn minimal_bytes(offset: usize) -> Vec<u8> {
+    strip_zeroes(&offset.to_be_bytes())
+}
+
+/// Width of a push of `bytes` when the listing asked for `width`.
+fn push_width(width: Option<usize>, bytes: &[u8]) -> usize {
+    match width {
+        Some(width) => width,
+        None => strip_zeroes(bytes).len(),
+    }
+}
+
+/// Resolves label offsets.
+///
+/// Label pushes start one byte wide and only ever grow, so the loop reaches a fixed
+/// point after at most a few passes.
+fn layout(items: &[Item]) -> Result<HashMap<String, usize>, AsmError> {
+    let mut widths: Vec<usize> = items
+        .iter()
+        .map(|item| match item {
+            Item::Push {
+                width: Some(width),
+                truncated: false,
+                ..
+            } => *width,
+            Item::Push {
+                operand: Operand::Bytes(bytes),
+                width,
+                ..
+            } => width.unwrap_or_else(|| push_width(None, bytes)),
+            _ => 1,
+        })
+        .collect();
+
+    loop {
+        let mut labels = HashMap::new();
+        let mut pc = 0;
+        for (item, width) in items.iter().zip(&widths) {
+            match item {
+                Item::Label { line, name } => {
+                    if labels.insert(name.clone(), pc).is_some() {
+                        return Err(AsmError::DuplicateLabel {
+                            line: *line,
+                            label: name.clone(),
+                        });
+                    }
+                }
+                Item::Op(_) => pc += 1,
+                Item::Data(bytes) => pc += bytes.len(),
+                Item::Push {
+                    operand: Operand::Bytes(bytes),
+                    truncated: true,
+                    ..
+                } => pc += 1 + bytes.len(),
+                Item::Push { .. } => pc += 1 + width,
+            }
+        }
+
+        let mut changed = false;
+        for (item, width) in items.iter().zip(widths.iter_mut()) {
+            if let Item::Push {
+                line,
+                width: fixed,
+                operand: Operand::Label(label),
+                ..
+            } = item
+            {
+                let offset = *labels.get(label).ok_or_else(|| AsmError::UndefinedLabel {
+                    line: *line,
+                    label: label.clone(),
+                })?;
+                let needed = minimal_bytes(offset).len();
+                match fixed {
+                    Some(fixed) if needed > *fixed => {
+                        return Err(AsmError::OperandTooLarge { line: *line })
+                    }
+                    Some(_) => {}
+                    None if needed > *width => {
+                        *width = needed;
+                        changed = true;
+                    }
+                    None => {}
+                }
+            }
+        }
+        if !changed {
+            return Ok(labels);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn assemble_mnemonics() {
+        let source = "
+            PUSH1 0x80
+            PUSH1 0x40
+            MSTORE ; free memory pointer
+            PUSH 0x0004
+            PUSH2 0x1
+            STOP
+        ";
+        assert_eq!(
+            assemble(source).unwrap(),
+            hex!("6080604052600461000100").to_vec()
+        );
+    }
+
+    #[test]
+    fn assemble_labels() {
+        let source = "
+            PUSH @end
+            JUMP
+            @loop:
+            JUMPDEST
+            PUSH @loop
+            JUMP
+            @end:
+            JUMPDEST
+            STOP
+        ";
+        assert_eq!(
+            assemble(source).unwrap(),
+            hex!("6007565b6003565b00").to_vec()
+        );
+    }
+
+    #[test]
+    fn assemble_grows_label_pushes() {
+        let mut source = String::from("PUSH @end\nJUMP\n");
+        for _ in 0..300 {
+            source.push_str("STOP\n");
+        }
+        source.push_str("@end:\nJUMPDEST\n");
+        let code = assemble(&source).unwrap();
+        assert_eq!(&code[..4], &hex!("61013056"));
+        assert_eq!(code[0x130], 0x5b);
+    }
+
+    #[test]
+    fn disasm_round_trip() {
+        let code = hex!("6080604052348015600f57600080fd5b50603f80601d6000396000f3fe0c61ff");
+        let listing: String = Opcode::disasm(&code)
+            .iter()
+            .map(|instruction| format!("{}\n", instruction))
+            .collect();
+        assert_eq!(assemble(&listing).unwrap(), code.to_vec());
+
+        let code = hex!("0062");
+        let listing: String = Opcode::disasm(&code)
+            .iter()
+            .map(|instruction| format!("{}\n", instruction))
+            .collect();
+        assert_eq!(assemble(&listing).unwrap(), code.to_vec());
+    }
+
+    #[test]
+    fn assemble_errors() {
+        assert_eq!(
+            assemble("NOPE"),
+            Err(AsmError::UnknownMnemonic {
+                line: 1,
+                mnemonic: "NOPE".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("STOP\nPUSH @missing"),
+            Err(AsmError::UndefinedLabel {
+                line: 2,
+                label: "missing".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("PUSH1 0x0102"),
+            Err(AsmError::OperandTooLarge { line: 1 })
+        );
+        assert_eq!(assemble("PUSH1"), Err(AsmError::MissingOperand { line: 1 }));
+        assert_eq!(
+            assemble("PUSH 0x01 (truncated)"),
+            Err(AsmError::InvalidOperand {
+                line: 1,
+                operand: "0x01".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("@a:\n@a:"),
+            Err(AsmError::DuplicateLabel {
+                line: 2,
+                label: "a".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("ADD 0x01"),
+            Err(AsmError::UnexpectedOperand { line: 1 })
+        );
+    }
+
+    #[test]
+    fn assemble_data() {
+        let source = "
+            PUSH @tail
+            STOP
+            DATA 0x00ff
+            @tail:
+            DATA 0x
+        ";
+        assert_eq!(assemble(source).unwrap(), hex!("60050000ff").to_vec());
+        assert_eq!(
+            assemble("DATA 12"),
+            Err(AsmError::InvalidOperand {
+                line: 1,
+                operand: "12".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use crate::disasm::{Instruction, Opcode};
+use crate::jumptable::JumpTable;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Straight-line run of instructions with a single entry and a single exit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// Offset of the first instruction.
+    pub start: usize,
+    pub instructions: Vec<Instruction>,
+}
+
+impl BasicBlock {
+    /// Offset just past the last instruction.
+    pub fn end(&self) -> usize {
+        self.instructions
+            .last()
+            .map(|instruction| instruction.next_pc())
+            .unwrap_or(self.start)
+    }
+
+    pub fn last(&self) -> &Instruction {
+        self.instructions
+            .last()
+            .expect("basic blocks are never empty")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Target of a `JUMP`.
+    Jump,
+    /// Target of a `JUMPI` when the condition is non-zero.
+    BranchTaken,
+    /// Instruction after a `JUMPI` when the condition is zero.
+    BranchNotTaken,
+    /// Execution runs into the next block, which starts with a `JUMPDEST`.
+    Fallthrough,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// Static jump whose target is not a `JUMPDEST`. Executing it halts with an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidJump {
+    /// Offset of the `JUMP`/`JUMPI`.
+    pub pc: usize,
+    /// Pushed target, `None` when it does not even fit into `usize`.
+    pub target: Option<usize>,
+}
+
+/// Control-flow graph recovered from bytecode.
+#[derive(Debug, Clone, Default)]
+pub struct Cfg {
+    /// Basic blocks keyed by their start offset.
+    pub blocks: BTreeMap<usize, BasicBlock>,
+    pub edges: Vec<Edge>,
+    /// Offsets of `JUMP`/`JUMPI` instructions whose target is computed at runtime.
+    pub unresolved: Vec<usize>,
+    pub invalid_jumps: Vec<InvalidJump>,
+}
+
+impl Cfg {
+    pub fn new(code: &[u8]) -> Cfg {
+        Self::from_instructions(Opcode::disasm(code))
+    }
+
+    /// Splits `instructions` into basic blocks and resolves `PUSH`-then-jump targets.
+    pub fn from_instructions(instructions: Vec<Instruction>) -> Cfg {
+        let mut cfg = Cfg::default();
+        let code: Vec<u8> = instructions
+            .iter()
+            .flat_map(|instruction| {
+                std::iter::once(instruction.opcode.code()).chain(instruction.immediate.clone())
+            })
+            .collect();
+        let jump_table = JumpTable::new(&code);
+
+        let mut current: Vec<Instruction> = Vec::new();
+        for instruction in instructions {
+            if instruction.opcode == Opcode::JUMPDEST && !current.is_empty() {
+                cfg.push_block(std::mem::take(&mut current));
+            }
+            let ends_block =
+                instruction.opcode == Opcode::JUMPI || instruction.opcode.is_terminator();
+            current.push(instruction);
+            if ends_block {
+                cfg.push_block(std::mem::take(&mut current));
+            }
+        }
+        if !current.is_empty() {
+            cfg.push_block(current);
+        }
+
+        let mut edges = Vec::new();
+        for block in cfg.blocks.values() {
+            let last = block.last();
+            match last.opcode {
+                Opcode::JUMP | Opcode::JUMPI => {
+                    let kind = if last.opcode == Opcode::JUMP {
+                        EdgeKind::Jump
+                    } else {
+                        EdgeKind::BranchTaken
+                    };
+                    match static_target(block) {
+                        Some(Some(target)) if jump_table.is_valid(target) => edges.push(Edge {
+                            from: block.start,
+                            to: target,
+                            kind,
+                        }),
+                        Some(target) => cfg.invalid_jumps.push(InvalidJump {
+                            pc: last.pc,
+                            target,
+                        }),
+                        None => cfg.unresolved.push(last.pc),
+                    }
+                    if last.opcode == Opcode::JUMPI && cfg.blocks.contains_key(&block.end()) {
+                        edges.push(Edge {
+                            from: block.start,
+                            to: block.end(),
+                            kind: EdgeKind::BranchNotTaken,
+                        });
+                    }
+                }
+                opcode if opcode.is_terminator() => {}
+                _ => {
+                    if cfg.blocks.contains_key(&block.end()) {
+                        edges.push(Edge {
+                            from: block.start,
+                            to: block.end(),
+                            kind: EdgeKind::Fallthrough,
+                        });
+                    }
+                }
+            }
+        }
+        cfg.edges = edges;
+        cfg
+    }
+
+    fn push_block(&mut self, instructions: Vec<Instruction>) {
+        let start = instructions[0].pc;
+        self.blocks.insert(
+            start,
+            BasicBlock {
+                start,
+                instructions,
+            },
+        );
+    }
+
+    /// Edges leaving the block starting at `start`.
+    pub fn successors(&self, start: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.from == start)
+    }
+
+    /// Edges entering the block starting at `start`.
+    pub fn predecessors(&self, start: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.to == start)
+    }
+
+    /// Start offsets of the blocks reachable from offset 0 through resolved edges.
+    ///
+    /// Blocks only entered through unresolved jumps are missing from the result, so
+    /// it is exact only when `unresolved` is empty.
+    pub fn reachable(&self) -> BTreeSet<usize> {
+        let mut reachable = BTreeSet::new();
+        let mut queue = Vec::new();
+        if self.blocks.contains_key(&0) {
+            queue.push(0);
+        }
+        while let Some(start) = queue.pop() {
+            if reachable.insert(start) {
+                queue.extend(self.successors(start).map(|edge| edge.to));
+            }
+        }
+        reachable
+    }
+
+    /// Renders the graph in Graphviz DOT format.
+    ///
+    /// `JUMPI` edges are green when taken and red otherwise. Blocks that are not
+    /// reachable through resolved edges are greyed out and unresolved jumps point to a
+    /// shared `?` node.
+    pub fn to_dot(&self) -> String {
+        let reachable = self.reachable();
+        let mut dot = String::from("digraph cfg {\n");
+        dot.push_str("    node [shape=box fontname=\"monospace\"];\n");
+        for block in self.blocks.values() {
+            let label: String = block
+                .instructions
+                .iter()
+                .map(|instruction| format!("{}\\l", instruction))
+                .collect();
+            let style = if reachable.contains(&block.start) {
+                ""
+            } else {
+                " style=filled fillcolor=lightgrey"
+            };
+            dot.push_str(&format!(
+                "    block_{:04x} [label=\"{}\"{}];\n",
+                block.start, label, style
+            ));
+        }
+        for edge in &self.edges {
+            let attributes = match edge.kind {
+                EdgeKind::Jump => "label=\"jump\"",
+                EdgeKind::BranchTaken => "label=\"true\" color=green",
+                EdgeKind::BranchNotTaken => "label=\"false\" color=red",
+                EdgeKind::Fallthrough => "label=\"fallthrough\" style=dashed",
+            };
+            dot.push_str(&format!(
+                "    block_{:04x} -> block_{:04x} [{}];\n",
+                edge.from, edge.to, attributes
+            ));
+        }
+        if !self.unresolved.is_empty() {
+            dot.push_str("    unresolved [label=\"?\" shape=circle];\n");
+            for &pc in &self.unresolved {
+                if let Some(block) = self.blocks.values().find(|block| block.last().pc == pc) {
+                    dot.push_str(&format!(
+                        "    block_{:04x} -> unresolved [label=\"dynamic\" style=dotted];\n",
+                        block.start
+                    ));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Target of the jump ending `block` when it is pushed right before the jump.
+///
+/// Returns `None` for dynamic targets and `Some(None)` when the pushed value does not
+/// fit into `usize`.
+fn static_target(block: &BasicBlock) -> Option<Option<usize>> {
+    let len = block.instructions.len();
+    if len < 2 {
+        return None;
+    }
+    let push = &block.instructions[len - 2];
+    if push.opcode.immediate_size() == 0 && push.opcode != Opcode::PUSH0 {
+        return None;
+    }
+    let mut value: usize = 0;
+    for &byte in &push.immediate {
+        if value > usize::MAX >> 8 {
+            return Some(None);
+        }
+        value = value << 8 | byte as usize;
+    }
+    Some(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn recover_cfg() {
+        // 00: CALLDATASIZE  01: PUSH1 0x08  03: JUMPI
+        // 04: PUSH1 0x00    06: STOP
+        // 07: INVALID
+        // 08: JUMPDEST      09: PUSH1 0x0e  0b: JUMP
+        // 0c: PUSH1 0x0d
+        // 0e: JUMPDEST      0f: CALLVALUE   10: JUMP
+        // 11: JUMPDEST      12: PUSH1 0x04  14: JUMP
+        let cfg = Cfg::new(&hex!("36600857600000fe5b600e56600d5b34565b600456"));
+        assert_eq!(
+            cfg.blocks.keys().copied().collect::<Vec<_>>(),
+            vec![0x00, 0x04, 0x07, 0x08, 0x0c, 0x0e, 0x11]
+        );
+        assert_eq!(cfg.blocks[&0x08].end(), 0x0c);
+        assert_eq!(
+            cfg.edges,
+            vec![
+                Edge {
+                    from: 0x00,
+                    to: 0x08,
+                    kind: EdgeKind::BranchTaken
+                },
+                Edge {
+                    from: 0x00,
+                    to: 0x04,
+                    kind: EdgeKind::BranchNotTaken
+                },
+                Edge {
+                    from: 0x08,
+                    to: 0x0e,
+                    kind: EdgeKind::Jump
+                },
+                Edge {
+                    from: 0x0c,
+                    to: 0x0e,
+                    kind: EdgeKind::Fallthrough
+                },
+            ]
+        );
+        assert_eq!(cfg.unresolved, vec![0x10]);
+        assert_eq!(
+            cfg.invalid_jumps,
+            vec![InvalidJump {
+                pc: 0x14,
+                target: Some(0x04)
+            }]
+        );
+        assert_eq!(
+            cfg.reachable().into_iter().collect::<Vec<_>>(),
+            vec![0x00, 0x04, 0x08, 0x0e]
+        );
+        assert_eq!(cfg.predecessors(0x0e).count(), 2);
+    }
+
+    #[test]
+    fn oversized_jump_target() {
+        let cfg = Cfg::new(&hex!(
+            "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff56"
+        ));
+        assert_eq!(
+            cfg.invalid_jumps,
+            vec![InvalidJump {
+                pc: 0x21,
+                target: None
+            }]
+        );
+    }
+
+    #[test]
+    fn dot_output() {
+        // 00: CALLDATASIZE  01: PUSH1 0x06  03: JUMPI
+        // 04: CALLVALUE     05: JUMP
+        // 06: JUMPDEST      07: STOP
+        // 08: JUMPDEST      09: STOP
+        let dot = Cfg::new(&hex!("3660065734565b005b00")).to_dot();
+        assert_eq!(
+            dot,
+            r#"digraph cfg {
+    node [shape=box fontname="monospace"];
+    block_0000 [label="0000: CALLDATASIZE\l0001: PUSH1 0x06\l0003: JUMPI\l"];
+    block_0004 [label="0004: CALLVALUE\l0005: JUMP\l"];
+    block_0006 [label="0006: JUMPDEST\l0007: STOP\l"];
+    block_0008 [label="0008: JUMPDEST\l0009: STOP\l" style=filled fillcolor=lightgrey];
+    block_0000 -> block_0006 [label="true" color=green];
+    block_0000 -> block_0004 [label="false" color=red];
+    unresolved [label="?" shape=circle];
+    block_0004 -> unresolved [label="dynamic" style=dotted];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn jump_into_push_data() {
+        // 00: PUSH1 0x04  02: JUMP  03: PUSH2 0x5b5b
+        let cfg = Cfg::new(&hex!("600456615b5b"));
+        assert!(cfg.edges.is_empty());
+        assert_eq!(
+            cfg.invalid_jumps,
+            vec![InvalidJump {
+                pc: 0x02,
+                target: Some(0x04)
+            }]
+        );
+    }
+}
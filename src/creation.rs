@@ -0,0 +1,191 @@
+use crate::disasm::{Instruction, Opcode};
+use crate::hardfork::Hardfork;
+
+/// Creation (init) code split at the runtime code it deploys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreationCode<'a> {
+    /// Code executed by the deployment transaction.
+    pub constructor: &'a [u8],
+    /// Code returned by the constructor and stored at the new address.
+    pub runtime: &'a [u8],
+    /// Offset of `runtime` in the creation code.
+    pub runtime_offset: usize,
+    /// Bytes after the runtime code, usually ABI-encoded constructor arguments.
+    pub data: &'a [u8],
+}
+
+/// Value tracked on the abstract stack while looking for the deploy sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Const(usize),
+    Unknown,
+}
+
+/// Splits creation code by finding the `CODECOPY`/`RETURN` sequence that deploys the
+/// runtime code.
+///
+/// The sequence emitted by solc and vyper copies a constant `size` bytes from a
+/// constant `offset` of the code into memory and returns them:
+///
+/// ```text
+/// PUSH2 size  DUP1  PUSH2 offset  PUSH1 0x00  CODECOPY  PUSH1 0x00  RETURN
+/// ```
+///
+/// Constants are tracked through `PUSH`, `DUP`, `SWAP` and `POP` within a basic
+/// block, so reordered variants are recognised as well. Returns `None` when no
+/// such sequence is found.
+pub fn split(code: &[u8]) -> Option<CreationCode<'_>> {
+    split_for(code, Hardfork::default())
+}
+
+/// Same as [`split`] with the opcode table of `fork`.
+pub fn split_for(code: &[u8], fork: Hardfork) -> Option<CreationCode<'_>> {
+    let instructions = Opcode::disasm_for(code, fork);
+    let (offset, size) = deploy_range(&instructions)?;
+    let end = offset.checked_add(size)?;
+    if end > code.len() {
+        return None;
+    }
+    Some(CreationCode {
+        constructor: &code[..offset],
+        runtime: &code[offset..end],
+        runtime_offset: offset,
+        data: &code[end..],
+    })
+}
+
+fn deploy_range(instructions: &[Instruction]) -> Option<(usize, usize)> {
+    let mut stack: Vec<Slot> = Vec::new();
+    // Offset and size of the last constant `CODECOPY` in the current block.
+    let mut copied: Option<(usize, usize)> = None;
+
+    for instruction in instructions {
+        let opcode = instruction.opcode;
+        if opcode == Opcode::JUMPDEST {
+            stack.clear();
+            copied = None;
+        }
+
+        let size = opcode.immediate_size();
+        if size > 0 || opcode == Opcode::PUSH0 {
+            stack.push(push_value(&instruction.immediate));
+            continue;
+        }
+
+        let code = opcode.code();
+        match opcode {
+            _ if (0x80..=0x8f).contains(&code) => {
+                let depth = (code - 0x7f) as usize;
+                let slot = peek(&stack, depth - 1);
+                stack.push(slot);
+            }
+            _ if (0x90..=0x9f).contains(&code) => {
+                let depth = (code - 0x8f) as usize;
+                // Missing items are pulled from below the tracked part of the stack.
+                while stack.len() <= depth {
+                    stack.insert(0, Slot::Unknown);
+                }
+                let top = stack.len() - 1;
+                stack.swap(top, top - depth);
+            }
+            Opcode::CODECOPY => {
+                let _dest = pop(&mut stack);
+                let offset = pop(&mut stack);
+                let size = pop(&mut stack);
+                if let (Slot::Const(offset), Slot::Const(size)) = (offset, size) {
+                    copied = Some((offset, size));
+                }
+            }
+            Opcode::RETURN => {
+                let _offset = pop(&mut stack);
+                let size = pop(&mut stack);
+                if let Some((offset, copied_size)) = copied {
+                    if size == Slot::Const(copied_size) || size == Slot::Unknown {
+                        return Some((offset, copied_size));
+                    }
+                }
+            }
+            _ if opcode.is_terminator() || opcode == Opcode::JUMPI => {
+                stack.clear();
+                copied = None;
+            }
+            _ => {
+                for _ in 0..opcode.stack_inputs() {
+                    pop(&mut stack);
+                }
+                for _ in 0..opcode.stack_outputs() {
+                    stack.push(Slot::Unknown);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn push_value(bytes: &[u8]) -> Slot {
+    let mut value: usize = 0;
+    for &byte in bytes {
+        if value > usize::MAX >> 8 {
+            return Slot::Unknown;
+        }
+        value = value << 8 | byte as usize;
+    }
+    Slot::Const(value)
+}
+
+fn peek(stack: &[Slot], depth: usize) -> Slot {
+    stack
+        .len()
+        .checked_sub(depth + 1)
+        .map(|index| stack[index])
+        .unwrap_or(Slot::Unknown)
+}
+
+fn pop(stack: &mut Vec<Slot>) -> Slot {
+    stack.pop().unwrap_or(Slot::Unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn split_solc_creation_code() {
+        // Constructor rejecting value, then the usual deploy sequence:
+        // 0000: PUSH1 0x80 PUSH1 0x40 MSTORE CALLVALUE DUP1 ISZERO PUSH1 0x0f JUMPI
+        // 000b: PUSH1 0x00 DUP1 REVERT
+        // 000f: JUMPDEST POP PUSH1 0x06 DUP1 PUSH1 0x1d PUSH1 0x00 CODECOPY PUSH1 0x00
+        //       RETURN INVALID
+        // 001d: runtime  PUSH1 0x00 DUP1 REVERT INVALID
+        // 0023: constructor argument
+        let code = hex!(
+            "6080604052348015600f57600080fd5b50600680601d6000396000f3fe"
+            "600080fdfe00"
+            "000000000000000000000000000000000000000000000000000000000000002a"
+        );
+        let creation = split(&code).unwrap();
+        assert_eq!(creation.runtime_offset, 0x1d);
+        assert_eq!(creation.constructor, &code[..0x1d]);
+        assert_eq!(creation.runtime, &hex!("600080fdfe00"));
+        assert_eq!(creation.data.len(), 32);
+    }
+
+    #[test]
+    fn split_push0_creation_code() {
+        // PUSH1 0x01 DUP1 PUSH1 0x0a PUSH0 CODECOPY PUSH0 RETURN INVALID | STOP
+        let code = hex!("600180600a5f395ff3fe00");
+        let creation = split(&code).unwrap();
+        assert_eq!(creation.runtime, &hex!("00"));
+        assert!(creation.data.is_empty());
+        // `PUSH0` is undefined before Shanghai, so the sequence is not recognised.
+        assert_eq!(split_for(&code, Hardfork::London), None);
+    }
+
+    #[test]
+    fn runtime_code_is_not_split() {
+        assert_eq!(split(&hex!("6080604052600080fd")), None);
+        // Copied range past the end of the code.
+        assert_eq!(split(&hex!("61ffff80600a5f395ff3fe00")), None);
+    }
+}
@@ -1,5 +1,6 @@
-#[derive(PartialEq, Eq)]
-// TODO: Add arguments for opcodes which require them.
+use crate::hardfork::Hardfork;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Opcode {
     /// 0x00 Stop and Arithmetic Operation.
     STOP,
@@ -54,9 +55,13 @@ pub enum Opcode {
     TIMESTAMP,
     NUMBER,
     DIFFICULTY,
+    PREVRANDAO,
     GASLIMIT,
     CHAINID,
+    SELFBALANCE,
     BASEFEE,
+    BLOBHASH,
+    BLOBBASEFEE,
     /// 0x50: Stack, Memory & Flow Operations.
     POP,
     MLOAD,
@@ -70,7 +75,11 @@ pub enum Opcode {
     MSIZE,
     GAS,
     JUMPDEST,
-    /// 0x60 & 0x70: Push Operations.
+    TLOAD,
+    TSTORE,
+    MCOPY,
+    /// 0x5f, 0x60 & 0x70: Push Operations.
+    PUSH0,
     PUSH1,
     PUSH2,
     PUSH3,
@@ -154,13 +163,20 @@ pub enum Opcode {
     REVERT,
     INVALID,
     SELFDESTRUCT,
-    // UNKNOWN
-    UNKNOWN,
+    /// Any byte that is not a known opcode.
+    UNKNOWN(u8),
 }
 
 impl Opcode {
+    /// Decodes `code` with the rules of the latest hardfork.
     pub fn new(code: u8) -> Opcode {
-        match code {
+        Self::new_for(code, Hardfork::default())
+    }
+
+    /// Decodes `code` with the rules in force at `fork`. Opcodes introduced by a later
+    /// hardfork decode as `UNKNOWN`.
+    pub fn new_for(code: u8, fork: Hardfork) -> Opcode {
+        let opcode = match code {
             0x00 => Opcode::STOP,
             0x01 => Opcode::ADD,
             0x02 => Opcode::MUL,
@@ -208,10 +224,14 @@ impl Opcode {
             0x41 => Opcode::COINBASE,
             0x42 => Opcode::TIMESTAMP,
             0x43 => Opcode::NUMBER,
+            0x44 if fork >= Hardfork::Paris => Opcode::PREVRANDAO,
             0x44 => Opcode::DIFFICULTY,
             0x45 => Opcode::GASLIMIT,
             0x46 => Opcode::CHAINID,
+            0x47 => Opcode::SELFBALANCE,
             0x48 => Opcode::BASEFEE,
+            0x49 => Opcode::BLOBHASH,
+            0x4a => Opcode::BLOBBASEFEE,
             0x50 => Opcode::POP,
             0x51 => Opcode::MLOAD,
             0x52 => Opcode::MSTORE,
@@ -224,6 +244,10 @@ impl Opcode {
             0x59 => Opcode::MSIZE,
             0x5a => Opcode::GAS,
             0x5b => Opcode::JUMPDEST,
+            0x5c => Opcode::TLOAD,
+            0x5d => Opcode::TSTORE,
+            0x5e => Opcode::MCOPY,
+            0x5f => Opcode::PUSH0,
             0x60 => Opcode::PUSH1,
             0x61 => Opcode::PUSH2,
             0x62 => Opcode::PUSH3,
@@ -303,170 +327,873 @@ impl Opcode {
             0xfd => Opcode::REVERT,
             0xfe => Opcode::INVALID,
             0xff => Opcode::SELFDESTRUCT,
-            _ => Opcode::UNKNOWN,
+            _ => Opcode::UNKNOWN(code),
+        };
+        if fork >= opcode.introduced_in() {
+            opcode
+        } else {
+            Opcode::UNKNOWN(code)
+        }
+    }
+
+    /// Hardfork that added the opcode to the instruction set.
+    pub fn introduced_in(&self) -> Hardfork {
+        match self {
+            Opcode::DELEGATECALL => Hardfork::Homestead,
+            Opcode::RETURNDATASIZE
+            | Opcode::RETURNDATACOPY
+            | Opcode::STATICCALL
+            | Opcode::REVERT => Hardfork::Byzantium,
+            Opcode::SHL | Opcode::SHR | Opcode::SAR | Opcode::EXTCODEHASH | Opcode::CREATE2 => {
+                Hardfork::Constantinople
+            }
+            Opcode::CHAINID | Opcode::SELFBALANCE => Hardfork::Istanbul,
+            Opcode::BASEFEE => Hardfork::London,
+            Opcode::PREVRANDAO => Hardfork::Paris,
+            Opcode::PUSH0 => Hardfork::Shanghai,
+            Opcode::TLOAD
+            | Opcode::TSTORE
+            | Opcode::MCOPY
+            | Opcode::BLOBHASH
+            | Opcode::BLOBBASEFEE => Hardfork::Cancun,
+            _ => Hardfork::Frontier,
+        }
+    }
+
+    /// Byte encoding of the opcode.
+    pub fn code(&self) -> u8 {
+        match self {
+            Opcode::STOP => 0x00,
+            Opcode::ADD => 0x01,
+            Opcode::MUL => 0x02,
+            Opcode::SUB => 0x03,
+            Opcode::DIV => 0x04,
+            Opcode::SDIV => 0x05,
+            Opcode::MOD => 0x06,
+            Opcode::SMOD => 0x07,
+            Opcode::ADDMOD => 0x08,
+            Opcode::MULMOD => 0x09,
+            Opcode::EXP => 0x0a,
+            Opcode::SIGNEXTEND => 0x0b,
+            Opcode::LT => 0x10,
+            Opcode::GT => 0x11,
+            Opcode::SLT => 0x12,
+            Opcode::SGT => 0x13,
+            Opcode::EQ => 0x14,
+            Opcode::ISZERO => 0x15,
+            Opcode::AND => 0x16,
+            Opcode::OR => 0x17,
+            Opcode::XOR => 0x18,
+            Opcode::NOT => 0x19,
+            Opcode::BYTE => 0x1a,
+            Opcode::SHL => 0x1b,
+            Opcode::SHR => 0x1c,
+            Opcode::SAR => 0x1d,
+            Opcode::KECCAK256 => 0x20,
+            Opcode::ADDRESS => 0x30,
+            Opcode::BALANCE => 0x31,
+            Opcode::ORIGIN => 0x32,
+            Opcode::CALLER => 0x33,
+            Opcode::CALLVALUE => 0x34,
+            Opcode::CALLDATALOAD => 0x35,
+            Opcode::CALLDATASIZE => 0x36,
+            Opcode::CALLDATACOPY => 0x37,
+            Opcode::CODESIZE => 0x38,
+            Opcode::CODECOPY => 0x39,
+            Opcode::GASPRICE => 0x3a,
+            Opcode::EXTCODESIZE => 0x3b,
+            Opcode::EXTCODECOPY => 0x3c,
+            Opcode::RETURNDATASIZE => 0x3d,
+            Opcode::RETURNDATACOPY => 0x3e,
+            Opcode::EXTCODEHASH => 0x3f,
+            Opcode::BLOCKHASH => 0x40,
+            Opcode::COINBASE => 0x41,
+            Opcode::TIMESTAMP => 0x42,
+            Opcode::NUMBER => 0x43,
+            Opcode::DIFFICULTY => 0x44,
+            Opcode::PREVRANDAO => 0x44,
+            Opcode::GASLIMIT => 0x45,
+            Opcode::CHAINID => 0x46,
+            Opcode::SELFBALANCE => 0x47,
+            Opcode::BASEFEE => 0x48,
+            Opcode::BLOBHASH => 0x49,
+            Opcode::BLOBBASEFEE => 0x4a,
+            Opcode::POP => 0x50,
+            Opcode::MLOAD => 0x51,
+            Opcode::MSTORE => 0x52,
+            Opcode::MSTORE8 => 0x53,
+            Opcode::SLOAD => 0x54,
+            Opcode::SSTORE => 0x55,
+            Opcode::JUMP => 0x56,
+            Opcode::JUMPI => 0x57,
+            Opcode::PC => 0x58,
+            Opcode::MSIZE => 0x59,
+            Opcode::GAS => 0x5a,
+            Opcode::JUMPDEST => 0x5b,
+            Opcode::TLOAD => 0x5c,
+            Opcode::TSTORE => 0x5d,
+            Opcode::MCOPY => 0x5e,
+            Opcode::PUSH0 => 0x5f,
+            Opcode::PUSH1 => 0x60,
+            Opcode::PUSH2 => 0x61,
+            Opcode::PUSH3 => 0x62,
+            Opcode::PUSH4 => 0x63,
+            Opcode::PUSH5 => 0x64,
+            Opcode::PUSH6 => 0x65,
+            Opcode::PUSH7 => 0x66,
+            Opcode::PUSH8 => 0x67,
+            Opcode::PUSH9 => 0x68,
+            Opcode::PUSH10 => 0x69,
+            Opcode::PUSH11 => 0x6a,
+            Opcode::PUSH12 => 0x6b,
+            Opcode::PUSH13 => 0x6c,
+            Opcode::PUSH14 => 0x6d,
+            Opcode::PUSH15 => 0x6e,
+            Opcode::PUSH16 => 0x6f,
+            Opcode::PUSH17 => 0x70,
+            Opcode::PUSH18 => 0x71,
+            Opcode::PUSH19 => 0x72,
+            Opcode::PUSH20 => 0x73,
+            Opcode::PUSH21 => 0x74,
+            Opcode::PUSH22 => 0x75,
+            Opcode::PUSH23 => 0x76,
+            Opcode::PUSH24 => 0x77,
+            Opcode::PUSH25 => 0x78,
+            Opcode::PUSH26 => 0x79,
+            Opcode::PUSH27 => 0x7a,
+            Opcode::PUSH28 => 0x7b,
+            Opcode::PUSH29 => 0x7c,
+            Opcode::PUSH30 => 0x7d,
+            Opcode::PUSH31 => 0x7e,
+            Opcode::PUSH32 => 0x7f,
+            Opcode::DUP1 => 0x80,
+            Opcode::DUP2 => 0x81,
+            Opcode::DUP3 => 0x82,
+            Opcode::DUP4 => 0x83,
+            Opcode::DUP5 => 0x84,
+            Opcode::DUP6 => 0x85,
+            Opcode::DUP7 => 0x86,
+            Opcode::DUP8 => 0x87,
+            Opcode::DUP9 => 0x88,
+            Opcode::DUP10 => 0x89,
+            Opcode::DUP11 => 0x8a,
+            Opcode::DUP12 => 0x8b,
+            Opcode::DUP13 => 0x8c,
+            Opcode::DUP14 => 0x8d,
+            Opcode::DUP15 => 0x8e,
+            Opcode::DUP16 => 0x8f,
+            Opcode::SWAP1 => 0x90,
+            Opcode::SWAP2 => 0x91,
+            Opcode::SWAP3 => 0x92,
+            Opcode::SWAP4 => 0x93,
+            Opcode::SWAP5 => 0x94,
+            Opcode::SWAP6 => 0x95,
+            Opcode::SWAP7 => 0x96,
+            Opcode::SWAP8 => 0x97,
+            Opcode::SWAP9 => 0x98,
+            Opcode::SWAP10 => 0x99,
+            Opcode::SWAP11 => 0x9a,
+            Opcode::SWAP12 => 0x9b,
+            Opcode::SWAP13 => 0x9c,
+            Opcode::SWAP14 => 0x9d,
+            Opcode::SWAP15 => 0x9e,
+            Opcode::SWAP16 => 0x9f,
+            Opcode::LOG0 => 0xa0,
+            Opcode::LOG1 => 0xa1,
+            Opcode::LOG2 => 0xa2,
+            Opcode::LOG3 => 0xa3,
+            Opcode::LOG4 => 0xa4,
+            Opcode::CREATE => 0xf0,
+            Opcode::CALL => 0xf1,
+            Opcode::CALLCODE => 0xf2,
+            Opcode::RETURN => 0xf3,
+            Opcode::DELEGATECALL => 0xf4,
+            Opcode::CREATE2 => 0xf5,
+            Opcode::STATICCALL => 0xfa,
+            Opcode::REVERT => 0xfd,
+            Opcode::INVALID => 0xfe,
+            Opcode::SELFDESTRUCT => 0xff,
+            Opcode::UNKNOWN(code) => *code,
+        }
+    }
+
+    /// Assembly mnemonic of the opcode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Opcode::STOP => "STOP",
+            Opcode::ADD => "ADD",
+            Opcode::MUL => "MUL",
+            Opcode::SUB => "SUB",
+            Opcode::DIV => "DIV",
+            Opcode::SDIV => "SDIV",
+            Opcode::MOD => "MOD",
+            Opcode::SMOD => "SMOD",
+            Opcode::ADDMOD => "ADDMOD",
+            Opcode::MULMOD => "MULMOD",
+            Opcode::EXP => "EXP",
+            Opcode::SIGNEXTEND => "SIGNEXTEND",
+            Opcode::LT => "LT",
+            Opcode::GT => "GT",
+            Opcode::SLT => "SLT",
+            Opcode::SGT => "SGT",
+            Opcode::EQ => "EQ",
+            Opcode::ISZERO => "ISZERO",
+            Opcode::AND => "AND",
+            Opcode::OR => "OR",
+            Opcode::XOR => "XOR",
+            Opcode::NOT => "NOT",
+            Opcode::BYTE => "BYTE",
+            Opcode::SHL => "SHL",
+            Opcode::SHR => "SHR",
+            Opcode::SAR => "SAR",
+            Opcode::KECCAK256 => "KECCAK256",
+            Opcode::ADDRESS => "ADDRESS",
+            Opcode::BALANCE => "BALANCE",
+            Opcode::ORIGIN => "ORIGIN",
+            Opcode::CALLER => "CALLER",
+            Opcode::CALLVALUE => "CALLVALUE",
+            Opcode::CALLDATALOAD => "CALLDATALOAD",
+            Opcode::CALLDATASIZE => "CALLDATASIZE",
+            Opcode::CALLDATACOPY => "CALLDATACOPY",
+            Opcode::CODESIZE => "CODESIZE",
+            Opcode::CODECOPY => "CODECOPY",
+            Opcode::GASPRICE => "GASPRICE",
+            Opcode::EXTCODESIZE => "EXTCODESIZE",
+            Opcode::EXTCODECOPY => "EXTCODECOPY",
+            Opcode::RETURNDATASIZE => "RETURNDATASIZE",
+            Opcode::RETURNDATACOPY => "RETURNDATACOPY",
+            Opcode::EXTCODEHASH => "EXTCODEHASH",
+            Opcode::BLOCKHASH => "BLOCKHASH",
+            Opcode::COINBASE => "COINBASE",
+            Opcode::TIMESTAMP => "TIMESTAMP",
+            Opcode::NUMBER => "NUMBER",
+            Opcode::DIFFICULTY => "DIFFICULTY",
+            Opcode::PREVRANDAO => "PREVRANDAO",
+            Opcode::GASLIMIT => "GASLIMIT",
+            Opcode::CHAINID => "CHAINID",
+            Opcode::SELFBALANCE => "SELFBALANCE",
+            Opcode::BASEFEE => "BASEFEE",
+            Opcode::BLOBHASH => "BLOBHASH",
+            Opcode::BLOBBASEFEE => "BLOBBASEFEE",
+            Opcode::POP => "POP",
+            Opcode::MLOAD => "MLOAD",
+            Opcode::MSTORE => "MSTORE",
+            Opcode::MSTORE8 => "MSTORE8",
+            Opcode::SLOAD => "SLOAD",
+            Opcode::SSTORE => "SSTORE",
+            Opcode::JUMP => "JUMP",
+            Opcode::JUMPI => "JUMPI",
+            Opcode::PC => "PC",
+            Opcode::MSIZE => "MSIZE",
+            Opcode::GAS => "GAS",
+            Opcode::JUMPDEST => "JUMPDEST",
+            Opcode::TLOAD => "TLOAD",
+            Opcode::TSTORE => "TSTORE",
+            Opcode::MCOPY => "MCOPY",
+            Opcode::PUSH0 => "PUSH0",
+            Opcode::PUSH1 => "PUSH1",
+            Opcode::PUSH2 => "PUSH2",
+            Opcode::PUSH3 => "PUSH3",
+            Opcode::PUSH4 => "PUSH4",
+            Opcode::PUSH5 => "PUSH5",
+            Opcode::PUSH6 => "PUSH6",
+            Opcode::PUSH7 => "PUSH7",
+            Opcode::PUSH8 => "PUSH8",
+            Opcode::PUSH9 => "PUSH9",
+            Opcode::PUSH10 => "PUSH10",
+            Opcode::PUSH11 => "PUSH11",
+            Opcode::PUSH12 => "PUSH12",
+            Opcode::PUSH13 => "PUSH13",
+            Opcode::PUSH14 => "PUSH14",
+            Opcode::PUSH15 => "PUSH15",
+            Opcode::PUSH16 => "PUSH16",
+            Opcode::PUSH17 => "PUSH17",
+            Opcode::PUSH18 => "PUSH18",
+            Opcode::PUSH19 => "PUSH19",
+            Opcode::PUSH20 => "PUSH20",
+            Opcode::PUSH21 => "PUSH21",
+            Opcode::PUSH22 => "PUSH22",
+            Opcode::PUSH23 => "PUSH23",
+            Opcode::PUSH24 => "PUSH24",
+            Opcode::PUSH25 => "PUSH25",
+            Opcode::PUSH26 => "PUSH26",
+            Opcode::PUSH27 => "PUSH27",
+            Opcode::PUSH28 => "PUSH28",
+            Opcode::PUSH29 => "PUSH29",
+            Opcode::PUSH30 => "PUSH30",
+            Opcode::PUSH31 => "PUSH31",
+            Opcode::PUSH32 => "PUSH32",
+            Opcode::DUP1 => "DUP1",
+            Opcode::DUP2 => "DUP2",
+            Opcode::DUP3 => "DUP3",
+            Opcode::DUP4 => "DUP4",
+            Opcode::DUP5 => "DUP5",
+            Opcode::DUP6 => "DUP6",
+            Opcode::DUP7 => "DUP7",
+            Opcode::DUP8 => "DUP8",
+            Opcode::DUP9 => "DUP9",
+            Opcode::DUP10 => "DUP10",
+            Opcode::DUP11 => "DUP11",
+            Opcode::DUP12 => "DUP12",
+            Opcode::DUP13 => "DUP13",
+            Opcode::DUP14 => "DUP14",
+            Opcode::DUP15 => "DUP15",
+            Opcode::DUP16 => "DUP16",
+            Opcode::SWAP1 => "SWAP1",
+            Opcode::SWAP2 => "SWAP2",
+            Opcode::SWAP3 => "SWAP3",
+            Opcode::SWAP4 => "SWAP4",
+            Opcode::SWAP5 => "SWAP5",
+            Opcode::SWAP6 => "SWAP6",
+            Opcode::SWAP7 => "SWAP7",
+            Opcode::SWAP8 => "SWAP8",
+            Opcode::SWAP9 => "SWAP9",
+            Opcode::SWAP10 => "SWAP10",
+            Opcode::SWAP11 => "SWAP11",
+            Opcode::SWAP12 => "SWAP12",
+            Opcode::SWAP13 => "SWAP13",
+            Opcode::SWAP14 => "SWAP14",
+            Opcode::SWAP15 => "SWAP15",
+            Opcode::SWAP16 => "SWAP16",
+            Opcode::LOG0 => "LOG0",
+            Opcode::LOG1 => "LOG1",
+            Opcode::LOG2 => "LOG2",
+            Opcode::LOG3 => "LOG3",
+            Opcode::LOG4 => "LOG4",
+            Opcode::CREATE => "CREATE",
+            Opcode::CALL => "CALL",
+            Opcode::CALLCODE => "CALLCODE",
+            Opcode::RETURN => "RETURN",
+            Opcode::DELEGATECALL => "DELEGATECALL",
+            Opcode::CREATE2 => "CREATE2",
+            Opcode::STATICCALL => "STATICCALL",
+            Opcode::REVERT => "REVERT",
+            Opcode::INVALID => "INVALID",
+            Opcode::SELFDESTRUCT => "SELFDESTRUCT",
+            Opcode::UNKNOWN(_) => "UNKNOWN",
+        }
+    }
+
+    /// Number of stack items the opcode consumes.
+    pub fn stack_inputs(&self) -> usize {
+        self.stack_io().0
+    }
+
+    /// Number of stack items the opcode produces.
+    pub fn stack_outputs(&self) -> usize {
+        self.stack_io().1
+    }
+
+    fn stack_io(&self) -> (usize, usize) {
+        match self {
+            Opcode::STOP | Opcode::JUMPDEST | Opcode::INVALID | Opcode::UNKNOWN(_) => (0, 0),
+            Opcode::ADD
+            | Opcode::MUL
+            | Opcode::SUB
+            | Opcode::DIV
+            | Opcode::SDIV
+            | Opcode::MOD
+            | Opcode::SMOD
+            | Opcode::EXP
+            | Opcode::SIGNEXTEND
+            | Opcode::LT
+            | Opcode::GT
+            | Opcode::SLT
+            | Opcode::SGT
+            | Opcode::EQ
+            | Opcode::AND
+            | Opcode::OR
+            | Opcode::XOR
+            | Opcode::BYTE
+            | Opcode::SHL
+            | Opcode::SHR
+            | Opcode::SAR
+            | Opcode::KECCAK256 => (2, 1),
+            Opcode::ADDMOD | Opcode::MULMOD | Opcode::CREATE => (3, 1),
+            Opcode::ISZERO
+            | Opcode::NOT
+            | Opcode::BALANCE
+            | Opcode::CALLDATALOAD
+            | Opcode::EXTCODESIZE
+            | Opcode::EXTCODEHASH
+            | Opcode::BLOCKHASH
+            | Opcode::BLOBHASH
+            | Opcode::MLOAD
+            | Opcode::SLOAD
+            | Opcode::TLOAD => (1, 1),
+            Opcode::ADDRESS
+            | Opcode::ORIGIN
+            | Opcode::CALLER
+            | Opcode::CALLVALUE
+            | Opcode::CALLDATASIZE
+            | Opcode::CODESIZE
+            | Opcode::GASPRICE
+            | Opcode::RETURNDATASIZE
+            | Opcode::COINBASE
+            | Opcode::TIMESTAMP
+            | Opcode::NUMBER
+            | Opcode::DIFFICULTY
+            | Opcode::PREVRANDAO
+            | Opcode::GASLIMIT
+            | Opcode::CHAINID
+            | Opcode::SELFBALANCE
+            | Opcode::BASEFEE
+            | Opcode::BLOBBASEFEE
+            | Opcode::PC
+            | Opcode::MSIZE
+            | Opcode::GAS
+            | Opcode::PUSH0 => (0, 1),
+            Opcode::CALLDATACOPY | Opcode::CODECOPY | Opcode::RETURNDATACOPY | Opcode::MCOPY => {
+                (3, 0)
+            }
+            Opcode::EXTCODECOPY => (4, 0),
+            Opcode::POP | Opcode::JUMP | Opcode::SELFDESTRUCT => (1, 0),
+            Opcode::MSTORE
+            | Opcode::MSTORE8
+            | Opcode::SSTORE
+            | Opcode::TSTORE
+            | Opcode::JUMPI
+            | Opcode::RETURN
+            | Opcode::REVERT => (2, 0),
+            Opcode::CALL | Opcode::CALLCODE => (7, 1),
+            Opcode::DELEGATECALL | Opcode::STATICCALL => (6, 1),
+            Opcode::CREATE2 => (4, 1),
+            _ if self.immediate_size() > 0 => (0, 1),
+            _ => {
+                let code = self.code() as usize;
+                match code {
+                    // DUPn
+                    0x80..=0x8f => (code - 0x7f, code - 0x7e),
+                    // SWAPn
+                    0x90..=0x9f => (code - 0x8e, code - 0x8e),
+                    // LOGn
+                    _ => (code - 0x9e, 0),
+                }
+            }
+        }
+    }
+
+    /// Gas charged before execution of the opcode under `fork`. Costs that depend on
+    /// operands or state (memory expansion, cold access, SSTORE, ...) are not included.
+    pub fn static_gas(&self, fork: Hardfork) -> u64 {
+        match self {
+            Opcode::STOP
+            | Opcode::RETURN
+            | Opcode::REVERT
+            | Opcode::INVALID
+            | Opcode::SSTORE
+            | Opcode::UNKNOWN(_) => 0,
+            Opcode::JUMPDEST => 1,
+            Opcode::ADDRESS
+            | Opcode::ORIGIN
+            | Opcode::CALLER
+            | Opcode::CALLVALUE
+            | Opcode::CALLDATASIZE
+            | Opcode::CODESIZE
+            | Opcode::GASPRICE
+            | Opcode::RETURNDATASIZE
+            | Opcode::COINBASE
+            | Opcode::TIMESTAMP
+            | Opcode::NUMBER
+            | Opcode::DIFFICULTY
+            | Opcode::PREVRANDAO
+            | Opcode::GASLIMIT
+            | Opcode::CHAINID
+            | Opcode::BASEFEE
+            | Opcode::BLOBBASEFEE
+            | Opcode::POP
+            | Opcode::PC
+            | Opcode::MSIZE
+            | Opcode::GAS
+            | Opcode::PUSH0 => 2,
+            Opcode::MUL
+            | Opcode::DIV
+            | Opcode::SDIV
+            | Opcode::MOD
+            | Opcode::SMOD
+            | Opcode::SIGNEXTEND
+            | Opcode::SELFBALANCE => 5,
+            Opcode::ADDMOD | Opcode::MULMOD | Opcode::JUMP => 8,
+            Opcode::EXP | Opcode::JUMPI => 10,
+            Opcode::BLOCKHASH => 20,
+            Opcode::KECCAK256 => 30,
+            Opcode::TLOAD | Opcode::TSTORE => 100,
+            Opcode::LOG0 => 375,
+            Opcode::LOG1 => 750,
+            Opcode::LOG2 => 1125,
+            Opcode::LOG3 => 1500,
+            Opcode::LOG4 => 1875,
+            Opcode::CREATE | Opcode::CREATE2 => 32000,
+            Opcode::BALANCE => match fork {
+                _ if fork >= Hardfork::Berlin => 100,
+                _ if fork >= Hardfork::Istanbul => 700,
+                _ if fork >= Hardfork::TangerineWhistle => 400,
+                _ => 20,
+            },
+            Opcode::EXTCODESIZE | Opcode::EXTCODECOPY => match fork {
+                _ if fork >= Hardfork::Berlin => 100,
+                _ if fork >= Hardfork::TangerineWhistle => 700,
+                _ => 20,
+            },
+            Opcode::EXTCODEHASH => match fork {
+                _ if fork >= Hardfork::Berlin => 100,
+                _ if fork >= Hardfork::Istanbul => 700,
+                _ => 400,
+            },
+            Opcode::SLOAD => match fork {
+                _ if fork >= Hardfork::Berlin => 100,
+                _ if fork >= Hardfork::Istanbul => 800,
+                _ if fork >= Hardfork::TangerineWhistle => 200,
+                _ => 50,
+            },
+            Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL => {
+                match fork {
+                    _ if fork >= Hardfork::Berlin => 100,
+                    _ if fork >= Hardfork::TangerineWhistle => 700,
+                    _ => 40,
+                }
+            }
+            Opcode::SELFDESTRUCT => match fork {
+                _ if fork >= Hardfork::TangerineWhistle => 5000,
+                _ => 0,
+            },
+            // Remaining arithmetic, comparison, memory, copy and stack operations.
+            _ => 3,
+        }
+    }
+
+    /// Whether execution never continues with the next instruction.
+    /// Unknown opcodes behave like `INVALID`.
+    pub fn is_terminator(&self) -> bool {
+        matches!(
+            self,
+            Opcode::STOP
+                | Opcode::RETURN
+                | Opcode::REVERT
+                | Opcode::INVALID
+                | Opcode::SELFDESTRUCT
+                | Opcode::JUMP
+                | Opcode::UNKNOWN(_)
+        )
+    }
+
+    /// Number of immediate bytes following the opcode in the code.
+    pub fn immediate_size(&self) -> usize {
+        match self {
+            Opcode::PUSH1 => 1,
+            Opcode::PUSH2 => 2,
+            Opcode::PUSH3 => 3,
+            Opcode::PUSH4 => 4,
+            Opcode::PUSH5 => 5,
+            Opcode::PUSH6 => 6,
+            Opcode::PUSH7 => 7,
+            Opcode::PUSH8 => 8,
+            Opcode::PUSH9 => 9,
+            Opcode::PUSH10 => 10,
+            Opcode::PUSH11 => 11,
+            Opcode::PUSH12 => 12,
+            Opcode::PUSH13 => 13,
+            Opcode::PUSH14 => 14,
+            Opcode::PUSH15 => 15,
+            Opcode::PUSH16 => 16,
+            Opcode::PUSH17 => 17,
+            Opcode::PUSH18 => 18,
+            Opcode::PUSH19 => 19,
+            Opcode::PUSH20 => 20,
+            Opcode::PUSH21 => 21,
+            Opcode::PUSH22 => 22,
+            Opcode::PUSH23 => 23,
+            Opcode::PUSH24 => 24,
+            Opcode::PUSH25 => 25,
+            Opcode::PUSH26 => 26,
+            Opcode::PUSH27 => 27,
+            Opcode::PUSH28 => 28,
+            Opcode::PUSH29 => 29,
+            Opcode::PUSH30 => 30,
+            Opcode::PUSH31 => 31,
+            Opcode::PUSH32 => 32,
+            _ => 0,
         }
     }
 
-    pub fn disasm(ops: Vec<u8>) -> Vec<Self> {
+    /// `PUSHn` opcode pushing `size` bytes, if `size` is in `1..=32`.
+    pub fn push(size: usize) -> Option<Opcode> {
+        match size {
+            1..=32 => Some(Opcode::new(0x5f + size as u8)),
+            _ => None,
+        }
+    }
+
+    /// Decodes `code` into instructions, attaching PUSH data to the push that owns it.
+    pub fn disasm(code: &[u8]) -> Vec<Instruction> {
+        Self::disasm_for(code, Hardfork::default())
+    }
+
+    /// Same as [`Opcode::disasm`] with the opcode table of `fork`.
+    pub fn disasm_for(code: &[u8], fork: Hardfork) -> Vec<Instruction> {
         let mut result = Vec::new();
-        for op in ops {
-            let opcode = Self::new(op);
-            result.push(opcode);
+        let mut pc = 0;
+        while pc < code.len() {
+            let opcode = Self::new_for(code[pc], fork);
+            let size = opcode.immediate_size();
+            let start = (pc + 1).min(code.len());
+            let end = (pc + 1 + size).min(code.len());
+            result.push(Instruction {
+                pc,
+                opcode,
+                immediate: code[start..end].to_vec(),
+                truncated: end - start < size,
+            });
+            pc += 1 + size;
         }
         result
     }
 }
 
+/// A single decoded instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Offset of the opcode byte in the code.
+    pub pc: usize,
+    pub opcode: Opcode,
+    /// Immediate bytes following the opcode, i.e. the PUSH data.
+    pub immediate: Vec<u8>,
+    /// The code ended before all immediate bytes could be read.
+    /// The EVM pads missing bytes with zeroes.
+    pub truncated: bool,
+}
+
+impl Instruction {
+    /// Offset of the instruction following this one.
+    pub fn next_pc(&self) -> usize {
+        self.pc + 1 + self.opcode.immediate_size()
+    }
+
+    /// Offsets of `0x5b` bytes inside the PUSH data. They are not opcodes, so jumping
+    /// to them fails even though the byte is a `JUMPDEST`.
+    pub fn push_data_jumpdests(&self) -> impl Iterator<Item = usize> + '_ {
+        self.immediate
+            .iter()
+            .enumerate()
+            .filter(|(_, &byte)| byte == 0x5b)
+            .map(move |(offset, _)| self.pc + 1 + offset)
+    }
+}
+
 impl std::fmt::Debug for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:02x}", self.code())
+    }
+}
+
+impl std::fmt::Display for Opcode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Opcode::STOP => write!(f, "0x00"),
-            Opcode::ADD => write!(f, "0x01"),
-            Opcode::MUL => write!(f, "0x02"),
-            Opcode::SUB => write!(f, "0x03"),
-            Opcode::DIV => write!(f, "0x04"),
-            Opcode::SDIV => write!(f, "0x05"),
-            Opcode::MOD => write!(f, "0x06"),
-            Opcode::SMOD => write!(f, "0x07"),
-            Opcode::ADDMOD => write!(f, "0x08"),
-            Opcode::MULMOD => write!(f, "0x09"),
-            Opcode::EXP => write!(f, "0x0a"),
-            Opcode::SIGNEXTEND => write!(f, "0x0b"),
-            Opcode::LT => write!(f, "0x10"),
-            Opcode::GT => write!(f, "0x11"),
-            Opcode::SLT => write!(f, "0x12"),
-            Opcode::SGT => write!(f, "0x13"),
-            Opcode::EQ => write!(f, "0x14"),
-            Opcode::ISZERO => write!(f, "0x15"),
-            Opcode::AND => write!(f, "0x16"),
-            Opcode::OR => write!(f, "0x17"),
-            Opcode::XOR => write!(f, "0x18"),
-            Opcode::NOT => write!(f, "0x19"),
-            Opcode::BYTE => write!(f, "0x1a"),
-            Opcode::SHL => write!(f, "0x1b"),
-            Opcode::SHR => write!(f, "0x1c"),
-            Opcode::SAR => write!(f, "0x1d"),
-            Opcode::KECCAK256 => write!(f, "0x20"),
-            Opcode::ADDRESS => write!(f, "0x30"),
-            Opcode::BALANCE => write!(f, "0x31"),
-            Opcode::ORIGIN => write!(f, "0x32"),
-            Opcode::CALLER => write!(f, "0x33"),
-            Opcode::CALLVALUE => write!(f, "0x34"),
-            Opcode::CALLDATALOAD => write!(f, "0x35"),
-            Opcode::CALLDATASIZE => write!(f, "0x36"),
-            Opcode::CALLDATACOPY => write!(f, "0x37"),
-            Opcode::CODESIZE => write!(f, "0x38"),
-            Opcode::CODECOPY => write!(f, "0x39"),
-            Opcode::GASPRICE => write!(f, "0x3a"),
-            Opcode::EXTCODESIZE => write!(f, "0x3b"),
-            Opcode::EXTCODECOPY => write!(f, "0x3c"),
-            Opcode::RETURNDATASIZE => write!(f, "0x3d"),
-            Opcode::RETURNDATACOPY => write!(f, "0x3e"),
-            Opcode::EXTCODEHASH => write!(f, "0x3f"),
-            Opcode::BLOCKHASH => write!(f, "0x40"),
-            Opcode::COINBASE => write!(f, "0x41"),
-            Opcode::TIMESTAMP => write!(f, "0x42"),
-            Opcode::NUMBER => write!(f, "0x43"),
-            Opcode::DIFFICULTY => write!(f, "0x44"),
-            Opcode::GASLIMIT => write!(f, "0x45"),
-            Opcode::CHAINID => write!(f, "0x46"),
-            Opcode::BASEFEE => write!(f, "0x48"),
-            Opcode::POP => write!(f, "0x50"),
-            Opcode::MLOAD => write!(f, "0x51"),
-            Opcode::MSTORE => write!(f, "0x52"),
-            Opcode::MSTORE8 => write!(f, "0x53"),
-            Opcode::SLOAD => write!(f, "0x54"),
-            Opcode::SSTORE => write!(f, "0x55"),
-            Opcode::JUMP => write!(f, "0x56"),
-            Opcode::JUMPI => write!(f, "0x57"),
-            Opcode::PC => write!(f, "0x58"),
-            Opcode::MSIZE => write!(f, "0x59"),
-            Opcode::GAS => write!(f, "0x5a"),
-            Opcode::JUMPDEST => write!(f, "0x5b"),
-            Opcode::PUSH1 => write!(f, "0x60"),
-            Opcode::PUSH2 => write!(f, "0x61"),
-            Opcode::PUSH3 => write!(f, "0x62"),
-            Opcode::PUSH4 => write!(f, "0x63"),
-            Opcode::PUSH5 => write!(f, "0x64"),
-            Opcode::PUSH6 => write!(f, "0x65"),
-            Opcode::PUSH7 => write!(f, "0x66"),
-            Opcode::PUSH8 => write!(f, "0x67"),
-            Opcode::PUSH9 => write!(f, "0x68"),
-            Opcode::PUSH10 => write!(f, "0x69"),
-            Opcode::PUSH11 => write!(f, "0x6a"),
-            Opcode::PUSH12 => write!(f, "0x6b"),
-            Opcode::PUSH13 => write!(f, "0x6c"),
-            Opcode::PUSH14 => write!(f, "0x6d"),
-            Opcode::PUSH15 => write!(f, "0x6e"),
-            Opcode::PUSH16 => write!(f, "0x6f"),
-            Opcode::PUSH17 => write!(f, "0x70"),
-            Opcode::PUSH18 => write!(f, "0x71"),
-            Opcode::PUSH19 => write!(f, "0x72"),
-            Opcode::PUSH20 => write!(f, "0x73"),
-            Opcode::PUSH21 => write!(f, "0x74"),
-            Opcode::PUSH22 => write!(f, "0x75"),
-            Opcode::PUSH23 => write!(f, "0x76"),
-            Opcode::PUSH24 => write!(f, "0x77"),
-            Opcode::PUSH25 => write!(f, "0x78"),
-            Opcode::PUSH26 => write!(f, "0x79"),
-            Opcode::PUSH27 => write!(f, "0x7a"),
-            Opcode::PUSH28 => write!(f, "0x7b"),
-            Opcode::PUSH29 => write!(f, "0x7c"),
-            Opcode::PUSH30 => write!(f, "0x7d"),
-            Opcode::PUSH31 => write!(f, "0x7e"),
-            Opcode::PUSH32 => write!(f, "0x7f"),
-            Opcode::DUP1 => write!(f, "0x80"),
-            Opcode::DUP2 => write!(f, "0x81"),
-            Opcode::DUP3 => write!(f, "0x82"),
-            Opcode::DUP4 => write!(f, "0x83"),
-            Opcode::DUP5 => write!(f, "0x84"),
-            Opcode::DUP6 => write!(f, "0x85"),
-            Opcode::DUP7 => write!(f, "0x86"),
-            Opcode::DUP8 => write!(f, "0x87"),
-            Opcode::DUP9 => write!(f, "0x88"),
-            Opcode::DUP10 => write!(f, "0x89"),
-            Opcode::DUP11 => write!(f, "0x8a"),
-            Opcode::DUP12 => write!(f, "0x8b"),
-            Opcode::DUP13 => write!(f, "0x8c"),
-            Opcode::DUP14 => write!(f, "0x8d"),
-            Opcode::DUP15 => write!(f, "0x8e"),
-            Opcode::DUP16 => write!(f, "0x8f"),
-            Opcode::SWAP1 => write!(f, "0x90"),
-            Opcode::SWAP2 => write!(f, "0x91"),
-            Opcode::SWAP3 => write!(f, "0x92"),
-            Opcode::SWAP4 => write!(f, "0x93"),
-            Opcode::SWAP5 => write!(f, "0x94"),
-            Opcode::SWAP6 => write!(f, "0x95"),
-            Opcode::SWAP7 => write!(f, "0x96"),
-            Opcode::SWAP8 => write!(f, "0x97"),
-            Opcode::SWAP9 => write!(f, "0x98"),
-            Opcode::SWAP10 => write!(f, "0x99"),
-            Opcode::SWAP11 => write!(f, "0x9a"),
-            Opcode::SWAP12 => write!(f, "0x9b"),
-            Opcode::SWAP13 => write!(f, "0x9c"),
-            Opcode::SWAP14 => write!(f, "0x9d"),
-            Opcode::SWAP15 => write!(f, "0x9e"),
-            Opcode::SWAP16 => write!(f, "0x9f"),
-            Opcode::LOG0 => write!(f, "0xa0"),
-            Opcode::LOG1 => write!(f, "0xa1"),
-            Opcode::LOG2 => write!(f, "0xa2"),
-            Opcode::LOG3 => write!(f, "0xa3"),
-            Opcode::LOG4 => write!(f, "0xa4"),
-            Opcode::CREATE => write!(f, "0xf0"),
-            Opcode::CALL => write!(f, "0xf1"),
-            Opcode::CALLCODE => write!(f, "0xf2"),
-            Opcode::RETURN => write!(f, "0xf3"),
-            Opcode::DELEGATECALL => write!(f, "0xf4"),
-            Opcode::CREATE2 => write!(f, "0xf5"),
-            Opcode::STATICCALL => write!(f, "0xfa"),
-            Opcode::REVERT => write!(f, "0xfd"),
-            Opcode::INVALID => write!(f, "0xfe"),
-            Opcode::SELFDESTRUCT => write!(f, "0xff"),
-            Opcode::UNKNOWN => write!(f, "0x???"),
+            Opcode::UNKNOWN(code) => write!(f, "UNKNOWN(0x{:02x})", code),
+            _ => f.write_str(self.name()),
         }
     }
 }
 
+/// Error returned when a mnemonic does not name an opcode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOpcodeError(pub String);
+
+impl std::fmt::Display for ParseOpcodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown mnemonic `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ParseOpcodeError {}
+
+impl std::str::FromStr for Opcode {
+    type Err = ParseOpcodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mnemonic = s.to_ascii_uppercase();
+        let opcode = match mnemonic.as_str() {
+            "STOP" => Opcode::STOP,
+            "ADD" => Opcode::ADD,
+            "MUL" => Opcode::MUL,
+            "SUB" => Opcode::SUB,
+            "DIV" => Opcode::DIV,
+            "SDIV" => Opcode::SDIV,
+            "MOD" => Opcode::MOD,
+            "SMOD" => Opcode::SMOD,
+            "ADDMOD" => Opcode::ADDMOD,
+            "MULMOD" => Opcode::MULMOD,
+            "EXP" => Opcode::EXP,
+            "SIGNEXTEND" => Opcode::SIGNEXTEND,
+            "LT" => Opcode::LT,
+            "GT" => Opcode::GT,
+            "SLT" => Opcode::SLT,
+            "SGT" => Opcode::SGT,
+            "EQ" => Opcode::EQ,
+            "ISZERO" => Opcode::ISZERO,
+            "AND" => Opcode::AND,
+            "OR" => Opcode::OR,
+            "XOR" => Opcode::XOR,
+            "NOT" => Opcode::NOT,
+            "BYTE" => Opcode::BYTE,
+            "SHL" => Opcode::SHL,
+            "SHR" => Opcode::SHR,
+            "SAR" => Opcode::SAR,
+            "KECCAK256" => Opcode::KECCAK256,
+            "ADDRESS" => Opcode::ADDRESS,
+            "BALANCE" => Opcode::BALANCE,
+            "ORIGIN" => Opcode::ORIGIN,
+            "CALLER" => Opcode::CALLER,
+            "CALLVALUE" => Opcode::CALLVALUE,
+            "CALLDATALOAD" => Opcode::CALLDATALOAD,
+            "CALLDATASIZE" => Opcode::CALLDATASIZE,
+            "CALLDATACOPY" => Opcode::CALLDATACOPY,
+            "CODESIZE" => Opcode::CODESIZE,
+            "CODECOPY" => Opcode::CODECOPY,
+            "GASPRICE" => Opcode::GASPRICE,
+            "EXTCODESIZE" => Opcode::EXTCODESIZE,
+            "EXTCODECOPY" => Opcode::EXTCODECOPY,
+            "RETURNDATASIZE" => Opcode::RETURNDATASIZE,
+            "RETURNDATACOPY" => Opcode::RETURNDATACOPY,
+            "EXTCODEHASH" => Opcode::EXTCODEHASH,
+            "BLOCKHASH" => Opcode::BLOCKHASH,
+            "COINBASE" => Opcode::COINBASE,
+            "TIMESTAMP" => Opcode::TIMESTAMP,
+            "NUMBER" => Opcode::NUMBER,
+            "DIFFICULTY" => Opcode::DIFFICULTY,
+            "PREVRANDAO" => Opcode::PREVRANDAO,
+            "GASLIMIT" => Opcode::GASLIMIT,
+            "CHAINID" => Opcode::CHAINID,
+            "SELFBALANCE" => Opcode::SELFBALANCE,
+            "BASEFEE" => Opcode::BASEFEE,
+            "BLOBHASH" => Opcode::BLOBHASH,
+            "BLOBBASEFEE" => Opcode::BLOBBASEFEE,
+            "POP" => Opcode::POP,
+            "MLOAD" => Opcode::MLOAD,
+            "MSTORE" => Opcode::MSTORE,
+            "MSTORE8" => Opcode::MSTORE8,
+            "SLOAD" => Opcode::SLOAD,
+            "SSTORE" => Opcode::SSTORE,
+            "JUMP" => Opcode::JUMP,
+            "JUMPI" => Opcode::JUMPI,
+            "PC" => Opcode::PC,
+            "MSIZE" => Opcode::MSIZE,
+            "GAS" => Opcode::GAS,
+            "JUMPDEST" => Opcode::JUMPDEST,
+            "TLOAD" => Opcode::TLOAD,
+            "TSTORE" => Opcode::TSTORE,
+            "MCOPY" => Opcode::MCOPY,
+            "PUSH0" => Opcode::PUSH0,
+            "PUSH1" => Opcode::PUSH1,
+            "PUSH2" => Opcode::PUSH2,
+            "PUSH3" => Opcode::PUSH3,
+            "PUSH4" => Opcode::PUSH4,
+            "PUSH5" => Opcode::PUSH5,
+            "PUSH6" => Opcode::PUSH6,
+            "PUSH7" => Opcode::PUSH7,
+            "PUSH8" => Opcode::PUSH8,
+            "PUSH9" => Opcode::PUSH9,
+            "PUSH10" => Opcode::PUSH10,
+            "PUSH11" => Opcode::PUSH11,
+            "PUSH12" => Opcode::PUSH12,
+            "PUSH13" => Opcode::PUSH13,
+            "PUSH14" => Opcode::PUSH14,
+            "PUSH15" => Opcode::PUSH15,
+            "PUSH16" => Opcode::PUSH16,
+            "PUSH17" => Opcode::PUSH17,
+            "PUSH18" => Opcode::PUSH18,
+            "PUSH19" => Opcode::PUSH19,
+            "PUSH20" => Opcode::PUSH20,
+            "PUSH21" => Opcode::PUSH21,
+            "PUSH22" => Opcode::PUSH22,
+            "PUSH23" => Opcode::PUSH23,
+            "PUSH24" => Opcode::PUSH24,
+            "PUSH25" => Opcode::PUSH25,
+            "PUSH26" => Opcode::PUSH26,
+            "PUSH27" => Opcode::PUSH27,
+            "PUSH28" => Opcode::PUSH28,
+            "PUSH29" => Opcode::PUSH29,
+            "PUSH30" => Opcode::PUSH30,
+            "PUSH31" => Opcode::PUSH31,
+            "PUSH32" => Opcode::PUSH32,
+            "DUP1" => Opcode::DUP1,
+            "DUP2" => Opcode::DUP2,
+            "DUP3" => Opcode::DUP3,
+            "DUP4" => Opcode::DUP4,
+            "DUP5" => Opcode::DUP5,
+            "DUP6" => Opcode::DUP6,
+            "DUP7" => Opcode::DUP7,
+            "DUP8" => Opcode::DUP8,
+            "DUP9" => Opcode::DUP9,
+            "DUP10" => Opcode::DUP10,
+            "DUP11" => Opcode::DUP11,
+            "DUP12" => Opcode::DUP12,
+            "DUP13" => Opcode::DUP13,
+            "DUP14" => Opcode::DUP14,
+            "DUP15" => Opcode::DUP15,
+            "DUP16" => Opcode::DUP16,
+            "SWAP1" => Opcode::SWAP1,
+            "SWAP2" => Opcode::SWAP2,
+            "SWAP3" => Opcode::SWAP3,
+            "SWAP4" => Opcode::SWAP4,
+            "SWAP5" => Opcode::SWAP5,
+            "SWAP6" => Opcode::SWAP6,
+            "SWAP7" => Opcode::SWAP7,
+            "SWAP8" => Opcode::SWAP8,
+            "SWAP9" => Opcode::SWAP9,
+            "SWAP10" => Opcode::SWAP10,
+            "SWAP11" => Opcode::SWAP11,
+            "SWAP12" => Opcode::SWAP12,
+            "SWAP13" => Opcode::SWAP13,
+            "SWAP14" => Opcode::SWAP14,
+            "SWAP15" => Opcode::SWAP15,
+            "SWAP16" => Opcode::SWAP16,
+            "LOG0" => Opcode::LOG0,
+            "LOG1" => Opcode::LOG1,
+            "LOG2" => Opcode::LOG2,
+            "LOG3" => Opcode::LOG3,
+            "LOG4" => Opcode::LOG4,
+            "CREATE" => Opcode::CREATE,
+            "CALL" => Opcode::CALL,
+            "CALLCODE" => Opcode::CALLCODE,
+            "RETURN" => Opcode::RETURN,
+            "DELEGATECALL" => Opcode::DELEGATECALL,
+            "CREATE2" => Opcode::CREATE2,
+            "STATICCALL" => Opcode::STATICCALL,
+            "REVERT" => Opcode::REVERT,
+            "INVALID" => Opcode::INVALID,
+            "SELFDESTRUCT" => Opcode::SELFDESTRUCT,
+            "SHA3" => Opcode::KECCAK256,
+            _ => {
+                let code = mnemonic
+                    .strip_prefix("UNKNOWN(0X")
+                    .and_then(|rest| rest.strip_suffix(')'))
+                    .and_then(|code| u8::from_str_radix(code, 16).ok())
+                    .ok_or_else(|| ParseOpcodeError(s.to_string()))?;
+                Opcode::UNKNOWN(code)
+            }
+        };
+        Ok(opcode)
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    /// Formats the instruction as a listing line, e.g. `0004: PUSH1 0x80`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04x}: {}", self.pc, self.opcode)?;
+        if self.opcode.immediate_size() > 0 {
+            write!(f, " 0x")?;
+            for byte in &self.immediate {
+                write!(f, "{:02x}", byte)?;
+            }
+            if self.truncated {
+                write!(f, " (truncated)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -474,9 +1201,9 @@ mod tests {
 
     #[test]
     fn it_works() {
-        let ops = hex!("000102030405060708090a0b101112131415161718191a1b1c1d20303132333435363738393a3b3c3d3e3f4041424344454648505152535455565758595a5b606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9fa0a1a2a3a4f0f1f2f3f4f5fafdfeff").to_vec();
-        Opcode::disasm(ops)
-            .into_iter()
+        let ops = hex!("000102030405060708090a0b101112131415161718191a1b1c1d20303132333435363738393a3b3c3d3e3f4041424344454648505152535455565758595a5b606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9fa0a1a2a3a4f0f1f2f3f4f5fafdfeff");
+        ops.iter()
+            .map(|&op| Opcode::new_for(op, Hardfork::London))
             .zip(vec![
                 Opcode::STOP,
                 Opcode::ADD,
@@ -625,4 +1352,207 @@ mod tests {
                 assert_eq!(a, b);
             });
     }
+
+    #[test]
+    fn disasm_push_immediates() {
+        let code = hex!("6080604052600436106100");
+        let instructions = Opcode::disasm(&code);
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction {
+                    pc: 0,
+                    opcode: Opcode::PUSH1,
+                    immediate: vec![0x80],
+                    truncated: false,
+                },
+                Instruction {
+                    pc: 2,
+                    opcode: Opcode::PUSH1,
+                    immediate: vec![0x40],
+                    truncated: false,
+                },
+                Instruction {
+                    pc: 4,
+                    opcode: Opcode::MSTORE,
+                    immediate: vec![],
+                    truncated: false,
+                },
+                Instruction {
+                    pc: 5,
+                    opcode: Opcode::PUSH1,
+                    immediate: vec![0x04],
+                    truncated: false,
+                },
+                Instruction {
+                    pc: 7,
+                    opcode: Opcode::CALLDATASIZE,
+                    immediate: vec![],
+                    truncated: false,
+                },
+                Instruction {
+                    pc: 8,
+                    opcode: Opcode::LT,
+                    immediate: vec![],
+                    truncated: false,
+                },
+                Instruction {
+                    pc: 9,
+                    opcode: Opcode::PUSH2,
+                    immediate: vec![0x00],
+                    truncated: true,
+                },
+            ]
+        );
+        assert_eq!(instructions[6].next_pc(), 12);
+    }
+
+    #[test]
+    fn disasm_truncated_push_at_end() {
+        let instructions = Opcode::disasm(&hex!("7f"));
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].opcode, Opcode::PUSH32);
+        assert!(instructions[0].immediate.is_empty());
+        assert!(instructions[0].truncated);
+    }
+
+    #[test]
+    fn mnemonics_round_trip() {
+        for code in 0..=u8::MAX {
+            let opcode = Opcode::new(code);
+            assert_eq!(opcode.to_string().parse::<Opcode>(), Ok(opcode));
+        }
+        assert_eq!("sha3".parse::<Opcode>(), Ok(Opcode::KECCAK256));
+        assert!("PUSH33".parse::<Opcode>().is_err());
+    }
+
+    #[test]
+    fn listing_lines() {
+        let lines: Vec<String> = Opcode::disasm(&hex!("60806040520c61ff"))
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "0000: PUSH1 0x80",
+                "0002: PUSH1 0x40",
+                "0004: MSTORE",
+                "0005: UNKNOWN(0x0c)",
+                "0006: PUSH2 0xff (truncated)",
+            ]
+        );
+    }
+
+    #[test]
+    fn code_matches_new() {
+        for code in 0..=u8::MAX {
+            assert_eq!(Opcode::new(code).code(), code);
+        }
+        assert_eq!(Opcode::push(20), Some(Opcode::PUSH20));
+        assert_eq!(Opcode::push(33), None);
+    }
+
+    #[test]
+    fn hardfork_tables() {
+        let ops = hex!("4447495f5c5d5e4a");
+        let decode =
+            |fork| -> Vec<Opcode> { ops.iter().map(|&op| Opcode::new_for(op, fork)).collect() };
+        assert_eq!(
+            decode(Hardfork::Cancun),
+            vec![
+                Opcode::PREVRANDAO,
+                Opcode::SELFBALANCE,
+                Opcode::BLOBHASH,
+                Opcode::PUSH0,
+                Opcode::TLOAD,
+                Opcode::TSTORE,
+                Opcode::MCOPY,
+                Opcode::BLOBBASEFEE,
+            ]
+        );
+        assert_eq!(
+            decode(Hardfork::Shanghai),
+            vec![
+                Opcode::PREVRANDAO,
+                Opcode::SELFBALANCE,
+                Opcode::UNKNOWN(0x49),
+                Opcode::PUSH0,
+                Opcode::UNKNOWN(0x5c),
+                Opcode::UNKNOWN(0x5d),
+                Opcode::UNKNOWN(0x5e),
+                Opcode::UNKNOWN(0x4a),
+            ]
+        );
+        assert_eq!(Opcode::new_for(0x44, Hardfork::London), Opcode::DIFFICULTY);
+        assert_eq!(
+            Opcode::new_for(0x47, Hardfork::Petersburg),
+            Opcode::UNKNOWN(0x47)
+        );
+        assert_eq!(
+            Opcode::new_for(0xfd, Hardfork::Homestead),
+            Opcode::UNKNOWN(0xfd)
+        );
+        assert_eq!(Opcode::new(0x5e), Opcode::MCOPY);
+    }
+
+    #[test]
+    fn stack_io() {
+        let io = |opcode: Opcode| (opcode.stack_inputs(), opcode.stack_outputs());
+        assert_eq!(io(Opcode::ADD), (2, 1));
+        assert_eq!(io(Opcode::PUSH32), (0, 1));
+        assert_eq!(io(Opcode::DUP1), (1, 2));
+        assert_eq!(io(Opcode::DUP16), (16, 17));
+        assert_eq!(io(Opcode::SWAP1), (2, 2));
+        assert_eq!(io(Opcode::SWAP16), (17, 17));
+        assert_eq!(io(Opcode::LOG0), (2, 0));
+        assert_eq!(io(Opcode::LOG4), (6, 0));
+        assert_eq!(io(Opcode::CALL), (7, 1));
+        assert_eq!(io(Opcode::EXTCODECOPY), (4, 0));
+    }
+
+    #[test]
+    fn static_gas() {
+        assert_eq!(Opcode::ADD.static_gas(Hardfork::Frontier), 3);
+        assert_eq!(Opcode::PUSH0.static_gas(Hardfork::Shanghai), 2);
+        assert_eq!(Opcode::SWAP3.static_gas(Hardfork::Cancun), 3);
+        assert_eq!(Opcode::SLOAD.static_gas(Hardfork::Frontier), 50);
+        assert_eq!(Opcode::SLOAD.static_gas(Hardfork::Byzantium), 200);
+        assert_eq!(Opcode::SLOAD.static_gas(Hardfork::Istanbul), 800);
+        assert_eq!(Opcode::SLOAD.static_gas(Hardfork::Berlin), 100);
+        assert_eq!(Opcode::BALANCE.static_gas(Hardfork::Istanbul), 700);
+        assert_eq!(Opcode::CALL.static_gas(Hardfork::TangerineWhistle), 700);
+        assert_eq!(Opcode::SELFDESTRUCT.static_gas(Hardfork::Homestead), 0);
+        assert_eq!(Opcode::LOG2.static_gas(Hardfork::London), 1125);
+    }
+
+    #[test]
+    fn terminators() {
+        let terminators: Vec<Opcode> = (0..=u8::MAX)
+            .map(Opcode::new)
+            .filter(|opcode| opcode.is_terminator() && !matches!(opcode, Opcode::UNKNOWN(_)))
+            .collect();
+        assert_eq!(
+            terminators,
+            vec![
+                Opcode::STOP,
+                Opcode::JUMP,
+                Opcode::RETURN,
+                Opcode::REVERT,
+                Opcode::INVALID,
+                Opcode::SELFDESTRUCT,
+            ]
+        );
+        assert!(!Opcode::JUMPI.is_terminator());
+    }
+
+    #[test]
+    fn push_data_jumpdests() {
+        let instructions = Opcode::disasm(&hex!("5b625b005b5b"));
+        let flagged: Vec<Vec<usize>> = instructions
+            .iter()
+            .map(|instruction| instruction.push_data_jumpdests().collect())
+            .collect();
+        assert_eq!(flagged, vec![vec![], vec![2, 4], vec![]]);
+    }
 }
@@ -0,0 +1,121 @@
+/// Ethereum mainnet hardforks that changed the EVM, in activation order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Hardfork {
+    Frontier,
+    Homestead,
+    /// EIP-150.
+    TangerineWhistle,
+    /// EIP-158.
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    /// The Merge.
+    Paris,
+    Shanghai,
+    Cancun,
+    Prague,
+}
+
+impl Hardfork {
+    pub const LATEST: Hardfork = Hardfork::Prague;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hardfork::Frontier => "Frontier",
+            Hardfork::Homestead => "Homestead",
+            Hardfork::TangerineWhistle => "TangerineWhistle",
+            Hardfork::SpuriousDragon => "SpuriousDragon",
+            Hardfork::Byzantium => "Byzantium",
+            Hardfork::Constantinople => "Constantinople",
+            Hardfork::Petersburg => "Petersburg",
+            Hardfork::Istanbul => "Istanbul",
+            Hardfork::Berlin => "Berlin",
+            Hardfork::London => "London",
+            Hardfork::Paris => "Paris",
+            Hardfork::Shanghai => "Shanghai",
+            Hardfork::Cancun => "Cancun",
+            Hardfork::Prague => "Prague",
+        }
+    }
+}
+
+impl Default for Hardfork {
+    fn default() -> Self {
+        Hardfork::LATEST
+    }
+}
+
+impl std::fmt::Display for Hardfork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Error returned when a string does not name a hardfork.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseHardforkError(pub String);
+
+impl std::fmt::Display for ParseHardforkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown hardfork `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ParseHardforkError {}
+
+impl std::str::FromStr for Hardfork {
+    type Err = ParseHardforkError;
+
+    /// Parses a hardfork name, case-insensitively. The names used by ethereum/tests
+    /// (`EIP150`, `EIP158`, `ConstantinopleFix`, `Merge`) are accepted as well.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "frontier" => Ok(Hardfork::Frontier),
+            "homestead" => Ok(Hardfork::Homestead),
+            "tangerinewhistle" | "eip150" => Ok(Hardfork::TangerineWhistle),
+            "spuriousdragon" | "eip158" => Ok(Hardfork::SpuriousDragon),
+            "byzantium" => Ok(Hardfork::Byzantium),
+            "constantinople" => Ok(Hardfork::Constantinople),
+            "petersburg" | "constantinoplefix" => Ok(Hardfork::Petersburg),
+            "istanbul" => Ok(Hardfork::Istanbul),
+            "berlin" => Ok(Hardfork::Berlin),
+            "london" => Ok(Hardfork::London),
+            "paris" | "merge" => Ok(Hardfork::Paris),
+            "shanghai" => Ok(Hardfork::Shanghai),
+            "cancun" => Ok(Hardfork::Cancun),
+            "prague" => Ok(Hardfork::Prague),
+            _ => Err(ParseHardforkError(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_names() {
+        assert_eq!("Cancun".parse(), Ok(Hardfork::Cancun));
+        assert_eq!("merge".parse(), Ok(Hardfork::Paris));
+        assert_eq!("EIP150".parse(), Ok(Hardfork::TangerineWhistle));
+        assert_eq!(
+            "Osaka".parse::<Hardfork>(),
+            Err(ParseHardforkError("Osaka".to_string()))
+        );
+        assert_eq!(
+            Hardfork::Petersburg.to_string().parse(),
+            Ok(Hardfork::Petersburg)
+        );
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(Hardfork::Frontier < Hardfork::Homestead);
+        assert!(Hardfork::London < Hardfork::Paris);
+        assert_eq!(Hardfork::default(), Hardfork::Prague);
+    }
+}
@@ -0,0 +1,34 @@
+//! Hex strings, as given on the command line and in JSON files.
+
+/// Bytes of a hex string, with or without `0x`. `None` unless it is an even number of
+/// hex digits.
+pub fn decode(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Lowercase hex digits of `bytes`, without `0x`.
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(decode("0x00ff1a"), Some(vec![0x00, 0xff, 0x1a]));
+        assert_eq!(decode("00FF1A"), Some(vec![0x00, 0xff, 0x1a]));
+        assert_eq!(decode(""), Some(vec![]));
+        assert_eq!(decode("0x123"), None);
+        assert_eq!(decode("0xzz"), None);
+        assert_eq!(encode(&[0x00, 0xff, 0x1a]), "00ff1a");
+    }
+}
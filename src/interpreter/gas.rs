@@ -0,0 +1,386 @@
+use super::Address;
+use crate::disasm::Opcode;
+use crate::hardfork::Hardfork;
+use crate::u256::U256;
+use std::collections::{BTreeMap, HashMap};
+
+/// Extra cost of the first access to an account in a transaction (EIP-2929), on top
+/// of the warm access cost charged as static gas.
+pub const COLD_ACCOUNT_ACCESS: u64 = 2500;
+/// Extra cost of the first `SLOAD` or `SSTORE` of a slot in a transaction (EIP-2929).
+pub const COLD_SLOAD: u64 = 2100;
+/// Cost of a call that transfers value.
+pub const CALL_VALUE: u64 = 9000;
+/// Gas given to the callee on top of what is forwarded when a call transfers value.
+pub const CALL_STIPEND: u64 = 2300;
+/// Cost of creating an account, e.g. by sending value to an empty one.
+pub const NEW_ACCOUNT: u64 = 25000;
+/// `SELFDESTRUCT` refund, removed in London (EIP-3529).
+pub const SELFDESTRUCT_REFUND: u64 = 24000;
+/// Gas `SSTORE` requires to be left since Istanbul (EIP-2200).
+pub const SSTORE_SENTRY: u64 = 2300;
+/// Cost per byte of the code stored by a contract creation.
+pub const CODE_DEPOSIT: u64 = 200;
+/// Largest code a contract creation may store since Spurious Dragon (EIP-170).
+pub const MAX_CODE_SIZE: usize = 0x6000;
+/// Largest init code since Shanghai (EIP-3860).
+pub const MAX_INIT_CODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+/// Blob gas used by each blob of a transaction (EIP-4844).
+pub const BLOB_GAS_PER_BLOB: u64 = 1 << 17;
+
+/// Total cost of `words` 32-byte words of memory.
+pub fn memory_cost(words: u64) -> u64 {
+    3 * words + words * words / 512
+}
+
+/// Per-word cost of copying `len` bytes, as charged by the `*COPY` opcodes.
+pub fn copy_cost(len: usize) -> u64 {
+    3 * words(len)
+}
+
+/// Per-word cost of hashing `len` bytes with `KECCAK256`.
+pub fn keccak_cost(len: usize) -> u64 {
+    6 * words(len)
+}
+
+/// Per-word cost of init code since Shanghai (EIP-3860).
+pub fn init_code_cost(len: usize) -> u64 {
+    2 * words(len)
+}
+
+/// Per-byte cost of the data of a `LOGn`.
+pub fn log_cost(len: usize) -> u64 {
+    8 * len as u64
+}
+
+/// Cost of `EXP` on top of the static cost, per byte of the exponent. EIP-160 raised it
+/// from 10 to 50 in Spurious Dragon.
+pub fn exp_cost(fork: Hardfork, exponent: U256) -> u64 {
+    let per_byte = if fork >= Hardfork::SpuriousDragon {
+        50
+    } else {
+        10
+    };
+    per_byte * exponent.bits().div_ceil(8) as u64
+}
+
+/// Gas a call may forward: all but one 64th of what is left since Tangerine Whistle
+/// (EIP-150), capped by what the caller asked for. Before that a call asking for more
+/// than is left fails, signalled by `None`.
+pub fn max_call_gas(fork: Hardfork, gas_left: u64, requested: U256) -> Option<u64> {
+    if fork >= Hardfork::TangerineWhistle {
+        let available = gas_left - gas_left / 64;
+        Some(
+            requested
+                .to_u64()
+                .map_or(available, |gas| gas.min(available)),
+        )
+    } else {
+        requested.to_u64().filter(|&gas| gas <= gas_left)
+    }
+}
+
+/// Number of precompiled contracts, at addresses `0x01` to this number. The BLS12-381
+/// precompiles of Prague are not supported.
+pub fn precompile_count(fork: Hardfork) -> u8 {
+    match fork {
+        _ if fork >= Hardfork::Cancun => 10,
+        _ if fork >= Hardfork::Istanbul => 9,
+        _ if fork >= Hardfork::Byzantium => 8,
+        _ => 4,
+    }
+}
+
+/// Storage values seen by an `SSTORE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SstoreValues {
+    /// Value at the start of the transaction.
+    pub original: U256,
+    pub current: U256,
+    pub new: U256,
+}
+
+/// Cost and refund change of an `SSTORE`, excluding the cold access surcharge.
+///
+/// Implements the original rules, the net metering of Constantinople (EIP-1283) and
+/// Istanbul (EIP-2200), the repricing of Berlin (EIP-2929) and the reduced refunds of
+/// London (EIP-3529).
+pub fn sstore_cost(fork: Hardfork, values: SstoreValues) -> (u64, i64) {
+    let SstoreValues {
+        original,
+        current,
+        new,
+    } = values;
+    let net_metering = fork == Hardfork::Constantinople || fork >= Hardfork::Istanbul;
+    let clears: i64 = if fork >= Hardfork::London {
+        4800
+    } else {
+        15000
+    };
+    if !net_metering {
+        return if current.is_zero() && !new.is_zero() {
+            (20000, 0)
+        } else if !current.is_zero() && new.is_zero() {
+            (5000, clears)
+        } else {
+            (5000, 0)
+        };
+    }
+
+    let (noop, reset) = match fork {
+        _ if fork >= Hardfork::Berlin => (100, 5000 - COLD_SLOAD),
+        _ if fork >= Hardfork::Istanbul => (800, 5000),
+        _ => (200, 5000),
+    };
+    if current == new {
+        return (noop, 0);
+    }
+    if original == current {
+        if original.is_zero() {
+            return (20000, 0);
+        }
+        return (reset, if new.is_zero() { clears } else { 0 });
+    }
+    let mut refund = 0i64;
+    if !original.is_zero() {
+        if current.is_zero() {
+            refund -= clears;
+        } else if new.is_zero() {
+            refund += clears;
+        }
+    }
+    if original == new {
+        refund += (if original.is_zero() { 20000 } else { reset } - noop) as i64;
+    }
+    (noop, refund)
+}
+
+/// Largest refund applied at the end of a transaction: half the gas used before
+/// London, a fifth since (EIP-3529).
+pub fn max_refund(fork: Hardfork, gas_used: u64) -> u64 {
+    if fork >= Hardfork::London {
+        gas_used / 5
+    } else {
+        gas_used / 2
+    }
+}
+
+/// Gas charged for a transaction before any code runs: the base cost, its data,
+/// contract creation and the access list (EIP-2930). `access_list` holds the number of
+/// addresses and of storage keys.
+pub fn intrinsic_gas(
+    fork: Hardfork,
+    data: &[u8],
+    is_create: bool,
+    access_list: (usize, usize),
+) -> u64 {
+    // EIP-2028
+    let nonzero_byte = if fork >= Hardfork::Istanbul { 16 } else { 68 };
+    let zeros = data.iter().filter(|&&byte| byte == 0).count() as u64;
+    let mut gas = 21000 + 4 * zeros + nonzero_byte * (data.len() as u64 - zeros);
+    if is_create && fork >= Hardfork::Homestead {
+        gas += 32000;
+        if fork >= Hardfork::Shanghai {
+            gas += init_code_cost(data.len());
+        }
+    }
+    gas + 2400 * access_list.0 as u64 + 1900 * access_list.1 as u64
+}
+
+/// Least gas a transaction pays for its data since Prague (EIP-7623), zero before.
+pub fn floor_data_gas(fork: Hardfork, data: &[u8]) -> u64 {
+    if fork < Hardfork::Prague {
+        return 0;
+    }
+    let zeros = data.iter().filter(|&&byte| byte == 0).count() as u64;
+    let tokens = zeros + 4 * (data.len() as u64 - zeros);
+    21000 + 10 * tokens
+}
+
+/// Price of blob gas given the excess blob gas of the block (EIP-4844), with the
+/// update fraction raised in Prague (EIP-7691).
+pub fn blob_base_fee(fork: Hardfork, excess_blob_gas: u64) -> U256 {
+    let fraction = if fork >= Hardfork::Prague {
+        5007716
+    } else {
+        3338477
+    };
+    fake_exponential(
+        U256::ONE,
+        U256::from(excess_blob_gas),
+        U256::from(fraction as u64),
+    )
+}
+
+/// Approximates `factor * e ** (numerator / denominator)` with a Taylor expansion.
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let mut output = U256::ZERO;
+    let mut accumulator = factor * denominator;
+    let mut i = 1u64;
+    while !accumulator.is_zero() {
+        output = output + accumulator;
+        accumulator = accumulator * numerator / (denominator * U256::from(i));
+        i += 1;
+    }
+    output / denominator
+}
+
+fn words(len: usize) -> u64 {
+    len.div_ceil(32) as u64
+}
+
+/// Gas spent by one opcode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpcodeGas {
+    /// Number of times it was executed.
+    pub count: u64,
+    pub gas: u64,
+}
+
+/// Gas spent by the instruction at one program counter of one contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotspot {
+    /// Account the code was loaded from.
+    pub address: Address,
+    pub pc: usize,
+    pub opcode: Opcode,
+    pub gas: u64,
+}
+
+/// Gas spent during execution, broken down per opcode and per program counter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasReport {
+    pub by_opcode: HashMap<Opcode, OpcodeGas>,
+    pub by_pc: BTreeMap<(Address, usize), Hotspot>,
+}
+
+impl GasReport {
+    pub fn record(&mut self, address: Address, pc: usize, opcode: Opcode, gas: u64) {
+        let entry = self.by_opcode.entry(opcode).or_default();
+        entry.count += 1;
+        entry.gas += gas;
+        self.by_pc
+            .entry((address, pc))
+            .or_insert(Hotspot {
+                address,
+                pc,
+                opcode,
+                gas: 0,
+            })
+            .gas += gas;
+    }
+
+    /// Opcodes from the most to the least expensive.
+    pub fn opcodes(&self) -> Vec<(Opcode, OpcodeGas)> {
+        let mut opcodes: Vec<_> = self
+            .by_opcode
+            .iter()
+            .map(|(&opcode, &gas)| (opcode, gas))
+            .collect();
+        opcodes.sort_by(|a, b| b.1.gas.cmp(&a.1.gas).then(a.0.code().cmp(&b.0.code())));
+        opcodes
+    }
+
+    /// The `count` program counters that spent the most gas, most expensive first.
+    pub fn hotspots(&self, count: usize) -> Vec<Hotspot> {
+        let mut pcs: Vec<_> = self.by_pc.values().copied().collect();
+        pcs.sort_by(|a, b| {
+            b.gas
+                .cmp(&a.gas)
+                .then(a.address.cmp(&b.address))
+                .then(a.pc.cmp(&b.pc))
+        });
+        pcs.truncate(count);
+        pcs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sstore(fork: Hardfork, original: u64, current: u64, new: u64) -> (u64, i64) {
+        sstore_cost(
+            fork,
+            SstoreValues {
+                original: U256::from(original),
+                current: U256::from(current),
+                new: U256::from(new),
+            },
+        )
+    }
+
+    #[test]
+    fn sstore_costs() {
+        // Cases from the EIP-2200 and EIP-3529 tables.
+        assert_eq!(sstore(Hardfork::Petersburg, 0, 0, 1), (20000, 0));
+        assert_eq!(sstore(Hardfork::Petersburg, 1, 1, 0), (5000, 15000));
+        assert_eq!(sstore(Hardfork::Istanbul, 0, 0, 0), (800, 0));
+        assert_eq!(sstore(Hardfork::Istanbul, 1, 1, 0), (5000, 15000));
+        assert_eq!(sstore(Hardfork::Istanbul, 1, 2, 1), (800, 4200));
+        assert_eq!(sstore(Hardfork::Istanbul, 0, 1, 0), (800, 19200));
+        assert_eq!(sstore(Hardfork::Istanbul, 1, 0, 1), (800, -15000 + 4200));
+        assert_eq!(sstore(Hardfork::Constantinople, 1, 1, 1), (200, 0));
+        assert_eq!(sstore(Hardfork::Berlin, 1, 1, 2), (2900, 0));
+        assert_eq!(sstore(Hardfork::London, 1, 1, 0), (2900, 4800));
+        assert_eq!(sstore(Hardfork::London, 0, 1, 0), (100, 19900));
+        assert_eq!(sstore(Hardfork::London, 1, 0, 2), (100, -4800));
+    }
+
+    #[test]
+    fn dynamic_costs() {
+        assert_eq!(memory_cost(1), 3);
+        assert_eq!(memory_cost(1024), 3 * 1024 + 2048);
+        assert_eq!(copy_cost(33), 6);
+        assert_eq!(exp_cost(Hardfork::Homestead, U256::from(256u64)), 20);
+        assert_eq!(exp_cost(Hardfork::London, U256::from(256u64)), 100);
+        assert_eq!(exp_cost(Hardfork::London, U256::ZERO), 0);
+        assert_eq!(max_call_gas(Hardfork::London, 6400, U256::MAX), Some(6300));
+        assert_eq!(
+            max_call_gas(Hardfork::London, 6400, U256::from(100u64)),
+            Some(100)
+        );
+        assert_eq!(
+            max_call_gas(Hardfork::Homestead, 6400, U256::from(6401u64)),
+            None
+        );
+    }
+
+    #[test]
+    fn transaction_costs() {
+        let data = [0, 0, 1, 2];
+        assert_eq!(
+            intrinsic_gas(Hardfork::Frontier, &data, true, (0, 0)),
+            21144
+        );
+        assert_eq!(intrinsic_gas(Hardfork::Berlin, &data, false, (1, 2)), 27240);
+        assert_eq!(
+            intrinsic_gas(Hardfork::Shanghai, &data, true, (0, 0)),
+            53042
+        );
+        assert_eq!(floor_data_gas(Hardfork::Cancun, &data), 0);
+        assert_eq!(floor_data_gas(Hardfork::Prague, &data), 21100);
+        assert_eq!(blob_base_fee(Hardfork::Cancun, 0), U256::ONE);
+        assert_eq!(
+            blob_base_fee(Hardfork::Cancun, 10 * 3338477),
+            U256::from(22026u64)
+        );
+    }
+
+    #[test]
+    fn report_ordering() {
+        let mut report = GasReport::default();
+        report.record([1; 20], 0, Opcode::PUSH1, 3);
+        report.record([1; 20], 2, Opcode::SSTORE, 22100);
+        report.record([2; 20], 0, Opcode::PUSH1, 3);
+        report.record([1; 20], 0, Opcode::PUSH1, 3);
+        assert_eq!(report.opcodes()[0].0, Opcode::SSTORE);
+        assert_eq!(report.opcodes()[1].1, OpcodeGas { count: 3, gas: 9 });
+        let hotspots: Vec<_> = report
+            .hotspots(2)
+            .iter()
+            .map(|hotspot| (hotspot.address, hotspot.pc, hotspot.gas))
+            .collect();
+        assert_eq!(hotspots, vec![([1; 20], 2, 22100), ([1; 20], 0, 6)]);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::u256::U256;
+
+/// Byte-addressed memory of a call frame. Its size is always a multiple of 32 bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Memory {
+    data: Vec<u8>,
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory::default()
+    }
+
+    /// Size in bytes, as returned by `MSIZE`.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Expands memory to cover `len` bytes at `offset`. Accessing zero bytes never
+    /// expands memory, whatever the offset.
+    pub fn expand(&mut self, offset: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let end = (offset + len).div_ceil(32) * 32;
+        if end > self.data.len() {
+            self.data.resize(end, 0);
+        }
+    }
+
+    /// `len` bytes at `offset`. The range must have been expanded.
+    pub fn slice(&self, offset: usize, len: usize) -> &[u8] {
+        if len == 0 {
+            return &[];
+        }
+        &self.data[offset..offset + len]
+    }
+
+    pub fn load(&mut self, offset: usize) -> U256 {
+        self.expand(offset, 32);
+        U256::from_be_slice(self.slice(offset, 32))
+    }
+
+    pub fn store(&mut self, offset: usize, bytes: &[u8]) {
+        self.expand(offset, bytes.len());
+        if !bytes.is_empty() {
+            self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+    }
+
+    /// Copies `len` bytes of `source` starting at `source_offset` to `offset`, padding
+    /// with zeroes past the end of `source`, as the `*COPY` opcodes do.
+    pub fn store_padded(&mut self, offset: usize, source: &[u8], source_offset: usize, len: usize) {
+        self.expand(offset, len);
+        if len == 0 {
+            return;
+        }
+        let start = source_offset.min(source.len());
+        let end = source_offset.saturating_add(len).min(source.len());
+        let copied = end - start;
+        self.data[offset..offset + copied].copy_from_slice(&source[start..end]);
+        self.data[offset + copied..offset + len].fill(0);
+    }
+
+    /// `MCOPY`: copies within memory, handling overlapping ranges.
+    pub fn copy_within(&mut self, destination: usize, source: usize, len: usize) {
+        self.expand(destination.max(source), len);
+        if len > 0 {
+            self.data.copy_within(source..source + len, destination);
+        }
+    }
+}
@@ -0,0 +1,1559 @@
+pub mod gas;
+mod memory;
+pub mod precompiles;
+mod stack;
+pub mod state;
+pub mod trace;
+mod world;
+
+pub use gas::{GasReport, Hotspot};
+pub use memory::Memory;
+pub use precompiles::{Precompile, PrecompileOutput};
+pub use stack::{Stack, STACK_LIMIT};
+pub use state::{AllocError, State};
+pub use trace::{JsonTracer, Step, Tracer};
+pub use world::{Account, WorldState, EMPTY_CODE_HASH};
+
+use crate::address::{create2_address, create_address};
+use crate::disasm::Opcode;
+use crate::hardfork::Hardfork;
+use crate::jumptable::JumpTable;
+use crate::keccak::keccak256;
+use crate::rlp;
+use crate::u256::U256;
+use std::collections::BTreeMap;
+
+pub type Address = [u8; 20];
+
+/// Largest memory offset or size accepted. Anything above would run out of gas long
+/// before it could be paid for.
+const MEMORY_LIMIT: usize = u32::MAX as usize;
+
+/// Deepest call nesting allowed. Calls beyond it fail without executing.
+pub const CALL_DEPTH_LIMIT: usize = 1024;
+
+/// Block the code executes in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockEnv {
+    pub number: u64,
+    pub timestamp: u64,
+    pub coinbase: Address,
+    pub gas_limit: u64,
+    pub base_fee: U256,
+    /// Returned by `DIFFICULTY` before the merge.
+    pub difficulty: U256,
+    /// Returned by `PREVRANDAO` from the merge on.
+    pub prevrandao: U256,
+    pub chain_id: U256,
+    pub blob_base_fee: U256,
+    /// Hashes of recent blocks by number, returned by `BLOCKHASH`. Missing blocks
+    /// hash to zero.
+    pub hashes: BTreeMap<u64, U256>,
+}
+
+/// Accounts and storage keys a transaction warms up before executing (EIP-2930).
+pub type AccessList = Vec<(Address, Vec<U256>)>;
+
+/// Transaction the code executes in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxEnv {
+    pub origin: Address,
+    pub gas_price: U256,
+    pub blob_hashes: Vec<U256>,
+    /// Accounts and storage slots that start warm (EIP-2930).
+    pub access_list: AccessList,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Env {
+    pub block: BlockEnv,
+    pub tx: TxEnv,
+}
+
+/// Instruction or transaction that started a message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CallKind {
+    #[default]
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+    Create,
+    Create2,
+}
+
+impl CallKind {
+    /// Whether the message runs init code, whose output becomes the code of the
+    /// account it creates.
+    pub fn is_create(self) -> bool {
+        matches!(self, CallKind::Create | CallKind::Create2)
+    }
+}
+
+/// Message call executed by the interpreter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Message {
+    pub kind: CallKind,
+    pub caller: Address,
+    /// Account whose storage and balance the code operates on.
+    pub address: Address,
+    /// Account the code was loaded from. Differs from `address` for `CALLCODE` and
+    /// `DELEGATECALL`.
+    pub code_address: Address,
+    pub value: U256,
+    /// Call data.
+    pub data: Vec<u8>,
+    pub code: Vec<u8>,
+    pub gas: u64,
+    /// Set for `STATICCALL`: state modifications fail.
+    pub is_static: bool,
+    /// Number of calls between the transaction and this message, zero for the
+    /// message of the transaction itself.
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<U256>,
+    pub data: Vec<u8>,
+}
+
+impl rlp::Encode for Log {
+    /// `[address, [topic, ...], data]`, as in receipts.
+    fn encode(&self, out: &mut Vec<u8>) {
+        let topics: Vec<[u8; 32]> = self.topics.iter().map(U256::to_be_bytes).collect();
+        (&self.address, topics, &self.data).encode(out)
+    }
+}
+
+/// Exceptional halt. All remaining gas is consumed and state changes are reverted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecError {
+    StackUnderflow,
+    StackOverflow,
+    /// Jump to a destination that is not a `JUMPDEST`.
+    InvalidJump(U256),
+    /// `INVALID` or a byte that is not an opcode in the active hardfork.
+    InvalidOpcode(Opcode),
+    OutOfGas,
+    /// State modification inside a static call.
+    StaticStateChange,
+    /// `RETURNDATACOPY` past the end of the return data.
+    ReturnDataOutOfBounds,
+    /// Precompiled contract called with invalid input.
+    PrecompileFailed,
+    /// Precompiled contract that needs the `crypto` feature, which is disabled.
+    PrecompileUnavailable(Precompile),
+    /// Contract creation at an address that already has code or a nonce.
+    CreateCollision,
+    /// Init code above the size limit of EIP-3860.
+    InitCodeTooLarge,
+    /// Created code above the size limit of EIP-170.
+    CodeTooLarge,
+    /// Created code starting with `0xef`, reserved by EIP-3541.
+    InvalidCodePrefix,
+}
+
+impl std::fmt::Display for ExecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecError::StackUnderflow => write!(f, "stack underflow"),
+            ExecError::StackOverflow => write!(f, "stack overflow"),
+            ExecError::InvalidJump(target) => write!(f, "invalid jump destination {:#x}", target),
+            ExecError::InvalidOpcode(opcode) => write!(f, "invalid opcode {}", opcode),
+            ExecError::OutOfGas => write!(f, "out of gas"),
+            ExecError::StaticStateChange => write!(f, "state change in static call"),
+            ExecError::ReturnDataOutOfBounds => write!(f, "return data out of bounds"),
+            ExecError::PrecompileFailed => write!(f, "precompile failed"),
+            ExecError::PrecompileUnavailable(precompile) => {
+                write!(f, "precompile {:?} needs the `crypto` feature", precompile)
+            }
+            ExecError::CreateCollision => write!(f, "contract address collision"),
+            ExecError::InitCodeTooLarge => write!(f, "init code too large"),
+            ExecError::CodeTooLarge => write!(f, "code too large"),
+            ExecError::InvalidCodePrefix => write!(f, "code starts with 0xef"),
+        }
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+/// How execution ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Stop,
+    Return,
+    Revert,
+    SelfDestruct,
+    Error(ExecError),
+}
+
+impl Status {
+    /// Whether state changes are kept.
+    pub fn is_success(&self) -> bool {
+        matches!(self, Status::Stop | Status::Return | Status::SelfDestruct)
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Stop => write!(f, "stop"),
+            Status::Return => write!(f, "return"),
+            Status::Revert => write!(f, "revert"),
+            Status::SelfDestruct => write!(f, "selfdestruct"),
+            Status::Error(err) => write!(f, "error: {}", err),
+        }
+    }
+}
+
+/// Result of executing a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub status: Status,
+    /// Data passed to `RETURN` or `REVERT`.
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+    pub gas_left: u64,
+    /// Refund counter, before the cap applied at the end of the transaction. See
+    /// [`gas::max_refund`].
+    pub refund: u64,
+    /// Logs emitted, empty unless execution succeeded.
+    pub logs: Vec<Log>,
+}
+
+/// Execution context of a message: program counter, stack, memory and gas.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub message: Message,
+    pub pc: usize,
+    pub stack: Stack,
+    pub memory: Memory,
+    pub gas_left: u64,
+    /// Output of the last call made by this frame.
+    pub return_data: Vec<u8>,
+    /// Data passed to `RETURN` or `REVERT`.
+    pub output: Vec<u8>,
+    jumptable: JumpTable,
+    /// Memory of the caller the output is copied to.
+    output_range: (usize, usize),
+}
+
+impl Frame {
+    pub fn new(message: Message) -> Frame {
+        Frame {
+            jumptable: JumpTable::new(&message.code),
+            gas_left: message.gas,
+            message,
+            pc: 0,
+            stack: Stack::new(),
+            memory: Memory::new(),
+            return_data: Vec::new(),
+            output: Vec::new(),
+            output_range: (0, 0),
+        }
+    }
+
+    /// Opcode at the program counter. Running past the end of the code executes `STOP`.
+    pub fn opcode(&self, fork: Hardfork) -> Opcode {
+        match self.message.code.get(self.pc) {
+            Some(&code) => Opcode::new_for(code, fork),
+            None => Opcode::STOP,
+        }
+    }
+}
+
+/// What a frame rolls back besides the state when it fails or reverts.
+#[derive(Debug, Clone, Copy)]
+struct Checkpoint {
+    logs: usize,
+    refund: i64,
+}
+
+/// Executes a message against an in-memory world state.
+///
+/// Execution is driven one instruction at a time with [`Interpreter::step`], or to
+/// completion with [`Interpreter::run`]. Calls and contract creations run in frames of
+/// their own. When a frame fails or reverts, `state` is restored to what it was when
+/// the frame started. Ending the transaction with [`State::finalize`] is left to the
+/// caller.
+#[derive(Debug, Clone)]
+pub struct Interpreter {
+    pub fork: Hardfork,
+    pub env: Env,
+    pub state: State,
+    /// Frame currently executing.
+    pub frame: Frame,
+    /// Frames waiting for the call they made to return, outermost first.
+    pub callers: Vec<Frame>,
+    pub logs: Vec<Log>,
+    pub gas_report: GasReport,
+    /// Gas refunded at the end of the transaction. Can go negative temporarily when an
+    /// `SSTORE` undoes an earlier refund.
+    refund: i64,
+    /// What to restore when a frame fails, one per frame from the outermost.
+    checkpoints: Vec<Checkpoint>,
+    outcome: Option<Outcome>,
+}
+
+impl Interpreter {
+    pub fn new(fork: Hardfork, env: Env, mut state: State, message: Message) -> Interpreter {
+        if fork >= Hardfork::Berlin {
+            for address in [env.tx.origin, message.caller, message.address] {
+                state.warm_account(address);
+            }
+            for precompile in 1..=gas::precompile_count(fork) {
+                let mut address = [0; 20];
+                address[19] = precompile;
+                state.warm_account(address);
+            }
+            // EIP-3651
+            if fork >= Hardfork::Shanghai {
+                state.warm_account(env.block.coinbase);
+            }
+            for (address, keys) in &env.tx.access_list {
+                state.warm_account(*address);
+                for key in keys {
+                    state.warm_slot(*address, *key);
+                }
+            }
+        }
+        let mut interpreter = Interpreter {
+            fork,
+            env,
+            state,
+            frame: Frame::new(message),
+            callers: Vec::new(),
+            logs: Vec::new(),
+            gas_report: GasReport::default(),
+            refund: 0,
+            checkpoints: Vec::new(),
+            outcome: None,
+        };
+        let checkpoint = interpreter.checkpoint();
+        interpreter.checkpoints.push(checkpoint);
+        interpreter
+    }
+
+    /// Outcome of the execution, once it has halted.
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+
+    /// Runs until execution halts.
+    pub fn run(&mut self) -> Outcome {
+        loop {
+            if let Some(outcome) = self.step() {
+                return outcome;
+            }
+        }
+    }
+
+    /// Same as [`Interpreter::run`], reporting every step to `tracer`.
+    pub fn run_with(&mut self, tracer: &mut dyn Tracer) -> Outcome {
+        loop {
+            if let Some(outcome) = self.step_with(tracer) {
+                return outcome;
+            }
+        }
+    }
+
+    /// Executes the instruction at the program counter. Returns the outcome once
+    /// execution has halted; further calls return the same outcome.
+    pub fn step(&mut self) -> Option<Outcome> {
+        self.step_inner(None)
+    }
+
+    /// Same as [`Interpreter::step`], reporting the step to `tracer`.
+    pub fn step_with(&mut self, tracer: &mut dyn Tracer) -> Option<Outcome> {
+        self.step_inner(Some(tracer))
+    }
+
+    fn step_inner(&mut self, tracer: Option<&mut dyn Tracer>) -> Option<Outcome> {
+        if self.outcome.is_some() {
+            return self.outcome.clone();
+        }
+        let (pc, gas_left) = (self.frame.pc, self.frame.gas_left);
+        let opcode = self.frame.opcode(self.fork);
+        // The trace shows the state before the instruction executes.
+        let step = tracer.is_some().then(|| Step {
+            pc,
+            opcode,
+            gas: gas_left,
+            gas_cost: 0,
+            memory_size: self.frame.memory.len(),
+            stack: self.frame.stack.as_slice().to_vec(),
+            depth: self.frame.message.depth + 1,
+            refund: self.refund.max(0) as u64,
+            error: None,
+        });
+        let code_address = self.frame.message.code_address;
+        let depth = self.callers.len();
+        let result = self.execute();
+        // A call that starts a frame leaves the caller with the gas it did not forward.
+        // The report leaves the forwarded gas to the instructions of the callee.
+        let (gas_cost, forwarded) = match self.callers.get(depth) {
+            Some(caller) => {
+                let message = &self.frame.message;
+                let stipend = match message.kind {
+                    CallKind::Call | CallKind::CallCode if !message.value.is_zero() => {
+                        gas::CALL_STIPEND
+                    }
+                    _ => 0,
+                };
+                (gas_left - caller.gas_left, message.gas - stipend)
+            }
+            None => (gas_left - self.frame.gas_left, 0),
+        };
+        self.gas_report
+            .record(code_address, pc, opcode, gas_cost - forwarded);
+        let error = result.as_ref().err().map(ExecError::to_string);
+        let status = match result {
+            Ok(status) => status,
+            Err(err) => Some(Status::Error(err)),
+        };
+        if let Some(status) = status {
+            self.halt(status);
+        }
+
+        if let (Some(tracer), Some(mut step)) = (tracer, step) {
+            step.gas_cost = gas_cost;
+            step.error = error;
+            tracer.step(&step);
+            if let Some(outcome) = &self.outcome {
+                tracer.end(outcome);
+            }
+        }
+        self.outcome.clone()
+    }
+
+    /// Ends the current frame. Its result is handed to the calling frame, or becomes
+    /// the outcome when it is the outermost one.
+    fn halt(&mut self, mut status: Status) {
+        if status.is_success() && self.frame.message.kind.is_create() {
+            if let Err(err) = self.deposit_code() {
+                status = Status::Error(err);
+            }
+        }
+        let checkpoint = self
+            .checkpoints
+            .pop()
+            .expect("every frame has a checkpoint");
+        let mut output = std::mem::take(&mut self.frame.output);
+        if status.is_success() {
+            self.state.commit();
+        } else {
+            self.revert_to(checkpoint);
+            if let Status::Error(_) = status {
+                self.frame.gas_left = 0;
+                output.clear();
+            }
+        }
+
+        let caller = match self.callers.pop() {
+            Some(caller) => caller,
+            None => {
+                self.outcome = Some(Outcome {
+                    status,
+                    output,
+                    gas_used: self.frame.message.gas - self.frame.gas_left,
+                    gas_left: self.frame.gas_left,
+                    refund: self.refund.max(0) as u64,
+                    logs: self.logs.clone(),
+                });
+                return;
+            }
+        };
+        let callee = std::mem::replace(&mut self.frame, caller);
+        self.frame.gas_left += callee.gas_left;
+        let result = if callee.message.kind.is_create() {
+            self.frame.return_data = match status {
+                Status::Revert => output,
+                _ => Vec::new(),
+            };
+            match status.is_success() {
+                true => address_to_word(&callee.message.address),
+                false => U256::ZERO,
+            }
+        } else {
+            let (offset, len) = callee.output_range;
+            let len = len.min(output.len());
+            self.frame.memory.store(offset, &output[..len]);
+            self.frame.return_data = output;
+            U256::from(status.is_success())
+        };
+        // The call popped its arguments, so there is room for the result.
+        self.frame
+            .stack
+            .push(result)
+            .expect("call arguments were popped");
+    }
+
+    /// Stores the output of successful init code as the code of the created account.
+    fn deposit_code(&mut self) -> Result<(), ExecError> {
+        let code = &self.frame.output;
+        // EIP-170
+        if self.fork >= Hardfork::SpuriousDragon && code.len() > gas::MAX_CODE_SIZE {
+            return Err(ExecError::CodeTooLarge);
+        }
+        // EIP-3541
+        if self.fork >= Hardfork::London && code.first() == Some(&0xef) {
+            return Err(ExecError::InvalidCodePrefix);
+        }
+        let cost = gas::CODE_DEPOSIT * code.len() as u64;
+        if self.frame.gas_left < cost {
+            // Frontier creates the account without code instead of failing.
+            if self.fork >= Hardfork::Homestead {
+                return Err(ExecError::OutOfGas);
+            }
+            return Ok(());
+        }
+        self.frame.gas_left -= cost;
+        let address = self.frame.message.address;
+        self.state.set_code(address, code.clone());
+        Ok(())
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.state.checkpoint();
+        Checkpoint {
+            logs: self.logs.len(),
+            refund: self.refund,
+        }
+    }
+
+    fn revert_to(&mut self, checkpoint: Checkpoint) {
+        self.state.revert();
+        self.logs.truncate(checkpoint.logs);
+        self.refund = checkpoint.refund;
+    }
+
+    fn charge(&mut self, gas: u64) -> Result<(), ExecError> {
+        if self.frame.gas_left < gas {
+            return Err(ExecError::OutOfGas);
+        }
+        self.frame.gas_left -= gas;
+        Ok(())
+    }
+
+    /// Marks `address` as accessed. Returns whether it was cold, which is never the
+    /// case before Berlin.
+    fn access_account(&mut self, address: Address) -> bool {
+        self.fork >= Hardfork::Berlin && self.state.warm_account(address)
+    }
+
+    /// Marks a storage slot of the current account as accessed. Returns whether it was
+    /// cold, which is never the case before Berlin.
+    fn access_slot(&mut self, key: U256) -> bool {
+        let address = self.frame.message.address;
+        self.fork >= Hardfork::Berlin && self.state.warm_slot(address, key)
+    }
+
+    /// Pops an address and charges for accessing it.
+    fn pop_account(&mut self) -> Result<Address, ExecError> {
+        let address = word_to_address(self.pop()?);
+        if self.access_account(address) {
+            self.charge(gas::COLD_ACCOUNT_ACCESS)?;
+        }
+        Ok(address)
+    }
+
+    fn pop(&mut self) -> Result<U256, ExecError> {
+        self.frame.stack.pop()
+    }
+
+    fn pop_n<const N: usize>(&mut self) -> Result<[U256; N], ExecError> {
+        self.frame.stack.pop_n()
+    }
+
+    fn push(&mut self, value: U256) -> Result<(), ExecError> {
+        self.frame.stack.push(value)
+    }
+
+    fn push_address(&mut self, address: Address) -> Result<(), ExecError> {
+        self.push(address_to_word(&address))
+    }
+
+    fn unary(&mut self, op: impl FnOnce(U256) -> U256) -> Result<(), ExecError> {
+        let a = self.pop()?;
+        self.push(op(a))
+    }
+
+    /// Applies `op` to the top two items, the top of the stack first.
+    fn binary(&mut self, op: impl FnOnce(U256, U256) -> U256) -> Result<(), ExecError> {
+        let [a, b] = self.pop_n()?;
+        self.push(op(a, b))
+    }
+
+    fn ternary(&mut self, op: impl FnOnce(U256, U256, U256) -> U256) -> Result<(), ExecError> {
+        let [a, b, c] = self.pop_n()?;
+        self.push(op(a, b, c))
+    }
+
+    /// Validates the memory range `len` bytes at `offset`, charges for and performs the
+    /// memory expansion needed to cover it. Empty ranges are valid at any offset and
+    /// return offset zero.
+    fn memory_range(&mut self, offset: U256, len: U256) -> Result<(usize, usize), ExecError> {
+        if len.is_zero() {
+            return Ok((0, 0));
+        }
+        let offset = offset.to_usize().filter(|&offset| offset <= MEMORY_LIMIT);
+        let len = len.to_usize().filter(|&len| len <= MEMORY_LIMIT);
+        let (offset, len) = offset.zip(len).ok_or(ExecError::OutOfGas)?;
+        let words = (offset + len).div_ceil(32) as u64;
+        let current = (self.frame.memory.len() / 32) as u64;
+        if words > current {
+            self.charge(gas::memory_cost(words) - gas::memory_cost(current))?;
+        }
+        self.frame.memory.expand(offset, len);
+        Ok((offset, len))
+    }
+
+    fn require_non_static(&self) -> Result<(), ExecError> {
+        if self.frame.message.is_static {
+            return Err(ExecError::StaticStateChange);
+        }
+        Ok(())
+    }
+
+    /// Implements `CALLDATACOPY`, `CODECOPY` and `EXTCODECOPY` once the source has been
+    /// determined.
+    fn copy_to_memory(&mut self, source: &[u8]) -> Result<(), ExecError> {
+        let [dest, offset, len] = self.pop_n()?;
+        let (dest, len) = self.memory_range(dest, len)?;
+        self.charge(gas::copy_cost(len))?;
+        let offset = offset.to_usize().unwrap_or(usize::MAX);
+        self.frame.memory.store_padded(dest, source, offset, len);
+        Ok(())
+    }
+
+    fn jump(&mut self, target: U256) -> Result<usize, ExecError> {
+        match target.to_usize() {
+            Some(pc) if self.frame.jumptable.is_valid(pc) => Ok(pc),
+            _ => Err(ExecError::InvalidJump(target)),
+        }
+    }
+
+    /// Implements the `CALL` family. Precompiles run immediately, other targets with
+    /// code in a new frame.
+    fn call(&mut self, opcode: Opcode) -> Result<(), ExecError> {
+        let requested = self.pop()?;
+        let target = word_to_address(self.pop()?);
+        let value = match opcode {
+            Opcode::CALL | Opcode::CALLCODE => self.pop()?,
+            _ => U256::ZERO,
+        };
+        let [in_offset, in_len, out_offset, out_len] = self.pop_n()?;
+        let transfers_value = !value.is_zero();
+        if opcode == Opcode::CALL && transfers_value {
+            self.require_non_static()?;
+        }
+
+        let (in_offset, in_len) = self.memory_range(in_offset, in_len)?;
+        let (out_offset, out_len) = self.memory_range(out_offset, out_len)?;
+        if self.access_account(target) {
+            self.charge(gas::COLD_ACCOUNT_ACCESS)?;
+        }
+        if transfers_value {
+            self.charge(gas::CALL_VALUE)?;
+        }
+        // EIP-161 only charges for creating an account when value is sent to it.
+        let creates_account = match self.state.account(&target) {
+            _ if opcode != Opcode::CALL => false,
+            Some(account) if self.fork >= Hardfork::SpuriousDragon => {
+                account.is_empty() && transfers_value
+            }
+            None if self.fork >= Hardfork::SpuriousDragon => transfers_value,
+            Some(_) => false,
+            None => true,
+        };
+        if creates_account {
+            self.charge(gas::NEW_ACCOUNT)?;
+        }
+        let mut gas = gas::max_call_gas(self.fork, self.frame.gas_left, requested)
+            .ok_or(ExecError::OutOfGas)?;
+        self.charge(gas)?;
+        if transfers_value {
+            gas += gas::CALL_STIPEND;
+        }
+
+        self.frame.return_data.clear();
+        let current = &self.frame.message;
+        let (caller, address, value) = match opcode {
+            Opcode::CALL => (current.address, target, value),
+            Opcode::CALLCODE => (current.address, current.address, value),
+            Opcode::DELEGATECALL => (current.caller, current.address, current.value),
+            _ => (current.address, target, U256::ZERO),
+        };
+        if current.depth >= CALL_DEPTH_LIMIT
+            || (transfers_value && self.state.balance(&current.address) < value)
+        {
+            self.frame.gas_left += gas;
+            return self.push(U256::ZERO);
+        }
+        let input = self.frame.memory.slice(in_offset, in_len).to_vec();
+
+        if let Some(precompile) = Precompile::at(self.fork, &target) {
+            return match precompile.run(self.fork, &input, gas) {
+                Ok(PrecompileOutput { gas_used, output }) => {
+                    self.frame.gas_left += gas - gas_used;
+                    if opcode == Opcode::CALL {
+                        self.state.transfer(caller, address, value);
+                    }
+                    let len = out_len.min(output.len());
+                    self.frame.memory.store(out_offset, &output[..len]);
+                    self.frame.return_data = output;
+                    self.push(U256::ONE)
+                }
+                Err(_) => self.push(U256::ZERO),
+            };
+        }
+
+        let checkpoint = self.checkpoint();
+        if opcode == Opcode::CALL {
+            self.state.transfer(caller, address, value);
+        }
+        let code = self.state.code(&target).to_vec();
+        if code.is_empty() {
+            self.state.commit();
+            self.frame.gas_left += gas;
+            return self.push(U256::ONE);
+        }
+        let message = Message {
+            kind: match opcode {
+                Opcode::CALL => CallKind::Call,
+                Opcode::CALLCODE => CallKind::CallCode,
+                Opcode::DELEGATECALL => CallKind::DelegateCall,
+                _ => CallKind::StaticCall,
+            },
+            caller,
+            address,
+            code_address: target,
+            value,
+            data: input,
+            code,
+            gas,
+            is_static: self.frame.message.is_static || opcode == Opcode::STATICCALL,
+            depth: self.frame.message.depth + 1,
+        };
+        let mut frame = Frame::new(message);
+        frame.output_range = (out_offset, out_len);
+        self.enter(frame, checkpoint);
+        Ok(())
+    }
+
+    /// Implements `CREATE` and `CREATE2`, running the init code in a new frame.
+    fn create(&mut self, opcode: Opcode) -> Result<(), ExecError> {
+        self.require_non_static()?;
+        let [value, offset, len] = self.pop_n()?;
+        let salt = match opcode {
+            Opcode::CREATE2 => Some(self.pop()?),
+            _ => None,
+        };
+        let (offset, len) = self.memory_range(offset, len)?;
+        if self.fork >= Hardfork::Shanghai {
+            if len > gas::MAX_INIT_CODE_SIZE {
+                return Err(ExecError::InitCodeTooLarge);
+            }
+            self.charge(gas::init_code_cost(len))?;
+        }
+        if salt.is_some() {
+            self.charge(gas::keccak_cost(len))?;
+        }
+        let gas = match gas::max_call_gas(self.fork, self.frame.gas_left, U256::MAX) {
+            Some(gas) => gas,
+            None => self.frame.gas_left,
+        };
+
+        self.frame.return_data.clear();
+        let creator = self.frame.message.address;
+        let nonce = self.state.nonce(&creator);
+        if self.frame.message.depth >= CALL_DEPTH_LIMIT
+            || self.state.balance(&creator) < value
+            || nonce == u64::MAX
+        {
+            return self.push(U256::ZERO);
+        }
+        self.charge(gas)?;
+        self.state.increment_nonce(creator);
+        let code = self.frame.memory.slice(offset, len).to_vec();
+        let address = match salt {
+            Some(salt) => create2_address(&creator, salt, &code),
+            None => create_address(&creator, nonce),
+        };
+        self.access_account(address);
+        if self
+            .state
+            .account(&address)
+            .is_some_and(Account::has_collision)
+        {
+            return self.push(U256::ZERO);
+        }
+
+        let checkpoint = self.checkpoint();
+        self.state.create_account(address);
+        // EIP-161
+        if self.fork >= Hardfork::SpuriousDragon {
+            self.state.set_nonce(address, 1);
+        }
+        self.state.transfer(creator, address, value);
+        let message = Message {
+            kind: match opcode {
+                Opcode::CREATE2 => CallKind::Create2,
+                _ => CallKind::Create,
+            },
+            caller: creator,
+            address,
+            code_address: address,
+            value,
+            data: Vec::new(),
+            code,
+            gas,
+            is_static: false,
+            depth: self.frame.message.depth + 1,
+        };
+        self.enter(Frame::new(message), checkpoint);
+        Ok(())
+    }
+
+    /// Suspends the current frame and starts executing `frame`.
+    fn enter(&mut self, frame: Frame, checkpoint: Checkpoint) {
+        let caller = std::mem::replace(&mut self.frame, frame);
+        self.callers.push(caller);
+        self.checkpoints.push(checkpoint);
+    }
+
+    /// Executes one instruction. Returns the status when execution halts normally.
+    fn execute(&mut self) -> Result<Option<Status>, ExecError> {
+        let opcode = self.frame.opcode(self.fork);
+        let height = self.frame.stack.len();
+        if height < opcode.stack_inputs() {
+            return Err(ExecError::StackUnderflow);
+        }
+        if height - opcode.stack_inputs() + opcode.stack_outputs() > STACK_LIMIT {
+            return Err(ExecError::StackOverflow);
+        }
+        self.charge(opcode.static_gas(self.fork))?;
+
+        let pc = self.frame.pc;
+        let mut next = pc + 1;
+        let code = opcode.code();
+        match opcode {
+            Opcode::STOP => return Ok(Some(Status::Stop)),
+            Opcode::ADD => self.binary(|a, b| a + b)?,
+            Opcode::MUL => self.binary(|a, b| a * b)?,
+            Opcode::SUB => self.binary(|a, b| a - b)?,
+            Opcode::DIV => self.binary(|a, b| a / b)?,
+            Opcode::SDIV => self.binary(U256::sdiv)?,
+            Opcode::MOD => self.binary(|a, b| a % b)?,
+            Opcode::SMOD => self.binary(U256::smod)?,
+            Opcode::ADDMOD => self.ternary(U256::addmod)?,
+            Opcode::MULMOD => self.ternary(U256::mulmod)?,
+            Opcode::EXP => {
+                let exponent = self.frame.stack.peek(1)?;
+                self.charge(gas::exp_cost(self.fork, exponent))?;
+                self.binary(U256::pow)?;
+            }
+            Opcode::SIGNEXTEND => self.binary(|byte, value| value.signextend(byte))?,
+
+            Opcode::LT => self.binary(|a, b| U256::from(a < b))?,
+            Opcode::GT => self.binary(|a, b| U256::from(a > b))?,
+            Opcode::SLT => self.binary(|a, b| U256::from(a.signed_cmp(&b).is_lt()))?,
+            Opcode::SGT => self.binary(|a, b| U256::from(a.signed_cmp(&b).is_gt()))?,
+            Opcode::EQ => self.binary(|a, b| U256::from(a == b))?,
+            Opcode::ISZERO => self.unary(|a| U256::from(a.is_zero()))?,
+            Opcode::AND => self.binary(|a, b| a & b)?,
+            Opcode::OR => self.binary(|a, b| a | b)?,
+            Opcode::XOR => self.binary(|a, b| a ^ b)?,
+            Opcode::NOT => self.unary(|a| !a)?,
+            Opcode::BYTE => self.binary(|index, value| value.byte(index))?,
+            Opcode::SHL => self.binary(|shift, value| value.shl_word(shift))?,
+            Opcode::SHR => self.binary(|shift, value| value.shr_word(shift))?,
+            Opcode::SAR => self.binary(|shift, value| value.sar(shift))?,
+
+            Opcode::KECCAK256 => {
+                let [offset, len] = self.pop_n()?;
+                let (offset, len) = self.memory_range(offset, len)?;
+                self.charge(gas::keccak_cost(len))?;
+                let hash = keccak256(self.frame.memory.slice(offset, len));
+                self.push(U256::from_be_bytes(hash))?;
+            }
+
+            Opcode::ADDRESS => self.push_address(self.frame.message.address)?,
+            Opcode::BALANCE => {
+                let address = self.pop_account()?;
+                self.push(self.state.balance(&address))?;
+            }
+            Opcode::ORIGIN => self.push_address(self.env.tx.origin)?,
+            Opcode::CALLER => self.push_address(self.frame.message.caller)?,
+            Opcode::CALLVALUE => self.push(self.frame.message.value)?,
+            Opcode::CALLDATALOAD => {
+                let offset = self.pop()?.to_usize().unwrap_or(usize::MAX);
+                let data = &self.frame.message.data;
+                let mut word = [0; 32];
+                if offset < data.len() {
+                    let len = (data.len() - offset).min(32);
+                    word[..len].copy_from_slice(&data[offset..offset + len]);
+                }
+                self.push(U256::from_be_bytes(word))?;
+            }
+            Opcode::CALLDATASIZE => self.push(U256::from(self.frame.message.data.len()))?,
+            Opcode::CALLDATACOPY => {
+                let data = std::mem::take(&mut self.frame.message.data);
+                let result = self.copy_to_memory(&data);
+                self.frame.message.data = data;
+                result?;
+            }
+            Opcode::CODESIZE => self.push(U256::from(self.frame.message.code.len()))?,
+            Opcode::CODECOPY => {
+                let code = std::mem::take(&mut self.frame.message.code);
+                let result = self.copy_to_memory(&code);
+                self.frame.message.code = code;
+                result?;
+            }
+            Opcode::GASPRICE => self.push(self.env.tx.gas_price)?,
+            Opcode::EXTCODESIZE => {
+                let address = self.pop_account()?;
+                self.push(U256::from(self.state.code(&address).len()))?;
+            }
+            Opcode::EXTCODECOPY => {
+                let address = self.pop_account()?;
+                let code = self.state.code(&address).to_vec();
+                self.copy_to_memory(&code)?;
+            }
+            Opcode::RETURNDATASIZE => self.push(U256::from(self.frame.return_data.len()))?,
+            Opcode::RETURNDATACOPY => {
+                let [dest, offset, len] = self.pop_n()?;
+                let end = offset
+                    .checked_add(len)
+                    .and_then(|end| end.to_usize())
+                    .filter(|&end| end <= self.frame.return_data.len())
+                    .ok_or(ExecError::ReturnDataOutOfBounds)?;
+                let (dest, len) = self.memory_range(dest, len)?;
+                self.charge(gas::copy_cost(len))?;
+                let data = self.frame.return_data[end - len..end].to_vec();
+                self.frame.memory.store(dest, &data);
+            }
+            Opcode::EXTCODEHASH => {
+                let address = self.pop_account()?;
+                let hash = match self.state.account(&address) {
+                    Some(account) if !account.is_empty() => {
+                        U256::from_be_bytes(account.code_hash())
+                    }
+                    _ => U256::ZERO,
+                };
+                self.push(hash)?;
+            }
+
+            Opcode::BLOCKHASH => {
+                let number = self.pop()?;
+                let current = self.env.block.number;
+                let hash = match number.to_u64() {
+                    Some(number) if number < current && current - number <= 256 => self
+                        .env
+                        .block
+                        .hashes
+                        .get(&number)
+                        .copied()
+                        .unwrap_or_default(),
+                    _ => U256::ZERO,
+                };
+                self.push(hash)?;
+            }
+            Opcode::COINBASE => self.push_address(self.env.block.coinbase)?,
+            Opcode::TIMESTAMP => self.push(U256::from(self.env.block.timestamp))?,
+            Opcode::NUMBER => self.push(U256::from(self.env.block.number))?,
+            Opcode::DIFFICULTY => self.push(self.env.block.difficulty)?,
+            Opcode::PREVRANDAO => self.push(self.env.block.prevrandao)?,
+            Opcode::GASLIMIT => self.push(U256::from(self.env.block.gas_limit))?,
+            Opcode::CHAINID => self.push(self.env.block.chain_id)?,
+            Opcode::SELFBALANCE => {
+                let balance = self.state.balance(&self.frame.message.address);
+                self.push(balance)?;
+            }
+            Opcode::BASEFEE => self.push(self.env.block.base_fee)?,
+            Opcode::BLOBHASH => {
+                let index = self.pop()?;
+                let hash = index
+                    .to_usize()
+                    .and_then(|index| self.env.tx.blob_hashes.get(index))
+                    .copied()
+                    .unwrap_or_default();
+                self.push(hash)?;
+            }
+            Opcode::BLOBBASEFEE => self.push(self.env.block.blob_base_fee)?,
+
+            Opcode::POP => {
+                self.pop()?;
+            }
+            Opcode::MLOAD => {
+                let offset = self.pop()?;
+                let (offset, _) = self.memory_range(offset, U256::from(32u64))?;
+                let value = self.frame.memory.load(offset);
+                self.push(value)?;
+            }
+            Opcode::MSTORE => {
+                let [offset, value] = self.pop_n()?;
+                let (offset, _) = self.memory_range(offset, U256::from(32u64))?;
+                self.frame.memory.store(offset, &value.to_be_bytes());
+            }
+            Opcode::MSTORE8 => {
+                let [offset, value] = self.pop_n()?;
+                let (offset, _) = self.memory_range(offset, U256::ONE)?;
+                self.frame.memory.store(offset, &[value.low_u64() as u8]);
+            }
+            Opcode::SLOAD => {
+                let key = self.pop()?;
+                if self.access_slot(key) {
+                    // The warm cost is charged as static gas.
+                    self.charge(gas::COLD_SLOAD - 100)?;
+                }
+                let value = self.state.storage(&self.frame.message.address, key);
+                self.push(value)?;
+            }
+            Opcode::SSTORE => {
+                self.require_non_static()?;
+                if self.fork >= Hardfork::Istanbul && self.frame.gas_left <= gas::SSTORE_SENTRY {
+                    return Err(ExecError::OutOfGas);
+                }
+                let [key, new] = self.pop_n()?;
+                if self.access_slot(key) {
+                    self.charge(gas::COLD_SLOAD)?;
+                }
+                let address = self.frame.message.address;
+                let values = gas::SstoreValues {
+                    original: self.state.original_storage(&address, key),
+                    current: self.state.storage(&address, key),
+                    new,
+                };
+                let (cost, refund) = gas::sstore_cost(self.fork, values);
+                self.charge(cost)?;
+                self.refund += refund;
+                self.state.set_storage(address, key, new);
+            }
+            Opcode::JUMP => {
+                let target = self.pop()?;
+                next = self.jump(target)?;
+            }
+            Opcode::JUMPI => {
+                let [target, condition] = self.pop_n()?;
+                if !condition.is_zero() {
+                    next = self.jump(target)?;
+                }
+            }
+            Opcode::PC => self.push(U256::from(pc))?,
+            Opcode::MSIZE => self.push(U256::from(self.frame.memory.len()))?,
+            Opcode::GAS => self.push(U256::from(self.frame.gas_left))?,
+            Opcode::JUMPDEST => {}
+            Opcode::TLOAD => {
+                let key = self.pop()?;
+                let address = self.frame.message.address;
+                let value = self.state.transient_storage(&address, key);
+                self.push(value)?;
+            }
+            Opcode::TSTORE => {
+                self.require_non_static()?;
+                let [key, value] = self.pop_n()?;
+                let address = self.frame.message.address;
+                self.state.set_transient_storage(address, key, value);
+            }
+            Opcode::MCOPY => {
+                let [dest, source, len] = self.pop_n()?;
+                let (dest, len) = self.memory_range(dest, len)?;
+                let (source, _) = self.memory_range(source, U256::from(len))?;
+                self.charge(gas::copy_cost(len))?;
+                self.frame.memory.copy_within(dest, source, len);
+            }
+
+            Opcode::PUSH0 => self.push(U256::ZERO)?,
+            _ if opcode.immediate_size() > 0 => {
+                let size = opcode.immediate_size();
+                let code = &self.frame.message.code;
+                let start = (pc + 1).min(code.len());
+                let end = (pc + 1 + size).min(code.len());
+                // Missing bytes at the end of the code read as zeroes.
+                let mut bytes = [0; 32];
+                bytes[..end - start].copy_from_slice(&code[start..end]);
+                self.push(U256::from_be_slice(&bytes[..size]))?;
+                next = pc + 1 + size;
+            }
+            _ if (0x80..=0x8f).contains(&code) => self.frame.stack.dup((code - 0x7f) as usize)?,
+            _ if (0x90..=0x9f).contains(&code) => self.frame.stack.swap((code - 0x8f) as usize)?,
+
+            Opcode::LOG0 | Opcode::LOG1 | Opcode::LOG2 | Opcode::LOG3 | Opcode::LOG4 => {
+                self.require_non_static()?;
+                let [offset, len] = self.pop_n()?;
+                let mut topics = Vec::new();
+                for _ in 0..code - 0xa0 {
+                    topics.push(self.pop()?);
+                }
+                let (offset, len) = self.memory_range(offset, len)?;
+                self.charge(gas::log_cost(len))?;
+                self.logs.push(Log {
+                    address: self.frame.message.address,
+                    topics,
+                    data: self.frame.memory.slice(offset, len).to_vec(),
+                });
+            }
+
+            Opcode::RETURN | Opcode::REVERT => {
+                let [offset, len] = self.pop_n()?;
+                let (offset, len) = self.memory_range(offset, len)?;
+                self.frame.output = self.frame.memory.slice(offset, len).to_vec();
+                return Ok(Some(if opcode == Opcode::RETURN {
+                    Status::Return
+                } else {
+                    Status::Revert
+                }));
+            }
+            Opcode::SELFDESTRUCT => {
+                self.require_non_static()?;
+                let beneficiary = word_to_address(self.pop()?);
+                if self.access_account(beneficiary) {
+                    // No warm cost is included in the static gas of `SELFDESTRUCT`.
+                    self.charge(gas::COLD_ACCOUNT_ACCESS + 100)?;
+                }
+                let address = self.frame.message.address;
+                let balance = self.state.balance(&address);
+                let creates_account = match self.state.account(&beneficiary) {
+                    _ if self.fork < Hardfork::TangerineWhistle => false,
+                    // EIP-161
+                    Some(account) if self.fork >= Hardfork::SpuriousDragon => {
+                        account.is_empty() && !balance.is_zero()
+                    }
+                    None if self.fork >= Hardfork::SpuriousDragon => !balance.is_zero(),
+                    Some(_) => false,
+                    None => true,
+                };
+                if creates_account {
+                    self.charge(gas::NEW_ACCOUNT)?;
+                }
+                if self.fork < Hardfork::London && !self.state.is_destructed(&address) {
+                    self.refund += gas::SELFDESTRUCT_REFUND as i64;
+                }
+                self.state.selfdestruct(self.fork, address, beneficiary);
+                return Ok(Some(Status::SelfDestruct));
+            }
+            // Both may switch to a new frame, so the caller resumes after them.
+            Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL => {
+                self.frame.pc = next;
+                self.call(opcode)?;
+                return Ok(None);
+            }
+            Opcode::CREATE | Opcode::CREATE2 => {
+                self.frame.pc = next;
+                self.create(opcode)?;
+                return Ok(None);
+            }
+            _ => return Err(ExecError::InvalidOpcode(opcode)),
+        }
+        self.frame.pc = next;
+        Ok(None)
+    }
+}
+
+/// Address as a stack word, right-aligned.
+pub fn address_to_word(address: &Address) -> U256 {
+    U256::from_be_slice(address)
+}
+
+/// Address held in the low 20 bytes of a stack word.
+pub fn word_to_address(word: U256) -> Address {
+    let mut address = [0; 20];
+    address.copy_from_slice(&word.to_be_bytes()[12..]);
+    address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use hex_literal::hex;
+
+    const CONTRACT: Address = [0xcc; 20];
+
+    fn execute(source: &str, state: WorldState) -> Interpreter {
+        let message = Message {
+            address: CONTRACT,
+            code_address: CONTRACT,
+            code: assemble(source).unwrap(),
+            gas: 100_000,
+            ..Message::default()
+        };
+        let mut interpreter =
+            Interpreter::new(Hardfork::default(), Env::default(), state.into(), message);
+        interpreter.run();
+        interpreter
+    }
+
+    fn outcome(source: &str) -> Outcome {
+        execute(source, WorldState::new())
+            .outcome()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn arithmetic_and_return() {
+        let outcome = outcome(
+            "PUSH1 0x02\nPUSH1 0x03\nADD\nPUSH1 0x04\nMUL\nPUSH0\nMSTORE\nPUSH1 0x20\nPUSH0\nRETURN",
+        );
+        assert_eq!(outcome.status, Status::Return);
+        assert_eq!(U256::from_be_slice(&outcome.output), U256::from(20u64));
+        // Static costs plus one word of memory.
+        assert_eq!(outcome.gas_used, 27 + 3);
+    }
+
+    #[test]
+    fn dynamic_gas() {
+        // PUSH1, PUSH0, cold SSTORE from zero, PUSH0, warm SLOAD, POP, then the same
+        // slot is cleared again.
+        let interpreter = execute(
+            "PUSH1 0x01\nPUSH0\nSSTORE\nPUSH0\nSLOAD\nPOP\nPUSH0\nPUSH0\nSSTORE",
+            WorldState::new(),
+        );
+        let stored = interpreter.outcome().unwrap();
+        assert_eq!(stored.gas_used, 3 + 2 + 22100 + 2 + 100 + 2 + 2 + 2 + 100);
+        assert_eq!(stored.refund, 19900);
+        assert_eq!(interpreter.gas_report.by_pc[&(CONTRACT, 3)].gas, 22100);
+        assert_eq!(interpreter.gas_report.opcodes()[0].1.count, 2);
+
+        // EXP with a two-byte exponent, then cold BALANCE and KECCAK256 of 33 bytes.
+        let outcome =
+            outcome("PUSH2 0x0100\nPUSH1 0x03\nEXP\nBALANCE\nPUSH1 0x21\nPUSH0\nKECCAK256");
+        assert_eq!(
+            outcome.gas_used,
+            3 + 3 + 10 + 100 + 2600 + 3 + 2 + 30 + 12 + 6
+        );
+    }
+
+    #[test]
+    fn storage_and_logs() {
+        let interpreter = execute(
+            "PUSH1 0x2a\nPUSH1 0x01\nSSTORE\nPUSH1 0x07\nPUSH0\nPUSH0\nLOG1\nSTOP",
+            WorldState::new(),
+        );
+        let outcome = interpreter.outcome().unwrap();
+        assert_eq!(outcome.status, Status::Stop);
+        assert_eq!(
+            interpreter.state.storage(&CONTRACT, U256::ONE),
+            U256::from(42u64)
+        );
+        assert_eq!(
+            outcome.logs,
+            vec![Log {
+                address: CONTRACT,
+                topics: vec![U256::from(7u64)],
+                data: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn revert_rolls_back_state() {
+        let mut state = WorldState::new();
+        state.set_storage(CONTRACT, U256::ONE, U256::ONE);
+        let interpreter = execute(
+            "PUSH1 0x2a\nPUSH1 0x01\nSSTORE\nPUSH1 0xff\nPUSH0\nMSTORE8\nPUSH1 0x01\nPUSH0\nREVERT",
+            state.clone(),
+        );
+        let outcome = interpreter.outcome().unwrap();
+        assert_eq!(outcome.status, Status::Revert);
+        assert_eq!(outcome.output, vec![0xff]);
+        assert!(outcome.gas_left > 0);
+        assert_eq!(interpreter.state.world(), &state);
+    }
+
+    #[test]
+    fn exceptional_halts() {
+        let invalid_jump = outcome("PUSH1 0x02\nJUMP\nJUMPDEST");
+        assert_eq!(
+            invalid_jump.status,
+            Status::Error(ExecError::InvalidJump(U256::from(2u64)))
+        );
+        assert_eq!(invalid_jump.gas_left, 0);
+        assert_eq!(
+            outcome("ADD").status,
+            Status::Error(ExecError::StackUnderflow)
+        );
+        assert_eq!(
+            outcome("INVALID").status,
+            Status::Error(ExecError::InvalidOpcode(Opcode::INVALID))
+        );
+        assert_eq!(
+            outcome("@loop:\nJUMPDEST\nPUSH @loop\nJUMP").status,
+            Status::Error(ExecError::OutOfGas)
+        );
+        assert_eq!(
+            outcome("PUSH1 0x01\nPUSH0\nPUSH0\nRETURNDATACOPY").status,
+            Status::Error(ExecError::ReturnDataOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn json_trace() {
+        let message = Message {
+            code: assemble("PUSH1 0x01\nPUSH1 0x02\nADD\nPOP\nPOP").unwrap(),
+            gas: 100,
+            ..Message::default()
+        };
+        let mut interpreter =
+            Interpreter::new(Hardfork::default(), Env::default(), State::new(), message);
+        let mut tracer = JsonTracer::new(Vec::new());
+        interpreter.run_with(&mut tracer);
+        let trace = String::from_utf8(tracer.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[2],
+            r#"{"pc":4,"op":1,"gas":"0x5e","gasCost":"0x3","memSize":0,"stack":["0x1","0x2"],"depth":1,"refund":0,"opName":"ADD"}"#
+        );
+        assert!(lines[4].ends_with(r#""opName":"POP","error":"stack underflow"}"#));
+        assert_eq!(
+            lines[5],
+            r#"{"output":"","gasUsed":"0x64","pass":false,"error":"stack underflow"}"#
+        );
+    }
+
+    #[test]
+    fn static_calls_cannot_write() {
+        let message = Message {
+            code: assemble("PUSH0\nPUSH0\nSSTORE").unwrap(),
+            gas: 100_000,
+            is_static: true,
+            ..Message::default()
+        };
+        let mut interpreter =
+            Interpreter::new(Hardfork::default(), Env::default(), State::new(), message);
+        assert_eq!(
+            interpreter.run().status,
+            Status::Error(ExecError::StaticStateChange)
+        );
+    }
+
+    #[test]
+    fn calldata_and_truncated_push() {
+        let message = Message {
+            data: vec![0xaa, 0xbb],
+            code: assemble(
+                "PUSH1 0x04\nPUSH0\nPUSH1 0x1e\nCALLDATACOPY\nPUSH1 0x20\nPUSH0\nRETURN",
+            )
+            .unwrap(),
+            gas: 100_000,
+            ..Message::default()
+        };
+        let mut interpreter =
+            Interpreter::new(Hardfork::default(), Env::default(), State::new(), message);
+        let outcome = interpreter.run();
+        assert_eq!(outcome.output[30..], [0xaa, 0xbb]);
+        assert_eq!(interpreter.frame.memory.len(), 64);
+
+        // PUSH2 with a single byte left reads the missing byte as zero.
+        let message = Message {
+            code: vec![0x61, 0x01],
+            gas: 100,
+            ..Message::default()
+        };
+        let mut interpreter =
+            Interpreter::new(Hardfork::default(), Env::default(), State::new(), message);
+        assert_eq!(interpreter.run().status, Status::Stop);
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::from(0x100u64)]);
+    }
+
+    #[test]
+    fn precompile_calls() {
+        // The identity precompile copies the first word of memory to the second.
+        let interpreter = execute(
+            "PUSH2 0xabcd\nPUSH0\nMSTORE\nPUSH1 0x20\nPUSH1 0x20\nPUSH1 0x20\nPUSH0\nPUSH1 0x04\nGAS\nSTATICCALL\nPUSH1 0x40\nPUSH0\nRETURN",
+            WorldState::new(),
+        );
+        let copied = interpreter.outcome().unwrap();
+        assert_eq!(copied.output[..32], copied.output[32..]);
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ONE]);
+        assert_eq!(interpreter.frame.return_data, copied.output[..32]);
+        assert_eq!(
+            copied.gas_used,
+            3 + 2 + 6 + 9 + 2 + 3 + 2 + 100 + 3 + 18 + 3 + 2
+        );
+
+        // Sending value the contract does not have fails without executing.
+        let interpreter = execute(
+            "PUSH0\nPUSH0\nPUSH0\nPUSH0\nPUSH1 0x01\nPUSH1 0x02\nGAS\nCALL",
+            WorldState::new(),
+        );
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ZERO]);
+
+        // Calling an account without code succeeds immediately.
+        let interpreter = execute(
+            "PUSH0\nPUSH0\nPUSH0\nPUSH0\nPUSH1 0x20\nGAS\nSTATICCALL",
+            WorldState::new(),
+        );
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ONE]);
+    }
+
+    fn contract(source: &str) -> Account {
+        Account {
+            code: assemble(source).unwrap(),
+            ..Account::default()
+        }
+    }
+
+    #[test]
+    fn nested_calls() {
+        const CALLEE: Address = [0xbb; 20];
+        let caller = "PUSH1 0x20\nPUSH0\nPUSH0\nPUSH0\nPUSH0\nPUSH20 0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\nGAS\nCALL\nPUSH1 0x20\nPUSH0\nRETURN";
+        let mut state = WorldState::new();
+        state.accounts.insert(
+            CALLEE,
+            contract("PUSH1 0x2a\nPUSH0\nSSTORE\nCALLER\nPUSH0\nMSTORE\nPUSH1 0x20\nPUSH0\nRETURN"),
+        );
+        let interpreter = execute(caller, state);
+        let returned = interpreter.outcome().unwrap();
+        assert_eq!(returned.status, Status::Return);
+        assert_eq!(returned.output, address_to_word(&CONTRACT).to_be_bytes());
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ONE]);
+        assert_eq!(interpreter.frame.return_data, returned.output);
+        assert_eq!(
+            interpreter.state.storage(&CALLEE, U256::ZERO),
+            U256::from(0x2au64)
+        );
+
+        // A reverting callee leaves no storage behind but still returns its data.
+        let mut state = WorldState::new();
+        state.accounts.insert(
+            CALLEE,
+            contract(
+                "PUSH1 0x2a\nPUSH0\nSSTORE\nPUSH1 0x07\nPUSH0\nMSTORE\nPUSH1 0x20\nPUSH0\nREVERT",
+            ),
+        );
+        let interpreter = execute(caller, state);
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ZERO]);
+        assert_eq!(
+            U256::from_be_slice(&interpreter.frame.return_data),
+            U256::from(7u64)
+        );
+        assert_eq!(interpreter.state.storage(&CALLEE, U256::ZERO), U256::ZERO);
+
+        // Delegated code writes the storage of the caller.
+        let mut state = WorldState::new();
+        state
+            .accounts
+            .insert(CALLEE, contract("ADDRESS\nPUSH0\nSSTORE"));
+        let interpreter = execute(
+            "PUSH0\nPUSH0\nPUSH0\nPUSH0\nPUSH20 0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\nGAS\nDELEGATECALL",
+            state,
+        );
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ONE]);
+        assert_eq!(
+            interpreter.state.storage(&CONTRACT, U256::ZERO),
+            address_to_word(&CONTRACT)
+        );
+
+        // Writing storage below a STATICCALL fails the callee only.
+        let mut state = WorldState::new();
+        state
+            .accounts
+            .insert(CALLEE, contract("PUSH1 0x01\nPUSH0\nSSTORE"));
+        let interpreter = execute(
+            "PUSH0\nPUSH0\nPUSH0\nPUSH0\nPUSH20 0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\nGAS\nSTATICCALL",
+            state,
+        );
+        assert_eq!(interpreter.outcome().unwrap().status, Status::Stop);
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ZERO]);
+        assert!(interpreter
+            .state
+            .account(&CALLEE)
+            .unwrap()
+            .storage
+            .is_empty());
+    }
+
+    #[test]
+    fn call_depth_limit() {
+        let mut state = WorldState::new();
+        state.accounts.insert([0xbb; 20], contract("STOP"));
+        let message = Message {
+            address: CONTRACT,
+            code_address: CONTRACT,
+            code: assemble(
+                "PUSH0\nPUSH0\nPUSH0\nPUSH0\nPUSH20 0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\nGAS\nSTATICCALL",
+            )
+            .unwrap(),
+            gas: 100_000,
+            depth: CALL_DEPTH_LIMIT,
+            ..Message::default()
+        };
+        let mut interpreter =
+            Interpreter::new(Hardfork::default(), Env::default(), state.into(), message);
+        let outcome = interpreter.run();
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ZERO]);
+        // Only the cold access is charged, the forwarded gas is given back.
+        assert_eq!(outcome.gas_used, 4 * 2 + 3 + 2 + 100 + 2500);
+    }
+
+    #[test]
+    fn contract_creation() {
+        // Init code returning the single byte 0xfe, stored in memory at 0x18.
+        const INIT_CODE: [u8; 8] = hex!("60fe5f5360015ff3");
+        let interpreter = execute(
+            "PUSH8 0x60fe5f5360015ff3\nPUSH0\nMSTORE\nPUSH1 0x08\nPUSH1 0x18\nPUSH0\nCREATE\nPUSH0\nPUSH1 0x08\nPUSH1 0x18\nPUSH0\nCREATE2\nPUSH0\nPUSH1 0x08\nPUSH1 0x18\nPUSH0\nCREATE2",
+            WorldState::new(),
+        );
+        let created = create_address(&CONTRACT, 0);
+        let created2 = create2_address(&CONTRACT, U256::ZERO, &INIT_CODE);
+        // The second CREATE2 collides with the first.
+        assert_eq!(
+            interpreter.frame.stack.as_slice(),
+            [
+                address_to_word(&created),
+                address_to_word(&created2),
+                U256::ZERO
+            ]
+        );
+        for address in [created, created2] {
+            let account = interpreter.state.account(&address).unwrap();
+            assert_eq!(account.code, [0xfe]);
+            assert_eq!(account.nonce, 1);
+        }
+        assert_eq!(interpreter.state.account(&CONTRACT).unwrap().nonce, 3);
+
+        // Code starting with 0xef cannot be deployed, so the creation fails.
+        let interpreter = execute(
+            "PUSH8 0x60ef5f5360015ff3\nPUSH0\nMSTORE\nPUSH1 0x08\nPUSH1 0x18\nPUSH0\nCREATE",
+            WorldState::new(),
+        );
+        assert_eq!(interpreter.frame.stack.as_slice(), [U256::ZERO]);
+    }
+
+    #[test]
+    fn selfdestruct_per_fork() {
+        const BENEFICIARY: Address = [0xbb; 20];
+        for fork in [Hardfork::Shanghai, Hardfork::Cancun] {
+            let mut state = WorldState::new();
+            state.accounts.insert(
+                CONTRACT,
+                Account {
+                    balance: U256::from(9u64),
+                    ..contract("PUSH20 0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\nSELFDESTRUCT")
+                },
+            );
+            let message = Message {
+                address: CONTRACT,
+                code_address: CONTRACT,
+                code: state.code(&CONTRACT).to_vec(),
+                gas: 100_000,
+                ..Message::default()
+            };
+            let mut interpreter = Interpreter::new(fork, Env::default(), state.into(), message);
+            assert_eq!(interpreter.run().status, Status::SelfDestruct);
+            interpreter.state.finalize(fork);
+            assert_eq!(interpreter.state.balance(&BENEFICIARY), U256::from(9u64));
+            // EIP-6780 keeps contracts that were not created by the transaction.
+            assert_eq!(
+                interpreter.state.exists(&CONTRACT),
+                fork == Hardfork::Cancun
+            );
+        }
+    }
+}
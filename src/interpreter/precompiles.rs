@@ -0,0 +1,636 @@
+use super::{Address, ExecError};
+#[cfg(feature = "crypto")]
+use crate::address;
+use crate::hardfork::Hardfork;
+use crate::u256::U256;
+#[cfg(feature = "crypto")]
+use num_bigint::BigUint;
+#[cfg(feature = "crypto")]
+use sha2::Digest;
+
+/// Value `0x0a` returns on success: the number of field elements per blob and the
+/// modulus of the BLS12-381 scalar field.
+#[cfg(feature = "crypto")]
+const POINT_EVALUATION_OUTPUT: [u8; 64] = hex_literal::hex!(
+    "0000000000000000000000000000000000000000000000000000000000001000"
+    "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+);
+
+/// Contracts executed natively at addresses `0x01` to `0x0a`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Precompile {
+    EcRecover,
+    Sha256,
+    Ripemd160,
+    Identity,
+    /// EIP-198.
+    ModExp,
+    /// EIP-196.
+    Bn254Add,
+    Bn254Mul,
+    /// EIP-197.
+    Bn254Pairing,
+    /// EIP-152.
+    Blake2F,
+    /// EIP-4844.
+    PointEvaluation,
+}
+
+/// Result of a successful precompile call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecompileOutput {
+    pub gas_used: u64,
+    pub output: Vec<u8>,
+}
+
+impl Precompile {
+    /// Precompile at `address` in `fork`, if any.
+    pub fn at(fork: Hardfork, address: &Address) -> Option<Precompile> {
+        if address[..19].iter().any(|&byte| byte != 0)
+            || address[19] > super::gas::precompile_count(fork)
+        {
+            return None;
+        }
+        match address[19] {
+            1 => Some(Precompile::EcRecover),
+            2 => Some(Precompile::Sha256),
+            3 => Some(Precompile::Ripemd160),
+            4 => Some(Precompile::Identity),
+            5 => Some(Precompile::ModExp),
+            6 => Some(Precompile::Bn254Add),
+            7 => Some(Precompile::Bn254Mul),
+            8 => Some(Precompile::Bn254Pairing),
+            9 => Some(Precompile::Blake2F),
+            10 => Some(Precompile::PointEvaluation),
+            _ => None,
+        }
+    }
+
+    /// Runs the precompile with `gas` available. Failures consume all of it: running out
+    /// of gas is reported as [`ExecError::OutOfGas`], invalid input as
+    /// [`ExecError::PrecompileFailed`], and precompiles built without the `crypto`
+    /// feature as [`ExecError::PrecompileUnavailable`].
+    pub fn run(
+        self,
+        fork: Hardfork,
+        input: &[u8],
+        gas: u64,
+    ) -> Result<PrecompileOutput, ExecError> {
+        let cost = self.cost(fork, input);
+        if cost > gas {
+            return Err(ExecError::OutOfGas);
+        }
+        let output = match self {
+            Precompile::Identity => input.to_vec(),
+            Precompile::Blake2F => blake2f(input)?,
+            #[cfg(feature = "crypto")]
+            Precompile::EcRecover => ecrecover(input),
+            #[cfg(feature = "crypto")]
+            Precompile::Sha256 => sha2::Sha256::digest(input).to_vec(),
+            #[cfg(feature = "crypto")]
+            Precompile::Ripemd160 => {
+                let mut output = vec![0; 12];
+                output.extend(ripemd::Ripemd160::digest(input));
+                output
+            }
+            #[cfg(feature = "crypto")]
+            Precompile::ModExp => modexp(input),
+            #[cfg(feature = "crypto")]
+            Precompile::Bn254Add => bn254_add(input)?,
+            #[cfg(feature = "crypto")]
+            Precompile::Bn254Mul => bn254_mul(input)?,
+            #[cfg(feature = "crypto")]
+            Precompile::Bn254Pairing => bn254_pairing(input)?,
+            #[cfg(feature = "crypto")]
+            Precompile::PointEvaluation => point_evaluation(input)?,
+            #[cfg(not(feature = "crypto"))]
+            _ => return Err(ExecError::PrecompileUnavailable(self)),
+        };
+        Ok(PrecompileOutput {
+            gas_used: cost,
+            output,
+        })
+    }
+
+    /// Gas charged for `input`. Inputs whose cost does not fit in 64 bits cost
+    /// `u64::MAX`.
+    pub fn cost(self, fork: Hardfork, input: &[u8]) -> u64 {
+        let words = input.len().div_ceil(32) as u64;
+        // EIP-1108
+        let istanbul = fork >= Hardfork::Istanbul;
+        match self {
+            Precompile::EcRecover => 3000,
+            Precompile::Sha256 => 60 + 12 * words,
+            Precompile::Ripemd160 => 600 + 120 * words,
+            Precompile::Identity => 15 + 3 * words,
+            Precompile::ModExp => modexp_cost(fork, input),
+            Precompile::Bn254Add if istanbul => 150,
+            Precompile::Bn254Add => 500,
+            Precompile::Bn254Mul if istanbul => 6000,
+            Precompile::Bn254Mul => 40000,
+            Precompile::Bn254Pairing => {
+                let pairs = (input.len() / 192) as u64;
+                if istanbul {
+                    45000 + 34000 * pairs
+                } else {
+                    100000 + 80000 * pairs
+                }
+            }
+            Precompile::Blake2F => match input.get(..4) {
+                Some(rounds) if input.len() == 213 => {
+                    u32::from_be_bytes(rounds.try_into().unwrap()) as u64
+                }
+                _ => 0,
+            },
+            Precompile::PointEvaluation => 50000,
+        }
+    }
+}
+
+/// `input[offset..offset + len]`, padded with zeros past the end of the input.
+fn padded(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    if offset < input.len() {
+        let end = input.len().min(offset.saturating_add(len));
+        bytes[..end - offset].copy_from_slice(&input[offset..end]);
+    }
+    bytes
+}
+
+/// Recovers the address that signed a hash. Invalid signatures return no output
+/// rather than failing.
+#[cfg(feature = "crypto")]
+fn ecrecover(input: &[u8]) -> Vec<u8> {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let input = padded(input, 0, 128);
+    let v = U256::from_be_slice(&input[32..64]);
+    let recovered = (|| {
+        let mut recovery_id = match v.to_u64()? {
+            27 => 0,
+            28 => 1,
+            _ => return None,
+        };
+        let mut signature = Signature::from_slice(&input[64..128]).ok()?;
+        // Signatures with a high s are valid here, unlike in transactions.
+        if let Some(normalized) = signature.normalize_s() {
+            signature = normalized;
+            recovery_id ^= 1;
+        }
+        let recovery_id = RecoveryId::from_byte(recovery_id)?;
+        VerifyingKey::recover_from_prehash(&input[..32], &signature, recovery_id).ok()
+    })();
+    match recovered {
+        Some(key) => {
+            let mut output = vec![0; 12];
+            output.extend_from_slice(&address::from_public_key(&key));
+            output
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Lengths of the base, exponent and modulus of a `modexp` input.
+fn modexp_lengths(input: &[u8]) -> [U256; 3] {
+    let header = padded(input, 0, 96);
+    [0, 32, 64].map(|offset| U256::from_be_slice(&header[offset..offset + 32]))
+}
+
+/// Gas of `modexp`, as defined by EIP-198 and repriced by EIP-2565 in Berlin.
+fn modexp_cost(fork: Hardfork, input: &[u8]) -> u64 {
+    let [base_len, exp_len, mod_len] = modexp_lengths(input);
+    // Without a base or a modulus there is nothing to multiply, whatever the length
+    // of the exponent.
+    if base_len.is_zero() && mod_len.is_zero() {
+        return if fork >= Hardfork::Berlin { 200 } else { 0 };
+    }
+    let (base_len, exp_len, mod_len) = match (base_len.to_u64(), exp_len.to_u64(), mod_len.to_u64())
+    {
+        (Some(base), Some(exp), Some(modulus)) if base.max(modulus) <= u32::MAX as u64 => {
+            (base, exp, modulus)
+        }
+        _ => return u64::MAX,
+    };
+
+    // Bit length of the exponent, using at most its first 32 bytes.
+    let head_len = exp_len.min(32) as usize;
+    let offset = 96u64.saturating_add(base_len).min(usize::MAX as u64) as usize;
+    let head = U256::from_be_slice(&padded(input, offset, head_len));
+    let head_bits = head.bits().saturating_sub(1) as u128;
+    let iterations = if exp_len <= 32 {
+        head_bits
+    } else {
+        8 * (exp_len as u128 - 32) + head_bits
+    }
+    .max(1);
+
+    let len = base_len.max(mod_len) as u128;
+    let cost = if fork >= Hardfork::Berlin {
+        let words = len.div_ceil(8);
+        (words * words * iterations / 3).max(200)
+    } else {
+        let complexity = if len <= 64 {
+            len * len
+        } else if len <= 1024 {
+            len * len / 4 + 96 * len - 3072
+        } else {
+            len * len / 16 + 480 * len - 199680
+        };
+        complexity.saturating_mul(iterations) / 20
+    };
+    cost.try_into().unwrap_or(u64::MAX)
+}
+
+/// `base ** exponent % modulus`, left-padded to the length of the modulus. The cost
+/// bounds the lengths when there is a modulus, so they fit in memory by the time this
+/// runs.
+#[cfg(feature = "crypto")]
+fn modexp(input: &[u8]) -> Vec<u8> {
+    let lengths = modexp_lengths(input);
+    if lengths[2].is_zero() {
+        return Vec::new();
+    }
+    let [base_len, exp_len, mod_len] = lengths.map(|len| len.to_usize().unwrap());
+    let base = BigUint::from_bytes_be(&padded(input, 96, base_len));
+    let exponent = BigUint::from_bytes_be(&padded(input, 96 + base_len, exp_len));
+    let modulus = BigUint::from_bytes_be(&padded(input, 96 + base_len + exp_len, mod_len));
+    let mut output = vec![0; mod_len];
+    if modulus.bits() == 0 {
+        return output;
+    }
+    let result = base.modpow(&exponent, &modulus).to_bytes_be();
+    output[mod_len - result.len()..].copy_from_slice(&result);
+    output
+}
+
+#[cfg(feature = "crypto")]
+fn bn254_point(input: &[u8]) -> Result<bn::G1, ExecError> {
+    use bn::{AffineG1, Fq, Group, G1};
+
+    let x = Fq::from_slice(&input[..32]).map_err(|_| ExecError::PrecompileFailed)?;
+    let y = Fq::from_slice(&input[32..64]).map_err(|_| ExecError::PrecompileFailed)?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1::zero());
+    }
+    AffineG1::new(x, y)
+        .map(G1::from)
+        .map_err(|_| ExecError::PrecompileFailed)
+}
+
+#[cfg(feature = "crypto")]
+fn bn254_encode(point: bn::G1) -> Vec<u8> {
+    let mut output = vec![0; 64];
+    if let Some(point) = bn::AffineG1::from_jacobian(point) {
+        point.x().to_big_endian(&mut output[..32]).unwrap();
+        point.y().to_big_endian(&mut output[32..]).unwrap();
+    }
+    output
+}
+
+#[cfg(feature = "crypto")]
+fn bn254_add(input: &[u8]) -> Result<Vec<u8>, ExecError> {
+    let input = padded(input, 0, 128);
+    let a = bn254_point(&input[..64])?;
+    let b = bn254_point(&input[64..])?;
+    Ok(bn254_encode(a + b))
+}
+
+#[cfg(feature = "crypto")]
+fn bn254_mul(input: &[u8]) -> Result<Vec<u8>, ExecError> {
+    let input = padded(input, 0, 96);
+    let point = bn254_point(&input[..64])?;
+    let scalar = bn::Fr::from_slice(&input[64..]).map_err(|_| ExecError::PrecompileFailed)?;
+    Ok(bn254_encode(point * scalar))
+}
+
+/// Checks that the product of the pairings of the G1 and G2 points of the input is
+/// one. G2 coordinates are encoded with the imaginary part first.
+#[cfg(feature = "crypto")]
+fn bn254_pairing(input: &[u8]) -> Result<Vec<u8>, ExecError> {
+    use bn::{AffineG2, Fq, Fq2, Group, Gt, G2};
+
+    if !input.len().is_multiple_of(192) {
+        return Err(ExecError::PrecompileFailed);
+    }
+    let mut pairs = Vec::new();
+    for chunk in input.chunks(192) {
+        let a = bn254_point(&chunk[..64])?;
+        let mut coordinates = [Fq::zero(); 4];
+        for (coordinate, bytes) in coordinates.iter_mut().zip(chunk[64..].chunks(32)) {
+            *coordinate = Fq::from_slice(bytes).map_err(|_| ExecError::PrecompileFailed)?;
+        }
+        let [x_imaginary, x_real, y_imaginary, y_real] = coordinates;
+        let x = Fq2::new(x_real, x_imaginary);
+        let y = Fq2::new(y_real, y_imaginary);
+        let b = if x.is_zero() && y.is_zero() {
+            G2::zero()
+        } else {
+            AffineG2::new(x, y)
+                .map(G2::from)
+                .map_err(|_| ExecError::PrecompileFailed)?
+        };
+        pairs.push((a, b));
+    }
+    let valid = bn::pairing_batch(&pairs) == Gt::one();
+    Ok(U256::from(valid).to_be_bytes().to_vec())
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The BLAKE2b compression function F with a configurable number of rounds. The input
+/// is the rounds as a big-endian `u32`, then the state, the message block and the
+/// offset counter as little-endian words and the final block flag.
+fn blake2f(input: &[u8]) -> Result<Vec<u8>, ExecError> {
+    if input.len() != 213 || input[212] > 1 {
+        return Err(ExecError::PrecompileFailed);
+    }
+    let rounds = u32::from_be_bytes(input[..4].try_into().unwrap()) as usize;
+    let word = |index: usize| {
+        let offset = 4 + 8 * index;
+        u64::from_le_bytes(input[offset..offset + 8].try_into().unwrap())
+    };
+    let mut h: [u64; 8] = std::array::from_fn(word);
+    let m: [u64; 16] = std::array::from_fn(|index| word(8 + index));
+
+    let mut v = [0; 16];
+    v[..8].copy_from_slice(&h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= word(24);
+    v[13] ^= word(25);
+    if input[212] == 1 {
+        v[14] = !v[14];
+    }
+    for round in 0..rounds {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        mix(&mut v, [0, 4, 8, 12], m[s[0]], m[s[1]]);
+        mix(&mut v, [1, 5, 9, 13], m[s[2]], m[s[3]]);
+        mix(&mut v, [2, 6, 10, 14], m[s[4]], m[s[5]]);
+        mix(&mut v, [3, 7, 11, 15], m[s[6]], m[s[7]]);
+        mix(&mut v, [0, 5, 10, 15], m[s[8]], m[s[9]]);
+        mix(&mut v, [1, 6, 11, 12], m[s[10]], m[s[11]]);
+        mix(&mut v, [2, 7, 8, 13], m[s[12]], m[s[13]]);
+        mix(&mut v, [3, 4, 9, 14], m[s[14]], m[s[15]]);
+    }
+    for (i, h) in h.iter_mut().enumerate() {
+        *h ^= v[i] ^ v[i + 8];
+    }
+    Ok(h.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
+
+/// The BLAKE2b mixing function G.
+fn mix(v: &mut [u64; 16], [a, b, c, d]: [usize; 4], x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Verifies that the blob committed to by a versioned hash evaluates to `y` at `z`.
+/// The input is the versioned hash, `z`, `y`, the commitment and the proof.
+#[cfg(feature = "crypto")]
+fn point_evaluation(input: &[u8]) -> Result<Vec<u8>, ExecError> {
+    use c_kzg::{Bytes32, Bytes48};
+
+    if input.len() != 192 {
+        return Err(ExecError::PrecompileFailed);
+    }
+    let commitment = &input[96..144];
+    let mut versioned_hash: [u8; 32] = sha2::Sha256::digest(commitment).into();
+    versioned_hash[0] = 1;
+    if input[..32] != versioned_hash {
+        return Err(ExecError::PrecompileFailed);
+    }
+    let settings = c_kzg::ethereum_kzg_settings(0);
+    let valid = settings.verify_kzg_proof(
+        &Bytes48::from_bytes(commitment).unwrap(),
+        &Bytes32::from_bytes(&input[32..64]).unwrap(),
+        &Bytes32::from_bytes(&input[64..96]).unwrap(),
+        &Bytes48::from_bytes(&input[144..]).unwrap(),
+    );
+    match valid {
+        Ok(true) => Ok(POINT_EVALUATION_OUTPUT.to_vec()),
+        _ => Err(ExecError::PrecompileFailed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    fn run(precompile: Precompile, input: &[u8]) -> Result<PrecompileOutput, ExecError> {
+        precompile.run(Hardfork::Cancun, input, 1_000_000)
+    }
+
+    #[test]
+    fn addresses() {
+        let mut address = [0; 20];
+        address[19] = 9;
+        assert_eq!(
+            Precompile::at(Hardfork::Istanbul, &address),
+            Some(Precompile::Blake2F)
+        );
+        assert_eq!(Precompile::at(Hardfork::Byzantium, &address), None);
+        address[0] = 1;
+        assert_eq!(Precompile::at(Hardfork::Istanbul, &address), None);
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn hashes() {
+        let output = run(Precompile::Sha256, b"").unwrap();
+        assert_eq!(output.gas_used, 60);
+        assert_eq!(
+            output.output,
+            hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        let output = run(Precompile::Ripemd160, b"").unwrap();
+        assert_eq!(
+            output.output,
+            hex!("0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31")
+        );
+        let output = run(Precompile::Identity, &[1; 33]).unwrap();
+        assert_eq!((output.gas_used, output.output), (21, vec![1; 33]));
+        assert_eq!(
+            Precompile::Identity.run(Hardfork::Cancun, &[1; 33], 20),
+            Err(ExecError::OutOfGas)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn recover() {
+        let input = hex!(
+            "18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c"
+            "000000000000000000000000000000000000000000000000000000000000001c"
+            "73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f"
+            "eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549"
+        );
+        assert_eq!(
+            run(Precompile::EcRecover, &input).unwrap().output,
+            hex!("000000000000000000000000a94f5374fce5edbc8e2a8697c15331677e6ebf0b")
+        );
+        let mut invalid = input;
+        invalid[63] = 29;
+        assert!(run(Precompile::EcRecover, &invalid)
+            .unwrap()
+            .output
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn modular_exponentiation() {
+        // Fermat's little theorem with the secp256k1 field prime, from EIP-198.
+        let input = hex!(
+            "0000000000000000000000000000000000000000000000000000000000000001"
+            "0000000000000000000000000000000000000000000000000000000000000020"
+            "0000000000000000000000000000000000000000000000000000000000000020"
+            "03"
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e"
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f"
+        );
+        let output = run(Precompile::ModExp, &input).unwrap();
+        assert_eq!(output.output, U256::ONE.to_be_bytes());
+        assert_eq!(output.gas_used, 1360);
+        assert_eq!(Precompile::ModExp.cost(Hardfork::Byzantium, &input), 13056);
+        // Zero modulus, and lengths too large to ever pay for.
+        let mut input = [0; 99];
+        input[31] = 1;
+        input[63] = 1;
+        input[95] = 1;
+        input[96..].copy_from_slice(&[2, 3, 0]);
+        assert_eq!(run(Precompile::ModExp, &input).unwrap().output, [0]);
+        assert_eq!(
+            Precompile::ModExp.cost(Hardfork::Cancun, &[0xff; 96]),
+            u64::MAX
+        );
+        // An exponent length beyond `u64` costs nothing extra without a base or a
+        // modulus, and the output is empty.
+        let mut input = [0; 96];
+        input[32..64].fill(0xff);
+        assert_eq!(Precompile::ModExp.cost(Hardfork::Cancun, &input), 200);
+        assert_eq!(Precompile::ModExp.cost(Hardfork::Byzantium, &input), 0);
+        let output = run(Precompile::ModExp, &input).unwrap();
+        assert_eq!((output.gas_used, output.output), (200, Vec::new()));
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn bn254() {
+        let generator = hex!(
+            "0000000000000000000000000000000000000000000000000000000000000001"
+            "0000000000000000000000000000000000000000000000000000000000000002"
+        );
+        let double = hex!(
+            "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3"
+            "15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4"
+        );
+        let sum = run(Precompile::Bn254Add, &[generator, generator].concat()).unwrap();
+        assert_eq!((sum.gas_used, sum.output), (150, double.to_vec()));
+        let mut input = generator.to_vec();
+        input.extend(U256::from(2u64).to_be_bytes());
+        assert_eq!(run(Precompile::Bn254Mul, &input).unwrap().output, double);
+        assert_eq!(
+            run(Precompile::Bn254Add, &[0xff; 64]),
+            Err(ExecError::PrecompileFailed)
+        );
+
+        let output = run(Precompile::Bn254Pairing, &[]).unwrap();
+        assert_eq!(output.output, U256::ONE.to_be_bytes());
+        assert_eq!(output.gas_used, 45000);
+        let output = run(Precompile::Bn254Pairing, &[0; 192]).unwrap();
+        assert_eq!(output.output, U256::ONE.to_be_bytes());
+        assert_eq!(
+            run(Precompile::Bn254Pairing, &[0; 191]),
+            Err(ExecError::PrecompileFailed)
+        );
+    }
+
+    #[test]
+    fn unavailable() {
+        let result = Precompile::Sha256.run(Hardfork::Cancun, b"", 1_000_000);
+        if cfg!(feature = "crypto") {
+            assert!(result.is_ok());
+        } else {
+            assert_eq!(
+                result,
+                Err(ExecError::PrecompileUnavailable(Precompile::Sha256))
+            );
+        }
+    }
+
+    #[test]
+    fn blake2_compression() {
+        // Test vector 5 of EIP-152: the single block of BLAKE2b-512("abc").
+        let mut input = hex!(
+            "0000000c"
+            "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5"
+            "d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b"
+        )
+        .to_vec();
+        input.extend(b"abc");
+        input.resize(4 + 64 + 128, 0);
+        input.extend(hex!("0300000000000000 0000000000000000 01"));
+        let output = run(Precompile::Blake2F, &input).unwrap();
+        assert_eq!(output.gas_used, 12);
+        assert_eq!(
+            output.output,
+            hex!(
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1"
+                "7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+            )
+        );
+        input[212] = 2;
+        assert_eq!(
+            run(Precompile::Blake2F, &input),
+            Err(ExecError::PrecompileFailed)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn point_evaluation_of_zero_blob() {
+        // The commitment and proof of the zero polynomial are the point at infinity.
+        let mut infinity = [0; 48];
+        infinity[0] = 0xc0;
+        let mut versioned_hash: [u8; 32] = sha2::Sha256::digest(infinity).into();
+        versioned_hash[0] = 1;
+        let input = [&versioned_hash[..], &[0; 64], &infinity, &infinity].concat();
+        let output = run(Precompile::PointEvaluation, &input).unwrap();
+        assert_eq!(output.output, POINT_EVALUATION_OUTPUT);
+
+        let mut wrong_value = input.clone();
+        wrong_value[95] = 1;
+        assert_eq!(
+            run(Precompile::PointEvaluation, &wrong_value),
+            Err(ExecError::PrecompileFailed)
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use super::ExecError;
+use crate::u256::U256;
+
+/// Maximum number of items on the stack.
+pub const STACK_LIMIT: usize = 1024;
+
+/// Operand stack of a call frame. The top of the stack is the last element.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stack {
+    data: Vec<U256>,
+}
+
+impl Stack {
+    pub fn new() -> Stack {
+        Stack::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Items from the bottom to the top of the stack.
+    pub fn as_slice(&self) -> &[U256] {
+        &self.data
+    }
+
+    pub fn push(&mut self, value: U256) -> Result<(), ExecError> {
+        if self.data.len() == STACK_LIMIT {
+            return Err(ExecError::StackOverflow);
+        }
+        self.data.push(value);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<U256, ExecError> {
+        self.data.pop().ok_or(ExecError::StackUnderflow)
+    }
+
+    /// Pops `N` items, the top of the stack first.
+    pub fn pop_n<const N: usize>(&mut self) -> Result<[U256; N], ExecError> {
+        if self.data.len() < N {
+            return Err(ExecError::StackUnderflow);
+        }
+        let mut items = [U256::ZERO; N];
+        for item in items.iter_mut() {
+            *item = self.data.pop().unwrap();
+        }
+        Ok(items)
+    }
+
+    /// Item `depth` positions below the top, `0` being the top.
+    pub fn peek(&self, depth: usize) -> Result<U256, ExecError> {
+        self.data
+            .len()
+            .checked_sub(depth + 1)
+            .map(|index| self.data[index])
+            .ok_or(ExecError::StackUnderflow)
+    }
+
+    /// `DUPn`: pushes a copy of the `n`th item, `1` being the top.
+    pub fn dup(&mut self, n: usize) -> Result<(), ExecError> {
+        let value = self.peek(n - 1)?;
+        self.push(value)
+    }
+
+    /// `SWAPn`: exchanges the top with the item `n` positions below it.
+    pub fn swap(&mut self, n: usize) -> Result<(), ExecError> {
+        if self.data.len() <= n {
+            return Err(ExecError::StackUnderflow);
+        }
+        let top = self.data.len() - 1;
+        self.data.swap(top, top - n);
+        Ok(())
+    }
+}
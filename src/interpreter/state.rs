@@ -0,0 +1,563 @@
+//! Journaled world state.
+//!
+//! [`State`] wraps a [`WorldState`] with everything that lives for the duration of a
+//! transaction: transient storage, warm accounts and slots, touched, created and
+//! destructed accounts, and the original value of written slots. Changes are recorded
+//! in a journal while a checkpoint is open, so that a failing frame can undo exactly
+//! what it did.
+
+use super::world::{Account, WorldState};
+use super::Address;
+use crate::hardfork::Hardfork;
+use crate::hex;
+use crate::u256::U256;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Error loading a genesis-style `alloc` file.
+#[derive(Debug)]
+pub enum AllocError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Account whose address or fields could not be parsed.
+    InvalidAccount(String),
+}
+
+impl std::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AllocError::Io(err) => write!(f, "{}", err),
+            AllocError::Json(err) => write!(f, "invalid JSON: {}", err),
+            AllocError::InvalidAccount(address) => write!(f, "invalid account `{}`", address),
+        }
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+impl From<std::io::Error> for AllocError {
+    fn from(err: std::io::Error) -> Self {
+        AllocError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for AllocError {
+    fn from(err: serde_json::Error) -> Self {
+        AllocError::Json(err)
+    }
+}
+
+/// Change recorded in the journal, holding what to restore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    /// The account did not exist.
+    AccountCreated(Address),
+    Balance(Address, U256),
+    Nonce(Address, u64),
+    Code(Address, Vec<u8>),
+    Storage(Address, U256, U256),
+    Transient(Address, U256, U256),
+    WarmAccount(Address),
+    WarmSlot(Address, U256),
+    Touched(Address),
+    Created(Address),
+    Destructed(Address),
+}
+
+/// World state with the journal and the per-transaction sets of the interpreter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct State {
+    world: WorldState,
+    transient: BTreeMap<(Address, U256), U256>,
+    warm_accounts: BTreeSet<Address>,
+    warm_slots: BTreeSet<(Address, U256)>,
+    /// Accounts changed by the transaction, removed at its end when empty (EIP-161).
+    touched: BTreeSet<Address>,
+    /// Accounts created by the transaction, which `SELFDESTRUCT` still deletes from
+    /// Cancun on (EIP-6780).
+    created: BTreeSet<Address>,
+    destructed: BTreeSet<Address>,
+    /// Value of written slots when the transaction started.
+    original: BTreeMap<(Address, U256), U256>,
+    journal: Vec<Change>,
+    /// Length of the journal when each open checkpoint was taken.
+    checkpoints: Vec<usize>,
+}
+
+impl From<WorldState> for State {
+    fn from(world: WorldState) -> State {
+        State {
+            world,
+            ..State::default()
+        }
+    }
+}
+
+impl State {
+    pub fn new() -> State {
+        State::default()
+    }
+
+    /// Reads a genesis-style `alloc` file, an object mapping addresses to accounts.
+    pub fn load_alloc(path: impl AsRef<Path>) -> Result<State, AllocError> {
+        let json = std::fs::read_to_string(path)?;
+        let value: serde_json::Value = serde_json::from_str(&json)?;
+        Ok(WorldState::from_alloc(&value)?.into())
+    }
+
+    /// Writes the accounts to a genesis-style `alloc` file.
+    pub fn dump_alloc(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut json = serde_json::to_string_pretty(&self.world.to_alloc())?;
+        json.push('\n');
+        std::fs::write(path, json)
+    }
+
+    pub fn world(&self) -> &WorldState {
+        &self.world
+    }
+
+    pub fn into_world(self) -> WorldState {
+        self.world
+    }
+
+    pub fn account(&self, address: &Address) -> Option<&Account> {
+        self.world.account(address)
+    }
+
+    pub fn exists(&self, address: &Address) -> bool {
+        self.world.exists(address)
+    }
+
+    pub fn balance(&self, address: &Address) -> U256 {
+        self.world.balance(address)
+    }
+
+    pub fn nonce(&self, address: &Address) -> u64 {
+        self.account(address).map_or(0, |account| account.nonce)
+    }
+
+    pub fn code(&self, address: &Address) -> &[u8] {
+        self.world.code(address)
+    }
+
+    pub fn storage(&self, address: &Address, key: U256) -> U256 {
+        self.world.storage(address, key)
+    }
+
+    /// Value of a slot when the transaction started, as used by `SSTORE` pricing.
+    pub fn original_storage(&self, address: &Address, key: U256) -> U256 {
+        match self.original.get(&(*address, key)) {
+            Some(value) => *value,
+            None => self.storage(address, key),
+        }
+    }
+
+    pub fn transient_storage(&self, address: &Address, key: U256) -> U256 {
+        self.transient
+            .get(&(*address, key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn is_destructed(&self, address: &Address) -> bool {
+        self.destructed.contains(address)
+    }
+
+    /// Starts recording changes, to be undone by [`State::revert`] or kept by
+    /// [`State::commit`]. Checkpoints nest.
+    pub fn checkpoint(&mut self) {
+        self.checkpoints.push(self.journal.len());
+    }
+
+    /// Undoes every change since the last open checkpoint and closes it.
+    pub fn revert(&mut self) {
+        let len = self.checkpoints.pop().expect("no open checkpoint");
+        for change in self.journal.drain(len..).rev() {
+            match change {
+                Change::AccountCreated(address) => {
+                    self.world.accounts.remove(&address);
+                }
+                Change::Balance(address, balance) => {
+                    self.world.account_mut(address).balance = balance;
+                }
+                Change::Nonce(address, nonce) => self.world.account_mut(address).nonce = nonce,
+                Change::Code(address, code) => self.world.account_mut(address).code = code,
+                Change::Storage(address, key, value) => self.world.set_storage(address, key, value),
+                Change::Transient(address, key, value) => {
+                    set_transient(&mut self.transient, address, key, value)
+                }
+                Change::WarmAccount(address) => {
+                    self.warm_accounts.remove(&address);
+                }
+                Change::WarmSlot(address, key) => {
+                    self.warm_slots.remove(&(address, key));
+                }
+                Change::Touched(address) => {
+                    self.touched.remove(&address);
+                }
+                Change::Created(address) => {
+                    self.created.remove(&address);
+                }
+                Change::Destructed(address) => {
+                    self.destructed.remove(&address);
+                }
+            }
+        }
+    }
+
+    /// Keeps the changes since the last open checkpoint and closes it. They are still
+    /// undone if an outer checkpoint is reverted.
+    pub fn commit(&mut self) {
+        self.checkpoints.pop().expect("no open checkpoint");
+        if self.checkpoints.is_empty() {
+            self.journal.clear();
+        }
+    }
+
+    /// Ends the transaction: deletes destructed accounts and, from Spurious Dragon,
+    /// touched accounts left empty, then forgets the per-transaction sets.
+    pub fn finalize(&mut self, fork: Hardfork) {
+        for address in std::mem::take(&mut self.destructed) {
+            self.world.accounts.remove(&address);
+        }
+        let touched = std::mem::take(&mut self.touched);
+        if fork >= Hardfork::SpuriousDragon {
+            for address in touched {
+                if self.account(&address).is_some_and(Account::is_empty) {
+                    self.world.accounts.remove(&address);
+                }
+            }
+        }
+        self.transient.clear();
+        self.warm_accounts.clear();
+        self.warm_slots.clear();
+        self.created.clear();
+        self.original.clear();
+        self.journal.clear();
+        self.checkpoints.clear();
+    }
+
+    /// Marks an account as accessed (EIP-2929). Returns whether it was cold.
+    pub fn warm_account(&mut self, address: Address) -> bool {
+        let cold = self.warm_accounts.insert(address);
+        if cold {
+            self.record(Change::WarmAccount(address));
+        }
+        cold
+    }
+
+    /// Marks a storage slot as accessed (EIP-2929). Returns whether it was cold.
+    pub fn warm_slot(&mut self, address: Address, key: U256) -> bool {
+        let cold = self.warm_slots.insert((address, key));
+        if cold {
+            self.record(Change::WarmSlot(address, key));
+        }
+        cold
+    }
+
+    /// Marks an account as touched, creating it if it does not exist.
+    pub fn touch(&mut self, address: Address) {
+        self.account_mut(address);
+    }
+
+    pub fn set_balance(&mut self, address: Address, balance: U256) {
+        let previous = std::mem::replace(&mut self.account_mut(address).balance, balance);
+        self.record(Change::Balance(address, previous));
+    }
+
+    /// Moves `value` between accounts, touching both. The caller checks the balance.
+    pub fn transfer(&mut self, from: Address, to: Address, value: U256) {
+        let balance = self.balance(&from);
+        self.set_balance(from, balance - value);
+        let balance = self.balance(&to);
+        self.set_balance(to, balance + value);
+    }
+
+    pub fn set_nonce(&mut self, address: Address, nonce: u64) {
+        let previous = std::mem::replace(&mut self.account_mut(address).nonce, nonce);
+        self.record(Change::Nonce(address, previous));
+    }
+
+    pub fn increment_nonce(&mut self, address: Address) {
+        let nonce = self.nonce(&address);
+        self.set_nonce(address, nonce + 1);
+    }
+
+    pub fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        let previous = std::mem::replace(&mut self.account_mut(address).code, code);
+        self.record(Change::Code(address, previous));
+    }
+
+    /// Stores `value` under `key`. Storing zero removes the slot.
+    pub fn set_storage(&mut self, address: Address, key: U256, value: U256) {
+        let previous = self.storage(&address, key);
+        self.original.entry((address, key)).or_insert(previous);
+        self.account_mut(address);
+        self.world.set_storage(address, key, value);
+        self.record(Change::Storage(address, key, previous));
+    }
+
+    pub fn set_transient_storage(&mut self, address: Address, key: U256, value: U256) {
+        let previous = self.transient_storage(&address, key);
+        set_transient(&mut self.transient, address, key, value);
+        self.record(Change::Transient(address, key, previous));
+    }
+
+    /// Records that a contract is being created at `address`, which keeps any balance
+    /// it already has.
+    pub fn create_account(&mut self, address: Address) {
+        self.account_mut(address);
+        if self.created.insert(address) {
+            self.record(Change::Created(address));
+        }
+    }
+
+    /// Sends the balance of `address` to `beneficiary` and schedules the account for
+    /// deletion at the end of the transaction. From Cancun only accounts created by
+    /// the same transaction are deleted (EIP-6780); others only lose their balance.
+    pub fn selfdestruct(&mut self, fork: Hardfork, address: Address, beneficiary: Address) {
+        let deletes = fork < Hardfork::Cancun || self.created.contains(&address);
+        let balance = self.balance(&address);
+        if beneficiary != address {
+            self.transfer(address, beneficiary, balance);
+        } else if deletes {
+            // Sending the balance to itself burns it.
+            self.set_balance(address, U256::ZERO);
+        }
+        if deletes && self.destructed.insert(address) {
+            self.record(Change::Destructed(address));
+        }
+    }
+
+    /// Account at `address`, created empty if it does not exist, after marking it as
+    /// touched.
+    fn account_mut(&mut self, address: Address) -> &mut Account {
+        if !self.world.exists(&address) {
+            self.record(Change::AccountCreated(address));
+        }
+        if self.touched.insert(address) {
+            self.record(Change::Touched(address));
+        }
+        self.world.account_mut(address)
+    }
+
+    /// Journals a change, unless no checkpoint is open and it can never be undone.
+    fn record(&mut self, change: Change) {
+        if !self.checkpoints.is_empty() {
+            self.journal.push(change);
+        }
+    }
+}
+
+fn set_transient(
+    transient: &mut BTreeMap<(Address, U256), U256>,
+    address: Address,
+    key: U256,
+    value: U256,
+) {
+    if value.is_zero() {
+        transient.remove(&(address, key));
+    } else {
+        transient.insert((address, key), value);
+    }
+}
+
+impl WorldState {
+    /// Parses a genesis-style `alloc` object. Every field of an account but the
+    /// balance is optional, and numbers are decimal or `0x` hexadecimal strings.
+    pub fn from_alloc(value: &serde_json::Value) -> Result<WorldState, AllocError> {
+        let accounts = value
+            .as_object()
+            .ok_or_else(|| AllocError::InvalidAccount(value.to_string()))?;
+        let mut state = WorldState::new();
+        for (address, account) in accounts {
+            let parsed = hex::decode(address).and_then(|address| address.try_into().ok());
+            match (parsed, parse_account(account)) {
+                (Some(address), Some(account)) => {
+                    state.accounts.insert(address, account);
+                }
+                _ => return Err(AllocError::InvalidAccount(address.clone())),
+            }
+        }
+        Ok(state)
+    }
+
+    /// Accounts as a genesis-style `alloc` object, leaving out empty fields.
+    pub fn to_alloc(&self) -> serde_json::Value {
+        let mut alloc = serde_json::Map::new();
+        for (address, account) in &self.accounts {
+            let mut fields = serde_json::Map::new();
+            fields.insert("balance".into(), format!("{:#x}", account.balance).into());
+            if account.nonce != 0 {
+                fields.insert("nonce".into(), format!("{:#x}", account.nonce).into());
+            }
+            if !account.code.is_empty() {
+                fields.insert(
+                    "code".into(),
+                    format!("0x{}", hex::encode(&account.code)).into(),
+                );
+            }
+            if !account.storage.is_empty() {
+                let storage = account
+                    .storage
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = format!("0x{}", hex::encode(&key.to_be_bytes()));
+                        (
+                            key,
+                            format!("0x{}", hex::encode(&value.to_be_bytes())).into(),
+                        )
+                    })
+                    .collect();
+                fields.insert("storage".into(), serde_json::Value::Object(storage));
+            }
+            alloc.insert(format!("0x{}", hex::encode(address)), fields.into());
+        }
+        alloc.into()
+    }
+}
+
+fn parse_account(value: &serde_json::Value) -> Option<Account> {
+    let number = |name: &str| match value.get(name) {
+        None => Some(U256::ZERO),
+        Some(number) => number.as_str()?.parse::<U256>().ok(),
+    };
+    let mut storage = BTreeMap::new();
+    if let Some(slots) = value.get("storage") {
+        for (key, slot) in slots.as_object()? {
+            let slot = slot.as_str()?.parse::<U256>().ok()?;
+            if !slot.is_zero() {
+                storage.insert(key.parse().ok()?, slot);
+            }
+        }
+    }
+    let code = match value.get("code") {
+        None => Vec::new(),
+        Some(code) => hex::decode(code.as_str()?)?,
+    };
+    Some(Account {
+        nonce: number("nonce")?.to_u64()?,
+        balance: value.get("balance").and(number("balance"))?,
+        code,
+        storage,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: Address = [0xaa; 20];
+    const B: Address = [0xbb; 20];
+
+    #[test]
+    fn nested_checkpoints() {
+        let mut state = State::new();
+        state.set_balance(A, U256::from(10u64));
+        state.checkpoint();
+        state.transfer(A, B, U256::from(4u64));
+        state.set_storage(A, U256::ONE, U256::from(7u64));
+
+        state.checkpoint();
+        state.set_storage(A, U256::ONE, U256::from(8u64));
+        state.set_transient_storage(A, U256::ONE, U256::ONE);
+        state.warm_account(B);
+        state.revert();
+        assert_eq!(state.storage(&A, U256::ONE), U256::from(7u64));
+        assert_eq!(state.transient_storage(&A, U256::ONE), U256::ZERO);
+        assert!(state.warm_account(B));
+
+        state.commit();
+        assert_eq!(state.balance(&B), U256::from(4u64));
+        assert_eq!(state.original_storage(&A, U256::ONE), U256::ZERO);
+
+        state.checkpoint();
+        state.increment_nonce(B);
+        state.set_code(B, vec![0]);
+        state.revert();
+        assert_eq!(state.account(&B).unwrap().nonce, 0);
+        assert!(state.code(&B).is_empty());
+
+        // Reverting the outermost checkpoint removes accounts it created.
+        state.checkpoint();
+        state.touch([0xcc; 20]);
+        state.revert();
+        assert!(!state.exists(&[0xcc; 20]));
+    }
+
+    #[test]
+    fn finalize_removes_touched_empty_accounts() {
+        let mut world = WorldState::new();
+        world.accounts.insert(B, Account::default());
+        world.accounts.insert([0xcc; 20], Account::default());
+        let mut state = State::from(world);
+        state.set_balance(A, U256::ONE);
+        state.transfer(A, B, U256::ZERO);
+        state.finalize(Hardfork::Cancun);
+        // The untouched empty account is kept.
+        let addresses: Vec<_> = state.world().accounts.keys().copied().collect();
+        assert_eq!(addresses, [A, [0xcc; 20]]);
+
+        let mut state = State::new();
+        state.touch(B);
+        state.finalize(Hardfork::Homestead);
+        assert!(state.exists(&B));
+    }
+
+    #[test]
+    fn selfdestruct_per_fork() {
+        let mut state = State::new();
+        state.set_balance(A, U256::from(5u64));
+        state.selfdestruct(Hardfork::Shanghai, A, B);
+        assert!(state.is_destructed(&A));
+        state.finalize(Hardfork::Shanghai);
+        assert!(!state.exists(&A));
+        assert_eq!(state.balance(&B), U256::from(5u64));
+
+        // From Cancun only the balance moves, unless the account is new.
+        let mut state = State::new();
+        state.set_code(A, vec![0xff]);
+        state.set_balance(A, U256::from(5u64));
+        state.selfdestruct(Hardfork::Cancun, A, A);
+        assert_eq!(state.balance(&A), U256::from(5u64));
+        state.selfdestruct(Hardfork::Cancun, A, B);
+        state.finalize(Hardfork::Cancun);
+        assert_eq!(state.code(&A), [0xff]);
+        assert_eq!(state.balance(&B), U256::from(5u64));
+
+        let mut state = State::new();
+        state.create_account(A);
+        state.set_code(A, vec![0xff]);
+        state.selfdestruct(Hardfork::Cancun, A, A);
+        state.finalize(Hardfork::Cancun);
+        assert!(!state.exists(&A));
+    }
+
+    #[test]
+    fn alloc_round_trip() {
+        let alloc = serde_json::json!({
+            "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa": {
+                "balance": "1000",
+                "nonce": "0x01",
+                "code": "0x6000",
+                "storage": {"0x01": "0x02", "0x03": "0x00"}
+            },
+            "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb": {"balance": "0x0"}
+        });
+        let world = WorldState::from_alloc(&alloc).unwrap();
+        let account = world.account(&A).unwrap();
+        assert_eq!(account.balance, U256::from(1000u64));
+        assert_eq!(account.nonce, 1);
+        assert_eq!(account.code, [0x60, 0x00]);
+        assert_eq!(account.storage.len(), 1);
+        assert!(world.exists(&B));
+        assert_eq!(WorldState::from_alloc(&world.to_alloc()).unwrap(), world);
+
+        let err = WorldState::from_alloc(&serde_json::json!({"0x12": {"balance": "0"}}));
+        assert_eq!(err.unwrap_err().to_string(), "invalid account `0x12`");
+        let err = WorldState::from_alloc(
+            &serde_json::json!({"0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa": {}}),
+        );
+        assert!(matches!(err, Err(AllocError::InvalidAccount(_))));
+    }
+}
@@ -0,0 +1,164 @@
+use super::{Outcome, Status};
+use crate::disasm::Opcode;
+use crate::hex;
+use crate::u256::U256;
+use std::io::Write;
+
+/// State before an instruction executes, with the gas it cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub pc: usize,
+    pub opcode: Opcode,
+    /// Gas left before the instruction.
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub memory_size: usize,
+    /// Stack from the bottom to the top.
+    pub stack: Vec<U256>,
+    /// Call depth, starting at 1.
+    pub depth: usize,
+    pub refund: u64,
+    /// Exceptional halt caused by the instruction.
+    pub error: Option<String>,
+}
+
+/// Receives every executed instruction and the final outcome.
+pub trait Tracer {
+    fn step(&mut self, step: &Step);
+
+    fn end(&mut self, _outcome: &Outcome) {}
+}
+
+/// Writes an EIP-3155 trace: one JSON object per step followed by a summary object,
+/// with the fields in the order geth and evmone print them.
+#[derive(Debug)]
+pub struct JsonTracer<W: Write> {
+    out: W,
+    /// First write error, reported by `finish`.
+    error: Option<std::io::Error>,
+}
+
+impl<W: Write> JsonTracer<W> {
+    pub fn new(out: W) -> JsonTracer<W> {
+        JsonTracer { out, error: None }
+    }
+
+    /// Flushes the output and returns it, or the first error hit while writing.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.error.is_none() {
+            if let Err(err) = writeln!(self.out, "{}", line) {
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+impl<W: Write> Tracer for JsonTracer<W> {
+    fn step(&mut self, step: &Step) {
+        let line = step_json(step);
+        self.write_line(&line);
+    }
+
+    fn end(&mut self, outcome: &Outcome) {
+        let line = summary_json(outcome);
+        self.write_line(&line);
+    }
+}
+
+/// EIP-3155 line of a step.
+pub fn step_json(step: &Step) -> String {
+    let stack: Vec<String> = step
+        .stack
+        .iter()
+        .map(|item| format!("\"{:#x}\"", item))
+        .collect();
+    let mut line = format!(
+        "{{\"pc\":{},\"op\":{},\"gas\":\"{:#x}\",\"gasCost\":\"{:#x}\",\"memSize\":{},\"stack\":[{}],\"depth\":{},\"refund\":{},\"opName\":\"{}\"",
+        step.pc,
+        step.opcode.code(),
+        step.gas,
+        step.gas_cost,
+        step.memory_size,
+        stack.join(","),
+        step.depth,
+        step.refund,
+        step.opcode.name(),
+    );
+    if let Some(error) = &step.error {
+        line.push_str(&format!(
+            ",\"error\":{}",
+            serde_json::Value::from(error.as_str())
+        ));
+    }
+    line.push('}');
+    line
+}
+
+/// Summary line printed after the last step.
+pub fn summary_json(outcome: &Outcome) -> String {
+    let output = hex::encode(&outcome.output);
+    let mut line = format!(
+        "{{\"output\":\"{}\",\"gasUsed\":\"{:#x}\",\"pass\":{}",
+        output,
+        outcome.gas_used,
+        outcome.status.is_success()
+    );
+    let error = match &outcome.status {
+        Status::Revert => Some("execution reverted".to_string()),
+        Status::Error(err) => Some(err.to_string()),
+        _ => None,
+    };
+    if let Some(error) = error {
+        line.push_str(&format!(",\"error\":{}", serde_json::Value::from(error)));
+    }
+    line.push('}');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_line() {
+        let step = Step {
+            pc: 2,
+            opcode: Opcode::ADD,
+            gas: 0x2540be3fa,
+            gas_cost: 3,
+            memory_size: 0,
+            stack: vec![U256::ONE, U256::from(2u64)],
+            depth: 1,
+            refund: 0,
+            error: None,
+        };
+        assert_eq!(
+            step_json(&step),
+            r#"{"pc":2,"op":1,"gas":"0x2540be3fa","gasCost":"0x3","memSize":0,"stack":["0x1","0x2"],"depth":1,"refund":0,"opName":"ADD"}"#
+        );
+    }
+
+    #[test]
+    fn summary_line() {
+        let outcome = Outcome {
+            status: Status::Revert,
+            output: vec![0xab],
+            gas_used: 0x1e,
+            gas_left: 0,
+            refund: 0,
+            logs: Vec::new(),
+        };
+        assert_eq!(
+            summary_json(&outcome),
+            r#"{"output":"ab","gasUsed":"0x1e","pass":false,"error":"execution reverted"}"#
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use super::Address;
+use crate::keccak::keccak256;
+use crate::rlp;
+use crate::trie;
+use crate::u256::U256;
+use std::collections::BTreeMap;
+
+/// Keccak-256 of empty code, the code hash of accounts without code.
+pub const EMPTY_CODE_HASH: [u8; 32] = [
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Account {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code: Vec<u8>,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl Account {
+    /// Empty as defined by EIP-161: no code, zero nonce and zero balance.
+    pub fn is_empty(&self) -> bool {
+        self.nonce == 0 && self.balance.is_zero() && self.code.is_empty()
+    }
+
+    /// Whether creating a contract at the address of this account fails: it has a
+    /// nonce, code or storage.
+    pub fn has_collision(&self) -> bool {
+        self.nonce != 0 || !self.code.is_empty() || !self.storage.is_empty()
+    }
+
+    pub fn code_hash(&self) -> [u8; 32] {
+        if self.code.is_empty() {
+            EMPTY_CODE_HASH
+        } else {
+            keccak256(&self.code)
+        }
+    }
+
+    /// Root of the trie of the non-zero storage slots.
+    pub fn storage_root(&self) -> [u8; 32] {
+        trie::secure_root(
+            self.storage
+                .iter()
+                .filter(|(_, value)| !value.is_zero())
+                .map(|(key, value)| (key.to_be_bytes(), rlp::encode(value))),
+        )
+    }
+}
+
+/// Accounts the interpreter reads and writes. Missing accounts behave as empty ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorldState {
+    pub accounts: BTreeMap<Address, Account>,
+}
+
+impl WorldState {
+    pub fn new() -> WorldState {
+        WorldState::default()
+    }
+
+    pub fn account(&self, address: &Address) -> Option<&Account> {
+        self.accounts.get(address)
+    }
+
+    /// Account at `address`, created empty if it does not exist.
+    pub fn account_mut(&mut self, address: Address) -> &mut Account {
+        self.accounts.entry(address).or_default()
+    }
+
+    pub fn exists(&self, address: &Address) -> bool {
+        self.accounts.contains_key(address)
+    }
+
+    pub fn balance(&self, address: &Address) -> U256 {
+        self.account(address)
+            .map(|account| account.balance)
+            .unwrap_or_default()
+    }
+
+    pub fn code(&self, address: &Address) -> &[u8] {
+        self.account(address)
+            .map(|account| account.code.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn storage(&self, address: &Address, key: U256) -> U256 {
+        self.account(address)
+            .and_then(|account| account.storage.get(&key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Stores `value` under `key`. Storing zero removes the slot.
+    pub fn set_storage(&mut self, address: Address, key: U256, value: U256) {
+        let storage = &mut self.account_mut(address).storage;
+        if value.is_zero() {
+            storage.remove(&key);
+        } else {
+            storage.insert(key, value);
+        }
+    }
+
+    /// Root of the trie of all accounts, `hash` in state tests and `stateRoot` in
+    /// block headers.
+    pub fn state_root(&self) -> [u8; 32] {
+        trie::secure_root(self.accounts.iter().map(|(address, account)| {
+            let fields = (
+                account.nonce,
+                account.balance,
+                account.storage_root(),
+                account.code_hash(),
+            );
+            (address, rlp::encode(&fields))
+        }))
+    }
+}
@@ -0,0 +1,66 @@
+/// Valid jump destinations of a piece of code.
+///
+/// A destination is valid when it holds a `JUMPDEST` opcode. A `0x5b` byte that is
+/// part of the data of a `PUSHn` is not an opcode and therefore not a destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpTable {
+    /// One bit per code byte, least significant bit first.
+    bitmap: Vec<u8>,
+}
+
+impl JumpTable {
+    pub fn new(code: &[u8]) -> JumpTable {
+        let mut bitmap = vec![0; code.len().div_ceil(8)];
+        let mut pc = 0;
+        while pc < code.len() {
+            match code[pc] {
+                0x5b => bitmap[pc / 8] |= 1 << (pc % 8),
+                // PUSH1..PUSH32
+                op @ 0x60..=0x7f => pc += (op - 0x5f) as usize,
+                _ => {}
+            }
+            pc += 1;
+        }
+        JumpTable { bitmap }
+    }
+
+    /// Whether jumping to `pc` is allowed.
+    pub fn is_valid(&self, pc: usize) -> bool {
+        self.bitmap
+            .get(pc / 8)
+            .is_some_and(|byte| byte & (1 << (pc % 8)) != 0)
+    }
+
+    /// Valid destinations in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.bitmap.len() * 8).filter(move |&pc| self.is_valid(pc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn push_data_is_not_a_destination() {
+        // 00: JUMPDEST  01: PUSH2 0x5b5b  04: JUMPDEST  05: PUSH1 (truncated)
+        let table = JumpTable::new(&hex!("5b615b5b5b60"));
+        assert!(table.is_valid(0));
+        assert!(!table.is_valid(2));
+        assert!(!table.is_valid(3));
+        assert!(table.is_valid(4));
+        assert!(!table.is_valid(5));
+        assert!(!table.is_valid(100));
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec![0, 4]);
+    }
+
+    #[test]
+    fn push32_data() {
+        let mut code = vec![0x7f];
+        code.extend([0x5b; 32]);
+        code.push(0x5b);
+        let table = JumpTable::new(&code);
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec![33]);
+    }
+}
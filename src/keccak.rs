@@ -0,0 +1,194 @@
+/// Round constants of the ι step.
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Rotation offsets of the ρ step, in the lane order visited by `PI_LANES`.
+const RHO_OFFSETS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+/// Lane permutation of the π step.
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// Bytes absorbed per permutation: 1600 bits of state minus 512 bits of capacity.
+const RATE: usize = 136;
+
+/// Streaming Keccak-256 hasher.
+///
+/// This is the original Keccak submission used by Ethereum, which pads with `0x01`
+/// rather than the `0x06` of the standardised SHA3-256.
+#[derive(Debug, Clone)]
+pub struct Keccak256 {
+    state: [u64; 25],
+    buffer: [u8; RATE],
+    len: usize,
+}
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Keccak256::new()
+    }
+}
+
+impl Keccak256 {
+    pub fn new() -> Keccak256 {
+        Keccak256 {
+            state: [0; 25],
+            buffer: [0; RATE],
+            len: 0,
+        }
+    }
+
+    /// Absorbs `data`. May be called any number of times before `finalize`.
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = (RATE - self.len).min(data.len());
+            self.buffer[self.len..self.len + take].copy_from_slice(&data[..take]);
+            self.len += take;
+            data = &data[take..];
+            if self.len == RATE {
+                self.absorb_block();
+            }
+        }
+    }
+
+    /// Pads the remaining input and returns the 32-byte digest.
+    pub fn finalize(mut self) -> [u8; 32] {
+        self.buffer[self.len..].fill(0);
+        self.buffer[self.len] ^= 0x01;
+        self.buffer[RATE - 1] ^= 0x80;
+        self.absorb_block();
+
+        let mut digest = [0; 32];
+        for (chunk, lane) in digest.chunks_exact_mut(8).zip(self.state.iter()) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+        digest
+    }
+
+    fn absorb_block(&mut self) {
+        for (lane, chunk) in self.state.iter_mut().zip(self.buffer.chunks_exact(8)) {
+            *lane ^= u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        keccak_f(&mut self.state);
+        self.len = 0;
+    }
+}
+
+/// Keccak-256 digest of `data`.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// The Keccak-f[1600] permutation.
+fn keccak_f(state: &mut [u64; 25]) {
+    for round_constant in ROUND_CONSTANTS {
+        // θ
+        let mut columns = [0u64; 5];
+        for (x, column) in columns.iter_mut().enumerate() {
+            *column = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+
+        // ρ and π
+        let mut last = state[1];
+        for (&lane, &offset) in PI_LANES.iter().zip(RHO_OFFSETS.iter()) {
+            let current = state[lane];
+            state[lane] = last.rotate_left(offset);
+            last = current;
+        }
+
+        // χ
+        for y in 0..5 {
+            let row = [
+                state[5 * y],
+                state[5 * y + 1],
+                state[5 * y + 2],
+                state[5 * y + 3],
+                state[5 * y + 4],
+            ];
+            for x in 0..5 {
+                state[5 * y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // ι
+        state[0] ^= round_constant;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn known_vectors() {
+        assert_eq!(
+            keccak256(b""),
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            keccak256(b"abc"),
+            hex!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+        );
+        assert_eq!(
+            keccak256(b"The quick brown fox jumps over the lazy dog"),
+            hex!("4d741b6f1eb29cb2a9b9911c82f56fa8d73b04959d3d9d222895df6c0b28aa15")
+        );
+        assert_eq!(
+            keccak256(b"transfer(address,uint256)")[..4],
+            hex!("a9059cbb")
+        );
+    }
+
+    #[test]
+    fn block_boundaries() {
+        // Inputs around the rate, where padding spills into an extra block.
+        for len in [RATE - 1, RATE, RATE + 1, 2 * RATE] {
+            let data = vec![0xa5; len];
+            let mut hasher = Keccak256::new();
+            for chunk in data.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), keccak256(&data), "length {}", len);
+        }
+        assert_eq!(
+            keccak256(&[0; RATE]),
+            hex!("3a5912a7c5faa06ee4fe906253e339467a9ce87d533c65be3c15cb231cdb25f9")
+        );
+    }
+}
@@ -1,4 +1,21 @@
+pub mod address;
+pub mod asm;
+pub mod cfg;
+pub mod creation;
 pub mod disasm;
+pub mod hardfork;
+pub mod hex;
+pub mod interpreter;
+pub mod jumptable;
+pub mod keccak;
+pub mod metadata;
+pub mod rlp;
+pub mod selectors;
+pub mod signatures;
+pub mod statetest;
+pub mod trie;
+pub mod tx;
+pub mod u256;
 pub mod yul;
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,270 @@
+use crate::hex;
+
+/// Contract metadata the Solidity compiler appends to runtime code.
+///
+/// The trailer is a CBOR map followed by its length as a big-endian `u16`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// Compiler version, e.g. `0.8.19`. Nightly builds store a full version string.
+    pub solc: Option<String>,
+    /// Multihash of the metadata JSON on IPFS.
+    pub ipfs: Option<Vec<u8>>,
+    /// Swarm hash of the metadata JSON, legacy `bzzr0` format.
+    pub bzzr0: Option<Vec<u8>>,
+    /// Swarm hash of the metadata JSON, `bzzr1` format.
+    pub bzzr1: Option<Vec<u8>>,
+    /// Compiled with experimental features enabled.
+    pub experimental: bool,
+}
+
+impl Metadata {
+    /// IPFS hash in the base58 `Qm...` form used by IPFS tooling.
+    pub fn ipfs_cid(&self) -> Option<String> {
+        self.ipfs.as_deref().map(base58)
+    }
+}
+
+impl std::fmt::Display for Metadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields = Vec::new();
+        if let Some(solc) = &self.solc {
+            fields.push(format!("solc {}", solc));
+        }
+        if let Some(cid) = self.ipfs_cid() {
+            fields.push(format!("ipfs {}", cid));
+        }
+        if let Some(hash) = &self.bzzr0 {
+            fields.push(format!("bzzr0 0x{}", hex::encode(hash)));
+        }
+        if let Some(hash) = &self.bzzr1 {
+            fields.push(format!("bzzr1 0x{}", hex::encode(hash)));
+        }
+        if self.experimental {
+            fields.push("experimental".to_string());
+        }
+        f.write_str(&fields.join(", "))
+    }
+}
+
+/// Splits runtime code into the executable part and the metadata trailer.
+///
+/// Returns the code unchanged when it does not end with a trailer that decodes as
+/// Solidity metadata.
+pub fn split(code: &[u8]) -> (&[u8], Option<Metadata>) {
+    if code.len() < 2 {
+        return (code, None);
+    }
+    let len = u16::from_be_bytes([code[code.len() - 2], code[code.len() - 1]]) as usize;
+    if len + 2 > code.len() {
+        return (code, None);
+    }
+    let start = code.len() - 2 - len;
+    match decode(&code[start..code.len() - 2]) {
+        Some(metadata) => (&code[..start], Some(metadata)),
+        None => (code, None),
+    }
+}
+
+/// Decodes the CBOR map of a metadata trailer, without the length suffix.
+pub fn decode(cbor: &[u8]) -> Option<Metadata> {
+    let mut decoder = Decoder {
+        input: cbor,
+        pos: 0,
+    };
+    let entries = decoder.map()?;
+    if decoder.pos != cbor.len() {
+        return None;
+    }
+
+    let mut metadata = Metadata::default();
+    let mut known = false;
+    for (key, value) in entries {
+        match (key.as_str(), value) {
+            ("solc", Value::Bytes(version)) if version.len() == 3 => {
+                metadata.solc = Some(format!("{}.{}.{}", version[0], version[1], version[2]));
+            }
+            ("solc", Value::Text(version)) => metadata.solc = Some(version),
+            ("ipfs", Value::Bytes(hash)) => metadata.ipfs = Some(hash),
+            ("bzzr0", Value::Bytes(hash)) => metadata.bzzr0 = Some(hash),
+            ("bzzr1", Value::Bytes(hash)) => metadata.bzzr1 = Some(hash),
+            ("experimental", Value::Bool(experimental)) => metadata.experimental = experimental,
+            // Keys added by future compiler versions.
+            _ => continue,
+        }
+        known = true;
+    }
+    known.then_some(metadata)
+}
+
+enum Value {
+    Uint,
+    Bytes(Vec<u8>),
+    Text(String),
+    Bool(bool),
+}
+
+/// Decoder for the subset of CBOR that solc emits: a flat map with text keys.
+struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, len: usize) -> Option<&[u8]> {
+        let bytes = self.input.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    /// Argument of the item header with the given additional information.
+    fn argument(&mut self, info: u8) -> Option<u64> {
+        let size = match info {
+            0..=23 => return Some(info as u64),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            _ => return None,
+        };
+        let bytes = self.take(size)?;
+        Some(
+            bytes
+                .iter()
+                .fold(0, |value, &byte| value << 8 | byte as u64),
+        )
+    }
+
+    /// The top-level map. Its values cannot be maps, so decoding never recurses.
+    fn map(&mut self) -> Option<Vec<(String, Value)>> {
+        let header = *self.take(1)?.first()?;
+        let (major, info) = (header >> 5, header & 0x1f);
+        if major != 5 {
+            return None;
+        }
+        let len = self.argument(info)? as usize;
+        let mut entries = Vec::new();
+        for _ in 0..len {
+            let key = match self.item()? {
+                Value::Text(key) => key,
+                _ => return None,
+            };
+            entries.push((key, self.item()?));
+        }
+        Some(entries)
+    }
+
+    fn item(&mut self) -> Option<Value> {
+        let header = *self.take(1)?.first()?;
+        let (major, info) = (header >> 5, header & 0x1f);
+        match major {
+            0 => self.argument(info).map(|_| Value::Uint),
+            2 => {
+                let len = self.argument(info)? as usize;
+                self.take(len).map(|bytes| Value::Bytes(bytes.to_vec()))
+            }
+            3 => {
+                let len = self.argument(info)? as usize;
+                let text = std::str::from_utf8(self.take(len)?).ok()?;
+                Some(Value::Text(text.to_string()))
+            }
+            7 => match info {
+                20 => Some(Value::Bool(false)),
+                21 => Some(Value::Bool(true)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn base58(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in bytes {
+        let mut carry = byte as usize;
+        for digit in digits.iter_mut() {
+            carry += (*digit as usize) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeroes = bytes.iter().take_while(|&&byte| byte == 0).count();
+    std::iter::repeat_n('1', zeroes)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&digit| ALPHABET[digit as usize] as char),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn split_solc_trailer() {
+        let code = hex!(
+            "6080604052600080fdfe"
+            "a2646970667358221220"
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            "64736f6c63430008130033"
+        );
+        let (runtime, metadata) = split(&code);
+        assert_eq!(runtime, &hex!("6080604052600080fdfe"));
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata.solc.as_deref(), Some("0.8.19"));
+        assert_eq!(
+            metadata.ipfs_cid().as_deref(),
+            Some("QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n")
+        );
+        assert!(!metadata.experimental);
+        assert_eq!(
+            metadata.to_string(),
+            "solc 0.8.19, ipfs QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n"
+        );
+    }
+
+    #[test]
+    fn split_bzzr0_experimental() {
+        let code = hex!(
+            "00"
+            "a265627a7a72305820"
+            "0000000000000000000000000000000000000000000000000000000000000001"
+            "6c6578706572696d656e74616cf5"
+            "0037"
+        );
+        let (runtime, metadata) = split(&code);
+        assert_eq!(runtime, &hex!("00"));
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata.bzzr0.unwrap()[31], 1);
+        assert!(metadata.experimental);
+        assert_eq!(metadata.solc, None);
+    }
+
+    #[test]
+    fn no_trailer() {
+        let code = hex!("6080604052600080fd0003");
+        assert_eq!(split(&code), (&code[..], None));
+        assert_eq!(split(&[]), (&[][..], None));
+        // Valid CBOR without any known key.
+        assert_eq!(split(&hex!("a16161000004")).1, None);
+    }
+
+    #[test]
+    fn nested_maps() {
+        // solc only emits a flat map, so a map value is rejected rather than recursed
+        // into, however deep the nesting.
+        assert_eq!(split(&hex!("a16161a16162f50007")).1, None);
+        let mut code = hex!("a160").repeat(32_000);
+        code.extend([0xf5, 0xfa, 0x01]);
+        assert_eq!(split(&code).1, None);
+    }
+}
@@ -0,0 +1,506 @@
+//! Recursive Length Prefix encoding, the serialization of transactions, receipts and
+//! trie nodes.
+//!
+//! A value is either a byte string or a list of values. [`Encode`] and [`Decode`] map
+//! Rust types onto them: byte slices, vectors and arrays of `u8` are byte strings,
+//! unsigned integers are big-endian byte strings without leading zeros, and vectors
+//! of other types and tuples are lists. Decoding rejects every non-canonical encoding,
+//! so each value has exactly one accepted form.
+
+use crate::u256::U256;
+
+/// Deepest nesting of lists that decoding accepts, so that hostile input cannot
+/// exhaust the stack.
+pub const MAX_DEPTH: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RlpError {
+    /// The input ends inside an item.
+    UnexpectedEnd,
+    /// Bytes follow the end of the top-level item.
+    TrailingBytes,
+    /// A length that fits the short form is encoded in the long form, or has leading
+    /// zero bytes.
+    NonCanonicalLength,
+    /// A single byte below `0x80` is encoded as a one-byte string instead of itself.
+    NonCanonicalByte,
+    /// An integer has leading zero bytes.
+    LeadingZero,
+    /// An integer does not fit the type it is decoded into, or a length does not fit
+    /// in memory.
+    Overflow,
+    /// Lists are nested deeper than [`MAX_DEPTH`].
+    TooDeep,
+    ExpectedBytes,
+    ExpectedList,
+    /// A fixed-size byte string or a list decoded into a tuple has the wrong length.
+    WrongLength {
+        expected: usize,
+        got: usize,
+    },
+}
+
+impl std::fmt::Display for RlpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RlpError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            RlpError::TrailingBytes => write!(f, "trailing bytes after the item"),
+            RlpError::NonCanonicalLength => write!(f, "non-canonical length"),
+            RlpError::NonCanonicalByte => {
+                write!(f, "single byte below 0x80 encoded as a string")
+            }
+            RlpError::LeadingZero => write!(f, "integer with leading zero bytes"),
+            RlpError::Overflow => write!(f, "integer too large"),
+            RlpError::TooDeep => write!(f, "lists nested too deeply"),
+            RlpError::ExpectedBytes => write!(f, "expected a byte string, found a list"),
+            RlpError::ExpectedList => write!(f, "expected a list, found a byte string"),
+            RlpError::WrongLength { expected, got } => {
+                write!(f, "expected {} elements, found {}", expected, got)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RlpError {}
+
+/// Decoded value, before it is interpreted as a Rust type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Bytes(Vec<u8>),
+    List(Vec<Item>),
+}
+
+impl Item {
+    pub fn as_bytes(&self) -> Result<&[u8], RlpError> {
+        match self {
+            Item::Bytes(bytes) => Ok(bytes),
+            Item::List(_) => Err(RlpError::ExpectedBytes),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Item], RlpError> {
+        match self {
+            Item::List(items) => Ok(items),
+            Item::Bytes(_) => Err(RlpError::ExpectedList),
+        }
+    }
+
+    pub fn decode<T: Decode>(&self) -> Result<T, RlpError> {
+        T::decode(self)
+    }
+}
+
+/// Types with an RLP encoding.
+pub trait Encode {
+    /// Appends the encoding of `self` to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+/// Types that can be read back from a decoded [`Item`].
+pub trait Decode: Sized {
+    fn decode(item: &Item) -> Result<Self, RlpError>;
+}
+
+pub fn encode<T: Encode + ?Sized>(value: &T) -> Vec<u8> {
+    let mut out = Vec::new();
+    value.encode(&mut out);
+    out
+}
+
+/// Decodes `bytes`, which must hold exactly one item.
+pub fn decode<T: Decode>(bytes: &[u8]) -> Result<T, RlpError> {
+    decode_item(bytes)?.decode()
+}
+
+/// Parses `bytes`, which must hold exactly one item, into its tree.
+pub fn decode_item(bytes: &[u8]) -> Result<Item, RlpError> {
+    let (item, rest) = parse(bytes, 0)?;
+    if !rest.is_empty() {
+        return Err(RlpError::TrailingBytes);
+    }
+    Ok(item)
+}
+
+/// Parses the item at the start of `bytes`, inside `depth` lists, returning it and the
+/// bytes after it.
+fn parse(bytes: &[u8], depth: usize) -> Result<(Item, &[u8]), RlpError> {
+    let (&prefix, rest) = bytes.split_first().ok_or(RlpError::UnexpectedEnd)?;
+    let (is_list, len, rest) = match prefix {
+        0x00..=0x7f => return Ok((Item::Bytes(vec![prefix]), rest)),
+        0x80..=0xb7 => (false, (prefix - 0x80) as usize, rest),
+        0xb8..=0xbf => {
+            let (len, rest) = parse_length(rest, (prefix - 0xb7) as usize)?;
+            (false, len, rest)
+        }
+        0xc0..=0xf7 => (true, (prefix - 0xc0) as usize, rest),
+        0xf8..=0xff => {
+            let (len, rest) = parse_length(rest, (prefix - 0xf7) as usize)?;
+            (true, len, rest)
+        }
+    };
+    if rest.len() < len {
+        return Err(RlpError::UnexpectedEnd);
+    }
+    let (payload, rest) = rest.split_at(len);
+    if !is_list {
+        if let [byte] = payload {
+            if *byte < 0x80 {
+                return Err(RlpError::NonCanonicalByte);
+            }
+        }
+        return Ok((Item::Bytes(payload.to_vec()), rest));
+    }
+    if depth == MAX_DEPTH {
+        return Err(RlpError::TooDeep);
+    }
+    let mut items = Vec::new();
+    let mut payload = payload;
+    while !payload.is_empty() {
+        let (item, remaining) = parse(payload, depth + 1)?;
+        items.push(item);
+        payload = remaining;
+    }
+    Ok((Item::List(items), rest))
+}
+
+/// Reads the `size`-byte length of a long-form item.
+fn parse_length(bytes: &[u8], size: usize) -> Result<(usize, &[u8]), RlpError> {
+    if bytes.len() < size {
+        return Err(RlpError::UnexpectedEnd);
+    }
+    let (len, rest) = bytes.split_at(size);
+    if len[0] == 0 {
+        return Err(RlpError::NonCanonicalLength);
+    }
+    if size > std::mem::size_of::<usize>() {
+        return Err(RlpError::Overflow);
+    }
+    let len = len
+        .iter()
+        .fold(0usize, |len, &byte| (len << 8) | byte as usize);
+    if len < 56 {
+        return Err(RlpError::NonCanonicalLength);
+    }
+    Ok((len, rest))
+}
+
+/// Appends the prefix of a byte string (`offset` 0x80) or list (0xc0) of `len` bytes.
+fn encode_header(len: usize, offset: u8, out: &mut Vec<u8>) {
+    if len < 56 {
+        out.push(offset + len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let bytes = &bytes[len.leading_zeros() as usize / 8..];
+        out.push(offset + 55 + bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+}
+
+/// Appends a list whose payload is written by `payload`.
+pub fn encode_list_with(out: &mut Vec<u8>, payload: impl FnOnce(&mut Vec<u8>)) {
+    let mut items = Vec::new();
+    payload(&mut items);
+    encode_header(items.len(), 0xc0, out);
+    out.extend(items);
+}
+
+impl Encode for [u8] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            [byte] if *byte < 0x80 => out.push(*byte),
+            _ => {
+                encode_header(self.len(), 0x80, out);
+                out.extend_from_slice(self);
+            }
+        }
+    }
+}
+
+impl Encode for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode(out)
+    }
+}
+
+impl<const N: usize> Encode for [u8; N] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode(out)
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_bytes().encode(out)
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        encode_list_with(out, |out| {
+            for item in self {
+                item.encode(out);
+            }
+        })
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode(out)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (**self).encode(out)
+    }
+}
+
+impl Encode for Item {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Item::Bytes(bytes) => bytes.encode(out),
+            Item::List(items) => items.encode(out),
+        }
+    }
+}
+
+impl Encode for U256 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let bytes = self.to_be_bytes();
+        bytes[self.leading_zeros() as usize / 8..].encode(out)
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out)
+    }
+}
+
+macro_rules! integers {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    let bytes = self.to_be_bytes();
+                    bytes[self.leading_zeros() as usize / 8..].encode(out)
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(item: &Item) -> Result<Self, RlpError> {
+                    let bytes = integer_bytes(item)?;
+                    if bytes.len() > std::mem::size_of::<$ty>() {
+                        return Err(RlpError::Overflow);
+                    }
+                    Ok(bytes.iter().fold(0, |value, &byte| (value << 8) | byte as $ty))
+                }
+            }
+        )*
+    };
+}
+
+integers!(u16, u32, u64, u128, usize);
+
+/// Bytes of an integer, checked to have no leading zeros.
+fn integer_bytes(item: &Item) -> Result<&[u8], RlpError> {
+    let bytes = item.as_bytes()?;
+    if bytes.first() == Some(&0) {
+        return Err(RlpError::LeadingZero);
+    }
+    Ok(bytes)
+}
+
+impl Decode for U256 {
+    fn decode(item: &Item) -> Result<Self, RlpError> {
+        let bytes = integer_bytes(item)?;
+        if bytes.len() > 32 {
+            return Err(RlpError::Overflow);
+        }
+        Ok(U256::from_be_slice(bytes))
+    }
+}
+
+impl Decode for bool {
+    fn decode(item: &Item) -> Result<Self, RlpError> {
+        match u64::decode(item)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(RlpError::Overflow),
+        }
+    }
+}
+
+impl Decode for Vec<u8> {
+    fn decode(item: &Item) -> Result<Self, RlpError> {
+        item.as_bytes().map(<[u8]>::to_vec)
+    }
+}
+
+impl<const N: usize> Decode for [u8; N] {
+    fn decode(item: &Item) -> Result<Self, RlpError> {
+        let bytes = item.as_bytes()?;
+        bytes.try_into().map_err(|_| RlpError::WrongLength {
+            expected: N,
+            got: bytes.len(),
+        })
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(item: &Item) -> Result<Self, RlpError> {
+        item.as_list()?.iter().map(T::decode).collect()
+    }
+}
+
+impl Decode for Item {
+    fn decode(item: &Item) -> Result<Self, RlpError> {
+        Ok(item.clone())
+    }
+}
+
+macro_rules! tuples {
+    ($(($($name:ident $index:tt),+))*) => {
+        $(
+            impl<$($name: Encode),+> Encode for ($($name,)+) {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    encode_list_with(out, |out| {
+                        $(self.$index.encode(out);)+
+                    })
+                }
+            }
+
+            impl<$($name: Decode),+> Decode for ($($name,)+) {
+                fn decode(item: &Item) -> Result<Self, RlpError> {
+                    let items = item.as_list()?;
+                    let expected = [$($index),+].len();
+                    if items.len() != expected {
+                        return Err(RlpError::WrongLength {
+                            expected,
+                            got: items.len(),
+                        });
+                    }
+                    Ok(($($name::decode(&items[$index])?,)+))
+                }
+            }
+        )*
+    };
+}
+
+tuples! {
+    (A 0)
+    (A 0, B 1)
+    (A 0, B 1, C 2)
+    (A 0, B 1, C 2, D 3)
+    (A 0, B 1, C 2, D 3, E 4)
+    (A 0, B 1, C 2, D 3, E 4, F 5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn encoding() {
+        assert_eq!(encode("dog"), hex!("83646f67"));
+        assert_eq!(encode(&vec!["cat", "dog"]), hex!("c88363617483646f67"));
+        assert_eq!(encode(&[0u8; 0][..]), hex!("80"));
+        assert_eq!(encode(&Vec::<u64>::new()), hex!("c0"));
+        assert_eq!(encode(&0u64), hex!("80"));
+        assert_eq!(encode(&[0u8][..]), hex!("00"));
+        assert_eq!(encode(&15u64), hex!("0f"));
+        assert_eq!(encode(&1024u64), hex!("820400"));
+        assert_eq!(encode(&U256::from(0x80u64)), hex!("8180"));
+        // The set-theoretical representation of three.
+        let three = Item::List(vec![
+            Item::List(vec![]),
+            Item::List(vec![Item::List(vec![])]),
+            Item::List(vec![
+                Item::List(vec![]),
+                Item::List(vec![Item::List(vec![])]),
+            ]),
+        ]);
+        assert_eq!(encode(&three), hex!("c7c0c1c0c3c0c1c0"));
+        let lorem = "Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let encoded = encode(lorem);
+        assert_eq!(encoded[..2], hex!("b838"));
+        assert_eq!(encoded[2..], *lorem.as_bytes());
+        assert_eq!(encode(&(1u64, "a", vec![2u64])), hex!("c40161c102"));
+    }
+
+    #[test]
+    fn decoding() {
+        assert_eq!(decode::<Vec<u8>>(&hex!("83646f67")).unwrap(), b"dog");
+        assert_eq!(decode::<u64>(&hex!("820400")).unwrap(), 1024);
+        assert_eq!(decode::<u64>(&hex!("80")).unwrap(), 0);
+        assert_eq!(
+            decode::<Vec<Vec<u8>>>(&hex!("c88363617483646f67")).unwrap(),
+            [b"cat".to_vec(), b"dog".to_vec()]
+        );
+        assert_eq!(
+            decode::<(u64, Vec<u8>, Vec<u64>)>(&hex!("c40161c102")).unwrap(),
+            (1, b"a".to_vec(), vec![2])
+        );
+        let lorem = encode("Lorem ipsum dolor sit amet, consectetur adipisicing elit");
+        assert_eq!(
+            decode_item(&lorem).unwrap(),
+            Item::Bytes(lorem[2..].to_vec())
+        );
+        let large = U256::MAX;
+        assert_eq!(decode::<U256>(&encode(&large)).unwrap(), large);
+        assert_eq!(decode::<[u8; 2]>(&hex!("820400")).unwrap(), [4, 0]);
+    }
+
+    #[test]
+    fn canonical_form() {
+        let error = |bytes: &[u8]| decode_item(bytes).unwrap_err();
+        assert_eq!(error(&hex!("")), RlpError::UnexpectedEnd);
+        assert_eq!(error(&hex!("83646f")), RlpError::UnexpectedEnd);
+        assert_eq!(error(&hex!("c283646f67")), RlpError::UnexpectedEnd);
+        assert_eq!(error(&hex!("8180ff")), RlpError::TrailingBytes);
+        assert_eq!(error(&hex!("8100")), RlpError::NonCanonicalByte);
+        assert_eq!(error(&hex!("b80100")), RlpError::NonCanonicalLength);
+        let mut padded = hex!("b90038").to_vec();
+        padded.extend([0x80; 0x38]);
+        assert_eq!(error(&padded), RlpError::NonCanonicalLength);
+        assert_eq!(error(&hex!("f800")), RlpError::NonCanonicalLength);
+
+        assert_eq!(decode::<u64>(&hex!("820004")), Err(RlpError::LeadingZero));
+        if std::mem::size_of::<usize>() < 8 {
+            assert_eq!(error(&hex!("bf0100000000000000")), RlpError::Overflow);
+        }
+        assert_eq!(decode::<u64>(&hex!("00")), Err(RlpError::LeadingZero));
+        assert_eq!(decode::<u16>(&hex!("83010000")), Err(RlpError::Overflow));
+        assert_eq!(decode::<u64>(&hex!("c0")), Err(RlpError::ExpectedBytes));
+        assert_eq!(decode::<Vec<u64>>(&hex!("80")), Err(RlpError::ExpectedList));
+        assert_eq!(
+            decode::<(u64, u64)>(&hex!("c101")),
+            Err(RlpError::WrongLength {
+                expected: 2,
+                got: 1
+            })
+        );
+    }
+
+    /// Lists nested `depth` deep around an empty list.
+    fn nested(depth: usize) -> Vec<u8> {
+        // Built back to front, as each header depends on the length of what it wraps.
+        let mut reversed = vec![0xc0];
+        for _ in 0..depth {
+            let mut header = Vec::new();
+            encode_header(reversed.len(), 0xc0, &mut header);
+            reversed.extend(header.iter().rev());
+        }
+        reversed.reverse();
+        reversed
+    }
+
+    #[test]
+    fn nesting() {
+        assert!(decode_item(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_eq!(decode_item(&nested(MAX_DEPTH)), Err(RlpError::TooDeep));
+        assert_eq!(decode_item(&nested(10_000)), Err(RlpError::TooDeep));
+    }
+}
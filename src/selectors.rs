@@ -0,0 +1,144 @@
+use crate::disasm::{Instruction, Opcode};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Recovers the external functions of a contract from its dispatcher.
+///
+/// Returns a map from 4-byte selectors to the offset execution continues at when
+/// the selector matches. The following comparisons are recognised anywhere in the
+/// code, which also covers the binary-search dispatchers solc emits for large
+/// contracts, since every leaf still ends in an equality check:
+///
+/// ```text
+/// PUSH4 sel  EQ  PUSH2 dest  JUMPI           ; solc
+/// PUSH4 sel  DUP2  EQ  PUSH2 dest  JUMPI     ; older solc
+/// PUSH4 sel  DUP2  XOR  PUSH2 next  JUMPI    ; vyper, entry is the fallthrough
+/// PUSH4 sel  EQ  ISZERO  PUSH2 next  JUMPI   ; vyper, entry is the fallthrough
+/// ```
+///
+/// Selectors with leading zero bytes are pushed with a shorter `PUSHn`; those are only
+/// accepted right after a `DUP1` to avoid matching ordinary comparisons. Dispatchers
+/// that jump through a computed table (vyper 0.3.10+) are not recognised.
+pub fn selectors(instructions: &[Instruction]) -> BTreeMap<[u8; 4], usize> {
+    let jumpdests: BTreeSet<usize> = instructions
+        .iter()
+        .filter(|instruction| instruction.opcode == Opcode::JUMPDEST)
+        .map(|instruction| instruction.pc)
+        .collect();
+
+    let mut selectors = BTreeMap::new();
+    for index in 0..instructions.len() {
+        let selector = match selector_push(instructions, index) {
+            Some(selector) => selector,
+            None => continue,
+        };
+        let rest: Vec<Opcode> = instructions[index + 1..]
+            .iter()
+            .take(5)
+            .map(|instruction| instruction.opcode)
+            .collect();
+
+        // Position of the `PUSHn` holding the jump target, relative to `index`, and
+        // whether the jump is taken on a match.
+        let (push, on_match) = match rest.as_slice() {
+            [Opcode::EQ, Opcode::ISZERO, ..] => (3, false),
+            [Opcode::EQ, ..] => (2, true),
+            [Opcode::DUP2, Opcode::EQ, ..] => (3, true),
+            [Opcode::DUP2, Opcode::XOR, ..] => (3, false),
+            _ => continue,
+        };
+        let target = match (
+            instructions.get(index + push),
+            instructions.get(index + push + 1),
+        ) {
+            (Some(target), Some(jumpi)) if jumpi.opcode == Opcode::JUMPI => {
+                if target.opcode.immediate_size() == 0 || target.immediate.len() > 8 {
+                    continue;
+                }
+                let dest = target
+                    .immediate
+                    .iter()
+                    .fold(0usize, |value, &byte| value << 8 | byte as usize);
+                if on_match {
+                    dest
+                } else {
+                    jumpi.next_pc()
+                }
+            }
+            _ => continue,
+        };
+        if jumpdests.contains(&target) || !on_match {
+            selectors.entry(selector).or_insert(target);
+        }
+    }
+    selectors
+}
+
+/// Selector pushed by the instruction at `index`, if it looks like one.
+fn selector_push(instructions: &[Instruction], index: usize) -> Option<[u8; 4]> {
+    let instruction = &instructions[index];
+    let size = instruction.opcode.immediate_size();
+    let after_dup = index > 0 && instructions[index - 1].opcode == Opcode::DUP1;
+    if size == 0 || size > 4 || instruction.truncated || (size < 4 && !after_dup) {
+        return None;
+    }
+    let mut selector = [0; 4];
+    selector[4 - size..].copy_from_slice(&instruction.immediate);
+    Some(selector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn solc_dispatcher() {
+        // 00: PUSH1 0x00 CALLDATALOAD PUSH1 0xe0 SHR
+        // 06: DUP1 PUSH4 0xa9059cbb EQ PUSH2 0x001f JUMPI
+        // 11: DUP1 PUSH3 0xfdd58e EQ PUSH2 0x0021 JUMPI
+        // 1b: PUSH1 0x00 DUP1 REVERT
+        // 1f: JUMPDEST STOP
+        // 21: JUMPDEST STOP
+        let code = hex!(
+            "60003560e01c"
+            "8063a9059cbb1461001f57"
+            "8062fdd58e1461002157"
+            "600080fd"
+            "5b005b00"
+        );
+        let found = selectors(&Opcode::disasm(&code));
+        assert_eq!(
+            found.into_iter().collect::<Vec<_>>(),
+            vec![(hex!("00fdd58e"), 0x21), (hex!("a9059cbb"), 0x1f)]
+        );
+    }
+
+    #[test]
+    fn vyper_dispatcher() {
+        // 00: PUSH4 0x12345678 DUP2 XOR PUSH2 0x000c JUMPI
+        // 0b: STOP
+        // 0c: JUMPDEST PUSH4 0xaabbccdd EQ ISZERO PUSH2 0x0019 JUMPI
+        // 18: STOP
+        // 19: JUMPDEST STOP
+        let code = hex!(
+            "63123456788118"
+            "61000c5700"
+            "5b63aabbccdd1415"
+            "6100195700"
+            "5b00"
+        );
+        let found = selectors(&Opcode::disasm(&code));
+        assert_eq!(
+            found.into_iter().collect::<Vec<_>>(),
+            vec![(hex!("12345678"), 0x0b), (hex!("aabbccdd"), 0x18)]
+        );
+    }
+
+    #[test]
+    fn ordinary_comparisons_are_ignored() {
+        // PUSH1 0x01 EQ PUSH2 0x0007 JUMPI STOP JUMPDEST
+        assert!(selectors(&Opcode::disasm(&hex!("60011461000757005b"))).is_empty());
+        // PUSH4 0xa9059cbb EQ PUSH2 0x0008 JUMPI: target is not a JUMPDEST.
+        assert!(selectors(&Opcode::disasm(&hex!("63a9059cbb1461000857"))).is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::hex;
+use crate::keccak::keccak256;
+use std::collections::HashMap;
+use std::path::Path;
+
+const BUNDLED: &str = include_str!("../data/signatures.txt");
+
+/// Error produced while loading signatures.
+#[derive(Debug)]
+pub enum SignatureError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Line of a text file that is not a `0x<hash> <signature>` pair. Lines start at 1.
+    InvalidLine {
+        line: usize,
+        text: String,
+    },
+    /// JSON entry that is not a hex hash mapped to a signature or list of signatures.
+    InvalidEntry {
+        key: String,
+    },
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureError::Io(err) => write!(f, "{}", err),
+            SignatureError::Json(err) => write!(f, "{}", err),
+            SignatureError::InvalidLine { line, text } => {
+                write!(f, "line {}: invalid signature entry `{}`", line, text)
+            }
+            SignatureError::InvalidEntry { key } => {
+                write!(f, "invalid signature entry for `{}`", key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl From<std::io::Error> for SignatureError {
+    fn from(err: std::io::Error) -> Self {
+        SignatureError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SignatureError {
+    fn from(err: serde_json::Error) -> Self {
+        SignatureError::Json(err)
+    }
+}
+
+/// Maps function selectors and event topics back to text signatures.
+///
+/// Several signatures may share a selector, so lookups return every known match in
+/// insertion order.
+#[derive(Debug, Clone, Default)]
+pub struct SignatureDb {
+    functions: HashMap<[u8; 4], Vec<String>>,
+    events: HashMap<[u8; 32], Vec<String>>,
+}
+
+impl SignatureDb {
+    pub fn new() -> SignatureDb {
+        SignatureDb::default()
+    }
+
+    /// Database preloaded with common ERC-20/721/1155, ownership, access control and
+    /// proxy signatures.
+    pub fn bundled() -> SignatureDb {
+        let mut db = SignatureDb::new();
+        db.add_text(BUNDLED)
+            .expect("bundled signature database is valid");
+        db
+    }
+
+    /// Adds the signatures of a file. Files starting with `{` are read as JSON, anything
+    /// else as text. See [`SignatureDb::add_text`] and [`SignatureDb::add_json`].
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), SignatureError> {
+        let content = std::fs::read_to_string(path)?;
+        if content.trim_start().starts_with('{') {
+            self.add_json(&content)
+        } else {
+            self.add_text(&content)
+        }
+    }
+
+    /// Adds signatures from text with one `0x<hash> <signature>` pair per line.
+    ///
+    /// A 4-byte hash is a function or error selector, a 32-byte hash an event topic.
+    /// A bare signature is hashed and added as both. Empty lines and lines starting
+    /// with `#` are skipped.
+    pub fn add_text(&mut self, text: &str) -> Result<(), SignatureError> {
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || SignatureError::InvalidLine {
+                line: index + 1,
+                text: line.to_string(),
+            };
+            if !line.starts_with("0x") && line.ends_with(')') {
+                self.insert_signature(line);
+                continue;
+            }
+            let (hash, signature) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            if !self.insert(hash, signature.trim()) {
+                return Err(invalid());
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds signatures from a JSON object mapping hashes to a signature or a list of
+    /// signatures, e.g. `{"0xa9059cbb": ["transfer(address,uint256)"]}`.
+    pub fn add_json(&mut self, json: &str) -> Result<(), SignatureError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let entries = match value {
+            serde_json::Value::Object(entries) => entries,
+            _ => return Err(SignatureError::InvalidEntry { key: String::new() }),
+        };
+        for (key, value) in entries {
+            let signatures = match value {
+                serde_json::Value::String(signature) => vec![signature],
+                serde_json::Value::Array(values) => values
+                    .into_iter()
+                    .map(|value| value.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| SignatureError::InvalidEntry { key: key.clone() })?,
+                _ => return Err(SignatureError::InvalidEntry { key }),
+            };
+            for signature in signatures {
+                if !self.insert(&key, &signature) {
+                    return Err(SignatureError::InvalidEntry { key });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts `signature` under the hex `hash`. Returns `false` if the hash is
+    /// neither 4 nor 32 bytes long.
+    fn insert(&mut self, hash: &str, signature: &str) -> bool {
+        let bytes = match hex::decode(hash) {
+            Some(bytes) => bytes,
+            None => return false,
+        };
+        if signature.is_empty() {
+            return false;
+        }
+        if let Ok(selector) = <[u8; 4]>::try_from(bytes.as_slice()) {
+            self.insert_function(selector, signature);
+        } else if let Ok(topic) = <[u8; 32]>::try_from(bytes.as_slice()) {
+            self.insert_event(topic, signature);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Adds `signature` under both its selector and its event topic, since a bare
+    /// signature does not say which one it is.
+    pub fn insert_signature(&mut self, signature: &str) {
+        let topic = keccak256(signature.as_bytes());
+        let mut selector = [0; 4];
+        selector.copy_from_slice(&topic[..4]);
+        self.insert_function(selector, signature);
+        self.insert_event(topic, signature);
+    }
+
+    pub fn insert_function(&mut self, selector: [u8; 4], signature: &str) {
+        let signatures = self.functions.entry(selector).or_default();
+        if !signatures.iter().any(|known| known == signature) {
+            signatures.push(signature.to_string());
+        }
+    }
+
+    pub fn insert_event(&mut self, topic: [u8; 32], signature: &str) {
+        let signatures = self.events.entry(topic).or_default();
+        if !signatures.iter().any(|known| known == signature) {
+            signatures.push(signature.to_string());
+        }
+    }
+
+    /// Signatures of functions and errors with the given selector.
+    pub fn function(&self, selector: [u8; 4]) -> &[String] {
+        self.functions
+            .get(&selector)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Signatures of events with the given topic.
+    pub fn event(&self, topic: [u8; 32]) -> &[String] {
+        self.events
+            .get(&topic)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Signatures matching a PUSH operand: a selector for 4 bytes, an event topic for
+    /// 32 bytes and nothing otherwise.
+    pub fn lookup(&self, operand: &[u8]) -> &[String] {
+        if let Ok(selector) = <[u8; 4]>::try_from(operand) {
+            self.function(selector)
+        } else if let Ok(topic) = <[u8; 32]>::try_from(operand) {
+            self.event(topic)
+        } else {
+            &[]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn bundled_signatures() {
+        let db = SignatureDb::bundled();
+        assert_eq!(db.function(hex!("a9059cbb")), ["transfer(address,uint256)"]);
+        assert_eq!(db.function(hex!("08c379a0")), ["Error(string)"]);
+        assert_eq!(
+            db.event(hex!(
+                "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+            )),
+            ["Transfer(address,address,uint256)"]
+        );
+        assert!(db.function(hex!("00000000")).is_empty());
+    }
+
+    #[test]
+    fn add_text_and_json() {
+        let mut db = SignatureDb::new();
+        db.add_text("# comment\n\n0x12345678 foo()\n0x12345678 bar()\n0x12345678 foo()\n")
+            .unwrap();
+        assert_eq!(db.function(hex!("12345678")), ["foo()", "bar()"]);
+
+        db.add_json(r#"{"0xaabbccdd": "baz(uint256)", "0x11223344": ["a()", "b()"]}"#)
+            .unwrap();
+        assert_eq!(db.lookup(&hex!("aabbccdd")), ["baz(uint256)"]);
+        assert_eq!(db.lookup(&hex!("11223344")), ["a()", "b()"]);
+        assert!(db.lookup(&hex!("1122")).is_empty());
+    }
+
+    #[test]
+    fn bare_signatures_are_hashed() {
+        let mut db = SignatureDb::new();
+        db.add_text("approve(address,uint256)\nApproval(address,address,uint256)\n")
+            .unwrap();
+        assert_eq!(db.function(hex!("095ea7b3")), ["approve(address,uint256)"]);
+        assert_eq!(
+            db.event(hex!(
+                "8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925"
+            )),
+            ["Approval(address,address,uint256)"]
+        );
+    }
+
+    #[test]
+    fn invalid_entries() {
+        let mut db = SignatureDb::new();
+        assert!(matches!(
+            db.add_text("0x1234 short()"),
+            Err(SignatureError::InvalidLine { line: 1, .. })
+        ));
+        assert!(matches!(
+            db.add_text("\nfoo"),
+            Err(SignatureError::InvalidLine { line: 2, .. })
+        ));
+        assert!(matches!(
+            db.add_json(r#"{"0x12345678": 1}"#),
+            Err(SignatureError::InvalidEntry { .. })
+        ));
+        assert!(matches!(db.add_json("{"), Err(SignatureError::Json(_))));
+    }
+}
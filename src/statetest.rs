@@ -0,0 +1,876 @@
+//! Runner for the `GeneralStateTests` format of ethereum/tests.
+//!
+//! A test holds a pre-state, a block environment and a transaction template whose
+//! `data`, `gasLimit` and `value` are lists. Each post entry of a fork picks one
+//! element of each list through its `indexes` and describes the expected result.
+//!
+//! A case passes when the transaction is rejected exactly when an exception is
+//! expected, and the state root in `hash`, the logs hash and, if the entry lists the
+//! accounts of the post-state under `state`, the resulting accounts all match. An
+//! entry with neither a `hash` nor a `state` fails, as nothing would be checked.
+
+use crate::address::create_address;
+use crate::hardfork::Hardfork;
+use crate::hex;
+use crate::interpreter::{
+    gas, AccessList, Account, Address, BlockEnv, CallKind, Env, ExecError, Interpreter, Message,
+    Outcome, Precompile, PrecompileOutput, State, Status, TxEnv, WorldState,
+};
+use crate::keccak::keccak256;
+use crate::rlp;
+use crate::u256::U256;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Error produced while loading state tests.
+#[derive(Debug)]
+pub enum StateTestError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Field of a test that is missing or malformed.
+    InvalidField {
+        test: String,
+        field: String,
+    },
+}
+
+impl std::fmt::Display for StateTestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateTestError::Io(err) => write!(f, "{}", err),
+            StateTestError::Json(err) => write!(f, "{}", err),
+            StateTestError::InvalidField { test, field } => {
+                write!(f, "{}: missing or invalid `{}`", test, field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateTestError {}
+
+impl From<std::io::Error> for StateTestError {
+    fn from(err: std::io::Error) -> Self {
+        StateTestError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for StateTestError {
+    fn from(err: serde_json::Error) -> Self {
+        StateTestError::Json(err)
+    }
+}
+
+/// Reason a transaction is rejected before execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidTransaction {
+    /// Transaction type the fork does not support.
+    TypeNotSupported,
+    NonceMismatch {
+        expected: u64,
+        got: u64,
+    },
+    /// The sender has code (EIP-3607).
+    SenderNotEoa,
+    GasLimitExceedsBlock,
+    IntrinsicGasTooLow {
+        intrinsic: u64,
+    },
+    MaxFeeBelowBaseFee,
+    PriorityFeeAboveMaxFee,
+    InsufficientFunds,
+    /// Contract creation with init code above the limit of EIP-3860.
+    InitCodeTooLarge,
+    /// Blob transaction without blobs, with too many of them or with a hash that is
+    /// not a versioned KZG hash.
+    InvalidBlobs,
+    BlobFeeBelowBaseFee,
+}
+
+impl std::fmt::Display for InvalidTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidTransaction::TypeNotSupported => write!(f, "transaction type not supported"),
+            InvalidTransaction::NonceMismatch { expected, got } => {
+                write!(
+                    f,
+                    "nonce {} does not match the account nonce {}",
+                    got, expected
+                )
+            }
+            InvalidTransaction::SenderNotEoa => write!(f, "sender has code"),
+            InvalidTransaction::GasLimitExceedsBlock => {
+                write!(f, "gas limit exceeds the block gas limit")
+            }
+            InvalidTransaction::IntrinsicGasTooLow { intrinsic } => {
+                write!(f, "gas limit below the intrinsic gas {}", intrinsic)
+            }
+            InvalidTransaction::MaxFeeBelowBaseFee => write!(f, "max fee below the base fee"),
+            InvalidTransaction::PriorityFeeAboveMaxFee => {
+                write!(f, "priority fee above the max fee")
+            }
+            InvalidTransaction::InsufficientFunds => write!(f, "insufficient funds"),
+            InvalidTransaction::InitCodeTooLarge => write!(f, "init code too large"),
+            InvalidTransaction::InvalidBlobs => write!(f, "invalid blobs"),
+            InvalidTransaction::BlobFeeBelowBaseFee => {
+                write!(f, "max blob fee below the blob base fee")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidTransaction {}
+
+/// Transaction executed by [`transact`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transaction {
+    pub sender: Address,
+    /// Recipient, `None` for a contract creation.
+    pub to: Option<Address>,
+    pub nonce: u64,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub gas_limit: u64,
+    /// Gas price of a legacy or EIP-2930 transaction.
+    pub gas_price: Option<U256>,
+    /// Fee caps of an EIP-1559 transaction.
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Set for EIP-2930 and later transaction types.
+    pub access_list: Option<AccessList>,
+    /// Set for EIP-4844 blob transactions.
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blob_hashes: Vec<U256>,
+}
+
+/// Result of a transaction that was included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub outcome: Outcome,
+    /// Gas paid for, after refunds.
+    pub gas_used: u64,
+}
+
+/// Validates `tx` and applies it to `state`: buys its gas, runs its message, refunds the
+/// gas left and pays the priority fee to the coinbase. A rejected transaction leaves
+/// `state` untouched.
+pub fn transact(
+    fork: Hardfork,
+    block: &BlockEnv,
+    state: &mut State,
+    tx: &Transaction,
+) -> Result<Receipt, InvalidTransaction> {
+    if (tx.access_list.is_some() && fork < Hardfork::Berlin)
+        || (tx.max_fee_per_gas.is_some() && fork < Hardfork::London)
+        || (tx.max_fee_per_blob_gas.is_some() && fork < Hardfork::Cancun)
+    {
+        return Err(InvalidTransaction::TypeNotSupported);
+    }
+    let sender = state.account(&tx.sender).cloned().unwrap_or_default();
+    if sender.nonce != tx.nonce {
+        return Err(InvalidTransaction::NonceMismatch {
+            expected: sender.nonce,
+            got: tx.nonce,
+        });
+    }
+    if !sender.code.is_empty() {
+        return Err(InvalidTransaction::SenderNotEoa);
+    }
+    if tx.gas_limit > block.gas_limit {
+        return Err(InvalidTransaction::GasLimitExceedsBlock);
+    }
+    let access_list = tx.access_list.clone().unwrap_or_default();
+    let keys = access_list.iter().map(|(_, keys)| keys.len()).sum();
+    let is_create = tx.to.is_none();
+    if is_create && fork >= Hardfork::Shanghai && tx.data.len() > gas::MAX_INIT_CODE_SIZE {
+        return Err(InvalidTransaction::InitCodeTooLarge);
+    }
+    let intrinsic = gas::intrinsic_gas(fork, &tx.data, is_create, (access_list.len(), keys));
+    let floor = gas::floor_data_gas(fork, &tx.data);
+    if tx.gas_limit < intrinsic.max(floor) {
+        return Err(InvalidTransaction::IntrinsicGasTooLow {
+            intrinsic: intrinsic.max(floor),
+        });
+    }
+
+    // Legacy transactions pay their gas price in full, EIP-1559 ones at most their max
+    // fee and the base fee plus their priority fee.
+    let base_fee = if fork >= Hardfork::London {
+        block.base_fee
+    } else {
+        U256::ZERO
+    };
+    let (max_fee, gas_price) = match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), priority_fee) => {
+            let priority_fee = priority_fee.unwrap_or_default();
+            if priority_fee > max_fee {
+                return Err(InvalidTransaction::PriorityFeeAboveMaxFee);
+            }
+            let price = (base_fee + priority_fee).min(max_fee);
+            (max_fee, price)
+        }
+        (None, _) => {
+            let price = tx.gas_price.unwrap_or_default();
+            (price, price)
+        }
+    };
+    if max_fee < base_fee {
+        return Err(InvalidTransaction::MaxFeeBelowBaseFee);
+    }
+
+    let blob_gas = gas::BLOB_GAS_PER_BLOB * tx.blob_hashes.len() as u64;
+    let mut max_cost = U256::from(tx.gas_limit).checked_mul(max_fee);
+    let mut blob_cost = U256::ZERO;
+    if let Some(max_blob_fee) = tx.max_fee_per_blob_gas {
+        let max_blobs = if fork >= Hardfork::Prague { 9 } else { 6 };
+        if tx.blob_hashes.is_empty()
+            || tx.blob_hashes.len() > max_blobs
+            || tx.blob_hashes.iter().any(|hash| hash.to_be_bytes()[0] != 1)
+        {
+            return Err(InvalidTransaction::InvalidBlobs);
+        }
+        if max_blob_fee < block.blob_base_fee {
+            return Err(InvalidTransaction::BlobFeeBelowBaseFee);
+        }
+        let blob_gas = U256::from(blob_gas);
+        max_cost = max_cost.and_then(|cost| cost.checked_add(blob_gas.checked_mul(max_blob_fee)?));
+        blob_cost = blob_gas * block.blob_base_fee;
+    }
+    match max_cost.and_then(|cost| cost.checked_add(tx.value)) {
+        Some(cost) if cost <= sender.balance => {}
+        _ => return Err(InvalidTransaction::InsufficientFunds),
+    }
+
+    state.increment_nonce(tx.sender);
+    let cost = U256::from(tx.gas_limit) * gas_price + blob_cost;
+    state.set_balance(tx.sender, sender.balance - cost);
+
+    // The value transfer is undone along with everything else when execution fails.
+    state.checkpoint();
+    let to = tx
+        .to
+        .unwrap_or_else(|| create_address(&tx.sender, tx.nonce));
+    let collision = is_create && state.account(&to).is_some_and(Account::has_collision);
+    if is_create {
+        state.create_account(to);
+        if fork >= Hardfork::SpuriousDragon {
+            state.set_nonce(to, 1);
+        }
+    }
+    state.transfer(tx.sender, to, tx.value);
+
+    let env = Env {
+        block: block.clone(),
+        tx: TxEnv {
+            origin: tx.sender,
+            gas_price,
+            blob_hashes: tx.blob_hashes.clone(),
+            access_list,
+        },
+    };
+    let (kind, data, code) = match is_create {
+        true => (CallKind::Create, Vec::new(), tx.data.clone()),
+        false => (CallKind::Call, tx.data.clone(), state.code(&to).to_vec()),
+    };
+    let message = Message {
+        kind,
+        caller: tx.sender,
+        address: to,
+        code_address: to,
+        value: tx.value,
+        data,
+        code,
+        gas: tx.gas_limit - intrinsic,
+        ..Message::default()
+    };
+    let precompile = Precompile::at(fork, &to).filter(|_| !is_create);
+    let outcome = match precompile {
+        _ if collision => Outcome {
+            status: Status::Error(ExecError::CreateCollision),
+            output: Vec::new(),
+            gas_used: message.gas,
+            gas_left: 0,
+            refund: 0,
+            logs: Vec::new(),
+        },
+        Some(precompile) => {
+            let result = precompile.run(fork, &message.data, message.gas);
+            let (status, output, gas_left) = match result {
+                Ok(PrecompileOutput { gas_used, output }) => {
+                    (Status::Return, output, message.gas - gas_used)
+                }
+                Err(err) => (Status::Error(err), Vec::new(), 0),
+            };
+            Outcome {
+                status,
+                output,
+                gas_used: message.gas - gas_left,
+                gas_left,
+                refund: 0,
+                logs: Vec::new(),
+            }
+        }
+        None => {
+            let mut interpreter = Interpreter::new(fork, env, std::mem::take(state), message);
+            let outcome = interpreter.run();
+            *state = interpreter.state;
+            outcome
+        }
+    };
+    if outcome.status.is_success() {
+        state.commit();
+    } else {
+        state.revert();
+    }
+
+    let mut gas_used = tx.gas_limit - outcome.gas_left;
+    gas_used -= outcome.refund.min(gas::max_refund(fork, gas_used));
+    gas_used = gas_used.max(floor);
+    let refund = U256::from(tx.gas_limit - gas_used) * gas_price;
+    state.set_balance(tx.sender, state.balance(&tx.sender) + refund);
+    let fee = U256::from(gas_used) * (gas_price - base_fee);
+    state.set_balance(block.coinbase, state.balance(&block.coinbase) + fee);
+    state.finalize(fork);
+    Ok(Receipt { outcome, gas_used })
+}
+
+/// Element of the `data`, `gasLimit` and `value` lists a post entry refers to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Indexes {
+    pub data: usize,
+    pub gas: usize,
+    pub value: usize,
+}
+
+impl std::fmt::Display for Indexes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "d{}g{}v{}", self.data, self.gas, self.value)
+    }
+}
+
+/// Expected result of one transaction of a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostEntry {
+    pub indexes: Indexes,
+    /// State root after the transaction.
+    pub hash: Option<U256>,
+    /// Keccak-256 of the RLP encoded logs.
+    pub logs: Option<U256>,
+    /// Reason the transaction must be rejected, e.g.
+    /// `TransactionException.INSUFFICIENT_ACCOUNT_FUNDS`.
+    pub expect_exception: Option<String>,
+    /// Every account of the post-state, when the test lists them.
+    pub state: Option<WorldState>,
+}
+
+/// Transaction of a test, with lists of alternatives for some fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionTemplate {
+    /// Sender, given directly or derived from `secretKey`.
+    pub sender: Address,
+    pub to: Option<Address>,
+    pub nonce: u64,
+    pub gas_price: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub data: Vec<Vec<u8>>,
+    pub gas_limit: Vec<u64>,
+    pub value: Vec<U256>,
+    /// One access list per element of `data`, when the transaction has them.
+    pub access_lists: Option<Vec<AccessList>>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blob_hashes: Vec<U256>,
+    /// Whether the transaction carries an EIP-7702 authorization list.
+    pub has_authorizations: bool,
+}
+
+impl TransactionTemplate {
+    /// The transaction selected by `indexes`, `None` if one of them is out of range.
+    pub fn select(&self, indexes: Indexes) -> Option<Transaction> {
+        let access_list = match &self.access_lists {
+            Some(lists) => Some(lists.get(indexes.data)?.clone()),
+            None => None,
+        };
+        Some(Transaction {
+            sender: self.sender,
+            to: self.to,
+            nonce: self.nonce,
+            value: *self.value.get(indexes.value)?,
+            data: self.data.get(indexes.data)?.clone(),
+            gas_limit: *self.gas_limit.get(indexes.gas)?,
+            gas_price: self.gas_price,
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+            access_list,
+            max_fee_per_blob_gas: self.max_fee_per_blob_gas,
+            blob_hashes: self.blob_hashes.clone(),
+        })
+    }
+}
+
+/// One test of a state test file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateTest {
+    pub name: String,
+    pub block: BlockEnv,
+    /// Excess blob gas of the block, from which the blob base fee of each fork is
+    /// derived.
+    pub excess_blob_gas: u64,
+    pub pre: WorldState,
+    pub transaction: TransactionTemplate,
+    /// Expectations per fork. Forks the crate does not know are left out.
+    pub post: BTreeMap<Hardfork, Vec<PostEntry>>,
+}
+
+/// How a case ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail(String),
+    Skip(String),
+}
+
+/// Result of one post entry of a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseResult {
+    pub test: String,
+    pub fork: Hardfork,
+    pub indexes: Indexes,
+    pub verdict: Verdict,
+}
+
+impl StateTest {
+    /// Runs every post entry of every fork, or only those of `fork`.
+    pub fn run(&self, fork: Option<Hardfork>) -> Vec<CaseResult> {
+        let mut results = Vec::new();
+        for (&entry_fork, entries) in &self.post {
+            if fork.is_some_and(|fork| fork != entry_fork) {
+                continue;
+            }
+            for entry in entries {
+                results.push(CaseResult {
+                    test: self.name.clone(),
+                    fork: entry_fork,
+                    indexes: entry.indexes,
+                    verdict: self.run_entry(entry_fork, entry),
+                });
+            }
+        }
+        results
+    }
+
+    fn run_entry(&self, fork: Hardfork, entry: &PostEntry) -> Verdict {
+        let template = &self.transaction;
+        if template.has_authorizations {
+            return Verdict::Skip("set-code transactions are not supported".to_string());
+        }
+        if entry.hash.is_none() && entry.state.is_none() {
+            return Verdict::Fail("no state root or post-state to check".to_string());
+        }
+        let tx = match template.select(entry.indexes) {
+            Some(tx) => tx,
+            None => return Verdict::Fail(format!("indexes {} out of range", entry.indexes)),
+        };
+
+        let mut block = self.block.clone();
+        block.blob_base_fee = gas::blob_base_fee(fork, self.excess_blob_gas);
+        let mut state = State::from(self.pre.clone());
+        let logs = match (
+            transact(fork, &block, &mut state, &tx),
+            &entry.expect_exception,
+        ) {
+            (Ok(_), Some(exception)) => {
+                return Verdict::Fail(format!("expected exception {}", exception));
+            }
+            (Err(err), None) => return Verdict::Fail(format!("transaction rejected: {}", err)),
+            (Ok(receipt), None) => receipt.outcome.logs,
+            (Err(_), Some(_)) => Vec::new(),
+        };
+        if let Some(expected) = entry.logs {
+            let actual = U256::from_be_bytes(keccak256(&rlp::encode(&logs)));
+            if actual != expected {
+                return Verdict::Fail(format!(
+                    "logs hash {:#066x}, expected {:#066x}",
+                    actual, expected
+                ));
+            }
+        }
+        if let Some(expected) = entry.hash {
+            let actual = U256::from_be_bytes(state.world().state_root());
+            if actual != expected {
+                return Verdict::Fail(format!(
+                    "state root {:#066x}, expected {:#066x}",
+                    actual, expected
+                ));
+            }
+        }
+        match entry
+            .state
+            .as_ref()
+            .and_then(|expected| compare_states(state.world(), expected))
+        {
+            Some(difference) => Verdict::Fail(difference),
+            None => Verdict::Pass,
+        }
+    }
+}
+
+/// First difference between the actual and the expected state.
+fn compare_states(actual: &WorldState, expected: &WorldState) -> Option<String> {
+    for (address, expected) in &expected.accounts {
+        let account = match actual.account(address) {
+            Some(account) => account,
+            None => return Some(format!("account 0x{} is missing", hex::encode(address))),
+        };
+        if account.nonce != expected.nonce {
+            return Some(format!(
+                "account 0x{}: nonce {}, expected {}",
+                hex::encode(address),
+                account.nonce,
+                expected.nonce
+            ));
+        }
+        if account.balance != expected.balance {
+            return Some(format!(
+                "account 0x{}: balance {:#x}, expected {:#x}",
+                hex::encode(address),
+                account.balance,
+                expected.balance
+            ));
+        }
+        if account.code != expected.code {
+            return Some(format!("account 0x{}: code differs", hex::encode(address)));
+        }
+        for key in account.storage.keys().chain(expected.storage.keys()) {
+            let value = account.storage.get(key).copied().unwrap_or_default();
+            let expected = expected.storage.get(key).copied().unwrap_or_default();
+            if value != expected {
+                return Some(format!(
+                    "account 0x{}: storage {:#x} is {:#x}, expected {:#x}",
+                    hex::encode(address),
+                    key,
+                    value,
+                    expected
+                ));
+            }
+        }
+    }
+    actual
+        .accounts
+        .keys()
+        .find(|address| !expected.exists(address))
+        .map(|address| format!("unexpected account 0x{}", hex::encode(address)))
+}
+
+/// Reads the tests of a JSON file.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<StateTest>, StateTestError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+/// Parses a JSON object mapping test names to tests.
+pub fn parse(json: &str) -> Result<Vec<StateTest>, StateTestError> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let tests = value
+        .as_object()
+        .ok_or_else(|| StateTestError::InvalidField {
+            test: String::new(),
+            field: "tests".to_string(),
+        })?;
+    tests
+        .iter()
+        .map(|(name, test)| {
+            parse_test(name, test).map_err(|field| StateTestError::InvalidField {
+                test: name.clone(),
+                field,
+            })
+        })
+        .collect()
+}
+
+/// Parses one test. Errors name the offending field.
+fn parse_test(name: &str, test: &serde_json::Value) -> Result<StateTest, String> {
+    let env = &test["env"];
+    let number = parse_u64(env, "currentNumber")?;
+    // Tests hash block numbers as decimal strings, the way geth's runner does.
+    let hashes = (number.saturating_sub(256)..number)
+        .map(|n| (n, U256::from_be_bytes(keccak256(n.to_string().as_bytes()))))
+        .collect();
+    let block = BlockEnv {
+        number,
+        timestamp: parse_u64(env, "currentTimestamp")?,
+        coinbase: parse_address(&env["currentCoinbase"]).ok_or("currentCoinbase")?,
+        gas_limit: parse_u64(env, "currentGasLimit")?,
+        base_fee: optional(env, "currentBaseFee", parse_u256)?.unwrap_or_default(),
+        difficulty: optional(env, "currentDifficulty", parse_u256)?.unwrap_or_default(),
+        prevrandao: optional(env, "currentRandom", parse_u256)?.unwrap_or_default(),
+        chain_id: U256::ONE,
+        blob_base_fee: U256::ZERO,
+        hashes,
+    };
+    let excess_blob_gas = optional(env, "currentExcessBlobGas", parse_u256)?
+        .map(|gas| gas.to_u64().ok_or("currentExcessBlobGas"))
+        .transpose()?
+        .unwrap_or_default();
+
+    let tx = &test["transaction"];
+    let to = match tx["to"].as_str() {
+        Some("") => None,
+        _ => Some(parse_address(&tx["to"]).ok_or("to")?),
+    };
+    let access_lists = optional(tx, "accessLists", |value| {
+        value
+            .as_array()?
+            .iter()
+            .map(|list| match list {
+                serde_json::Value::Null => Some(Vec::new()),
+                list => parse_access_list(list),
+            })
+            .collect()
+    })?;
+    let sender = match optional(tx, "sender", parse_address)? {
+        Some(sender) => sender,
+        None => optional(tx, "secretKey", parse_secret_key)?.ok_or("secretKey")?,
+    };
+    let transaction = TransactionTemplate {
+        sender,
+        to,
+        nonce: parse_u64(tx, "nonce")?,
+        gas_price: optional(tx, "gasPrice", parse_u256)?,
+        max_fee_per_gas: optional(tx, "maxFeePerGas", parse_u256)?,
+        max_priority_fee_per_gas: optional(tx, "maxPriorityFeePerGas", parse_u256)?,
+        data: parse_list(tx, "data", parse_bytes)?,
+        gas_limit: parse_list(tx, "gasLimit", |value| parse_u256(value)?.to_u64())?,
+        value: parse_list(tx, "value", parse_u256)?,
+        access_lists,
+        max_fee_per_blob_gas: optional(tx, "maxFeePerBlobGas", parse_u256)?,
+        blob_hashes: optional(tx, "blobVersionedHashes", |value| {
+            value.as_array()?.iter().map(parse_u256).collect()
+        })?
+        .unwrap_or_default(),
+        has_authorizations: !tx["authorizationList"].is_null(),
+    };
+
+    let mut post = BTreeMap::new();
+    let forks = test["post"].as_object().ok_or("post")?;
+    for (fork, entries) in forks {
+        let fork = match fork.parse::<Hardfork>() {
+            Ok(fork) => fork,
+            Err(_) => continue,
+        };
+        let entries = entries
+            .as_array()
+            .ok_or_else(|| format!("post.{}", fork))?
+            .iter()
+            .map(parse_post_entry)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|field| format!("post.{}.{}", fork, field))?;
+        post.insert(fork, entries);
+    }
+
+    Ok(StateTest {
+        name: name.to_string(),
+        block,
+        excess_blob_gas,
+        pre: parse_state(&test["pre"]).ok_or("pre")?,
+        transaction,
+        post,
+    })
+}
+
+fn parse_post_entry(entry: &serde_json::Value) -> Result<PostEntry, String> {
+    let indexes = &entry["indexes"];
+    let index = |name: &str| {
+        indexes[name]
+            .as_u64()
+            .map(|index| index as usize)
+            .ok_or(format!("indexes.{}", name))
+    };
+    Ok(PostEntry {
+        indexes: Indexes {
+            data: index("data")?,
+            gas: index("gas")?,
+            value: index("value")?,
+        },
+        hash: optional(entry, "hash", parse_u256)?,
+        logs: optional(entry, "logs", parse_u256)?,
+        expect_exception: optional(entry, "expectException", |value| {
+            value.as_str().map(str::to_string)
+        })?,
+        state: optional(entry, "state", parse_state)?,
+    })
+}
+
+/// Parses an object mapping addresses to accounts, the format of `pre`.
+fn parse_state(value: &serde_json::Value) -> Option<WorldState> {
+    WorldState::from_alloc(value).ok()
+}
+
+/// Address of the account of a secret key. Without the `crypto` feature, tests that
+/// give no `sender` cannot be loaded.
+#[cfg(feature = "crypto")]
+fn parse_secret_key(value: &serde_json::Value) -> Option<Address> {
+    crate::address::from_secret_key(&parse_bytes(value)?.try_into().ok()?)
+}
+
+#[cfg(not(feature = "crypto"))]
+fn parse_secret_key(_: &serde_json::Value) -> Option<Address> {
+    None
+}
+
+fn parse_access_list(value: &serde_json::Value) -> Option<AccessList> {
+    value
+        .as_array()?
+        .iter()
+        .map(|entry| {
+            let keys = entry["storageKeys"]
+                .as_array()?
+                .iter()
+                .map(parse_u256)
+                .collect::<Option<Vec<_>>>()?;
+            Some((parse_address(&entry["address"])?, keys))
+        })
+        .collect()
+}
+
+/// Parses an optional field, failing if it is present but invalid.
+fn optional<T>(
+    object: &serde_json::Value,
+    name: &str,
+    parse: impl FnOnce(&serde_json::Value) -> Option<T>,
+) -> Result<Option<T>, String> {
+    match object.get(name) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => parse(value).map(Some).ok_or_else(|| name.to_string()),
+    }
+}
+
+fn parse_list<T>(
+    object: &serde_json::Value,
+    name: &str,
+    parse: impl Fn(&serde_json::Value) -> Option<T>,
+) -> Result<Vec<T>, String> {
+    object[name]
+        .as_array()
+        .and_then(|values| values.iter().map(parse).collect())
+        .ok_or_else(|| name.to_string())
+}
+
+fn parse_u64(object: &serde_json::Value, name: &str) -> Result<u64, String> {
+    parse_u256(&object[name])
+        .and_then(|value| value.to_u64())
+        .ok_or_else(|| name.to_string())
+}
+
+/// Parses a hex or decimal number given as a string.
+fn parse_u256(value: &serde_json::Value) -> Option<U256> {
+    value.as_str()?.parse().ok()
+}
+
+fn parse_address(value: &serde_json::Value) -> Option<Address> {
+    parse_bytes(value)?.try_into().ok()
+}
+
+fn parse_bytes(value: &serde_json::Value) -> Option<Vec<u8>> {
+    hex::decode(value.as_str()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "crypto")]
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/state");
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn fixtures() {
+        let mut passed = 0;
+        for entry in std::fs::read_dir(FIXTURES).unwrap() {
+            let path = entry.unwrap().path();
+            for test in load(&path).unwrap() {
+                for result in test.run(None) {
+                    assert_eq!(
+                        result.verdict,
+                        Verdict::Pass,
+                        "{} {} {} in {}",
+                        result.test,
+                        result.fork,
+                        result.indexes,
+                        path.display()
+                    );
+                    passed += 1;
+                }
+            }
+        }
+        assert!(passed > 0);
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn state_roots() {
+        // Like filled tests, the fixtures list only the state root of the post-state.
+        let path = format!("{}/refundCap.json", FIXTURES);
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let verdict = |json: &serde_json::Value| {
+            let test = parse(&json.to_string()).unwrap().remove(0);
+            test.run(Some(Hardfork::London)).remove(0).verdict
+        };
+
+        assert_eq!(verdict(&json), Verdict::Pass);
+        let entry = &mut json["refundCap"]["post"]["London"][0];
+        entry["hash"] = format!("0x{}", "00".repeat(32)).into();
+        assert!(
+            matches!(verdict(&json), Verdict::Fail(reason) if reason.starts_with("state root"))
+        );
+        json["refundCap"]["post"]["London"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("hash");
+        assert!(matches!(verdict(&json), Verdict::Fail(_)));
+    }
+
+    /// Runs the `GeneralStateTests` of an ethereum/tests checkout whose path is in
+    /// `ETHEREUM_TESTS`: `cargo test ethereum_tests -- --ignored`.
+    #[test]
+    #[ignore]
+    #[cfg(feature = "crypto")]
+    fn ethereum_tests() {
+        let root = std::env::var("ETHEREUM_TESTS").expect("ETHEREUM_TESTS is not set");
+        let mut dirs = vec![Path::new(&root).join("GeneralStateTests")];
+        let (mut passed, mut failures) = (0, Vec::new());
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                if path.extension().is_none_or(|extension| extension != "json") {
+                    continue;
+                }
+                for test in load(&path).unwrap() {
+                    for result in test.run(None) {
+                        match result.verdict {
+                            Verdict::Pass => passed += 1,
+                            Verdict::Fail(reason) => failures.push(format!(
+                                "{} {} {}: {}",
+                                result.test, result.fork, result.indexes, reason
+                            )),
+                            Verdict::Skip(_) => {}
+                        }
+                    }
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+        assert!(passed > 0);
+    }
+
+    #[test]
+    fn invalid_fields() {
+        let err = parse(r#"{"t": {"env": {}, "pre": {}, "transaction": {}, "post": {}}}"#)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "t: missing or invalid `currentNumber`");
+    }
+}
@@ -0,0 +1,156 @@
+//! Root hash of a Merkle Patricia trie, the commitment to the accounts of a state
+//! and to the storage of each account.
+//!
+//! Only the root is computed: the trie is built from a sorted set of key/value pairs
+//! at once, without storing its nodes.
+
+use crate::keccak::keccak256;
+use crate::rlp::{self, Encode};
+use std::collections::BTreeMap;
+
+/// Root of the empty trie, `keccak256(rlp(""))`.
+pub const EMPTY_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/// Root of the trie holding `entries`. Values must not be empty.
+pub fn root(entries: &BTreeMap<Vec<u8>, Vec<u8>>) -> [u8; 32] {
+    if entries.is_empty() {
+        return EMPTY_ROOT;
+    }
+    let entries: Vec<(Vec<u8>, &[u8])> = entries
+        .iter()
+        .map(|(key, value)| (nibbles(key), value.as_slice()))
+        .collect();
+    keccak256(&node(&entries, 0))
+}
+
+/// Root of a secure trie, whose keys are the Keccak-256 hashes of `entries`' keys.
+pub fn secure_root<K: AsRef<[u8]>>(entries: impl IntoIterator<Item = (K, Vec<u8>)>) -> [u8; 32] {
+    let entries = entries
+        .into_iter()
+        .map(|(key, value)| (keccak256(key.as_ref()).to_vec(), value))
+        .collect();
+    root(&entries)
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xf])
+        .collect()
+}
+
+/// Encoded node holding `entries`, sorted and sharing their first `depth` nibbles.
+fn node(entries: &[(Vec<u8>, &[u8])], depth: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    if let [(key, value)] = entries {
+        (hex_prefix(&key[depth..], true), *value).encode(&mut out);
+        return out;
+    }
+
+    let (first, last) = (&entries[0].0, &entries[entries.len() - 1].0);
+    let shared = first[depth..]
+        .iter()
+        .zip(&last[depth..])
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared > 0 {
+        let child = node(entries, depth + shared);
+        rlp::encode_list_with(&mut out, |out| {
+            hex_prefix(&first[depth..depth + shared], false).encode(out);
+            reference(&child, out);
+        });
+        return out;
+    }
+
+    // A branch: one child per next nibble, and the value of a key ending here.
+    rlp::encode_list_with(&mut out, |out| {
+        let mut rest = entries;
+        let value = match rest.first() {
+            Some((key, value)) if key.len() == depth => {
+                rest = &rest[1..];
+                *value
+            }
+            _ => &[],
+        };
+        for nibble in 0..16 {
+            let len = rest
+                .iter()
+                .take_while(|(key, _)| key[depth] == nibble)
+                .count();
+            let (children, next) = rest.split_at(len);
+            if children.is_empty() {
+                [0u8; 0].encode(out);
+            } else {
+                reference(&node(children, depth + 1), out);
+            }
+            rest = next;
+        }
+        value.encode(out);
+    });
+    out
+}
+
+/// Appends a child node: inline when its encoding is shorter than a hash.
+fn reference(node: &[u8], out: &mut Vec<u8>) {
+    if node.len() < 32 {
+        out.extend_from_slice(node);
+    } else {
+        keccak256(node).encode(out);
+    }
+}
+
+/// Compact encoding of a key path, with flags for leaves and odd lengths.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 } + (nibbles.len() % 2) as u8;
+    let (mut out, rest) = match nibbles.split_first() {
+        Some((&first, rest)) if flag & 1 == 1 => (vec![flag << 4 | first], rest),
+        _ => (vec![flag << 4], nibbles),
+    };
+    out.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    fn trie(entries: &[(&str, &str)]) -> [u8; 32] {
+        root(
+            &entries
+                .iter()
+                .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn roots() {
+        assert_eq!(trie(&[]), EMPTY_ROOT);
+        assert_eq!(EMPTY_ROOT, keccak256(&rlp::encode(&[0u8; 0][..])));
+        // Vectors of ethereum/tests TrieTests.
+        assert_eq!(
+            trie(&[
+                ("doe", "reindeer"),
+                ("dog", "puppy"),
+                ("dogglesworth", "cat")
+            ]),
+            hex!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+        );
+        assert_eq!(
+            trie(&[
+                ("do", "verb"),
+                ("horse", "stallion"),
+                ("doge", "coin"),
+                ("dog", "puppy")
+            ]),
+            hex!("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+        );
+        assert_eq!(
+            trie(&[("A", &"a".repeat(50))]),
+            hex!("d23786fb4a010da3ce639d66d5e904a11dbc02746d1ce25029e53290cabf28ab")
+        );
+    }
+}
@@ -0,0 +1,619 @@
+//! Signed raw transactions: the legacy RLP list and the typed envelopes of EIP-2718,
+//! `type ++ rlp(fields)`, for access list (EIP-2930), dynamic fee (EIP-1559), blob
+//! (EIP-4844) and set code (EIP-7702) transactions.
+
+#[cfg(feature = "crypto")]
+use crate::address;
+use crate::interpreter::{AccessList, Address};
+use crate::keccak::keccak256;
+use crate::rlp::{self, Encode, Item, RlpError};
+use crate::u256::U256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxError {
+    Empty,
+    UnknownType(u8),
+    Rlp(RlpError),
+    /// `v` of a legacy transaction is neither 27, 28 nor an EIP-155 value.
+    InvalidV(u64),
+    /// Blob and set code transactions cannot create contracts.
+    MissingRecipient(TxType),
+    /// The signature is malformed, has a high `s` (EIP-2), or recovers no key.
+    InvalidSignature,
+    /// Signers cannot be recovered without the `crypto` feature.
+    RecoveryUnavailable,
+}
+
+impl std::fmt::Display for TxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxError::Empty => write!(f, "empty transaction"),
+            TxError::UnknownType(ty) => write!(f, "unknown transaction type 0x{:02x}", ty),
+            TxError::Rlp(err) => write!(f, "{}", err),
+            TxError::InvalidV(v) => write!(f, "invalid v {}", v),
+            TxError::MissingRecipient(ty) => write!(f, "{} transaction without recipient", ty),
+            TxError::InvalidSignature => write!(f, "invalid signature"),
+            TxError::RecoveryUnavailable => {
+                write!(f, "recovering signers needs the `crypto` feature")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TxError {}
+
+impl From<RlpError> for TxError {
+    fn from(err: RlpError) -> Self {
+        TxError::Rlp(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TxType {
+    Legacy,
+    /// EIP-2930.
+    AccessList,
+    /// EIP-1559.
+    DynamicFee,
+    /// EIP-4844.
+    Blob,
+    /// EIP-7702.
+    SetCode,
+}
+
+impl TxType {
+    /// Byte that starts the envelope, `None` for legacy transactions.
+    pub fn byte(&self) -> Option<u8> {
+        match self {
+            TxType::Legacy => None,
+            TxType::AccessList => Some(0x01),
+            TxType::DynamicFee => Some(0x02),
+            TxType::Blob => Some(0x03),
+            TxType::SetCode => Some(0x04),
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<TxType> {
+        match byte {
+            0x01 => Some(TxType::AccessList),
+            0x02 => Some(TxType::DynamicFee),
+            0x03 => Some(TxType::Blob),
+            0x04 => Some(TxType::SetCode),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for TxType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxType::Legacy => write!(f, "legacy"),
+            TxType::AccessList => write!(f, "access list"),
+            TxType::DynamicFee => write!(f, "dynamic fee"),
+            TxType::Blob => write!(f, "blob"),
+            TxType::SetCode => write!(f, "set code"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    /// Parity of the `y` coordinate of the signature point, the recovery id.
+    pub y_parity: bool,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl Signature {
+    /// Address of the key that signed `hash`.
+    #[cfg(feature = "crypto")]
+    pub fn recover(&self, hash: &[u8; 32]) -> Result<Address, TxError> {
+        use k256::ecdsa::{RecoveryId, VerifyingKey};
+
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(&self.r.to_be_bytes());
+        bytes[32..].copy_from_slice(&self.s.to_be_bytes());
+        let signature =
+            k256::ecdsa::Signature::from_slice(&bytes).map_err(|_| TxError::InvalidSignature)?;
+        if signature.normalize_s().is_some() {
+            return Err(TxError::InvalidSignature);
+        }
+        let recovery_id = RecoveryId::new(self.y_parity, false);
+        let key = VerifyingKey::recover_from_prehash(hash, &signature, recovery_id)
+            .map_err(|_| TxError::InvalidSignature)?;
+        Ok(address::from_public_key(&key))
+    }
+
+    #[cfg(not(feature = "crypto"))]
+    pub fn recover(&self, _hash: &[u8; 32]) -> Result<Address, TxError> {
+        Err(TxError::RecoveryUnavailable)
+    }
+}
+
+/// Delegation signed by an account in a set code transaction (EIP-7702).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authorization {
+    /// Chain the authorization is valid on, or zero for every chain.
+    pub chain_id: U256,
+    /// Contract whose code the authority delegates to.
+    pub address: Address,
+    pub nonce: u64,
+    pub signature: Signature,
+}
+
+impl Authorization {
+    /// Account that signed the authorization:
+    /// `keccak256(0x05 ++ rlp([chain_id, address, nonce]))`.
+    pub fn authority(&self) -> Result<Address, TxError> {
+        let mut preimage = vec![0x05];
+        (&self.chain_id, &self.address, self.nonce).encode(&mut preimage);
+        self.signature.recover(&keccak256(&preimage))
+    }
+}
+
+/// Decoded signed transaction. Fields a type does not have are `None` or empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransaction {
+    pub tx_type: TxType,
+    /// `None` for a legacy transaction signed without replay protection.
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    /// Gas price of a legacy or access list transaction.
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub gas_limit: u64,
+    /// Recipient, `None` for a contract creation.
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub access_list: AccessList,
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blob_hashes: Vec<U256>,
+    pub authorizations: Vec<Authorization>,
+    pub signature: Signature,
+    /// Transaction hash, of the canonical encoding.
+    pub hash: [u8; 32],
+    /// Hash the sender signed.
+    pub signing_hash: [u8; 32],
+}
+
+impl SignedTransaction {
+    /// Decodes a raw transaction. Blob transactions are also accepted in the network
+    /// form that carries the blobs, which are dropped.
+    pub fn decode(bytes: &[u8]) -> Result<SignedTransaction, TxError> {
+        let (&first, payload) = bytes.split_first().ok_or(TxError::Empty)?;
+        if first >= 0xc0 {
+            return decode_legacy(bytes);
+        }
+        let tx_type = TxType::from_byte(first).ok_or(TxError::UnknownType(first))?;
+        let mut item = rlp::decode_item(payload)?;
+        if tx_type == TxType::Blob {
+            // `[tx, blobs, commitments, proofs]` starts with a list, `tx` with a number.
+            if let Some(Item::List(tx)) = item.as_list()?.first() {
+                item = Item::List(tx.clone());
+            }
+        }
+        decode_typed(tx_type, &item)
+    }
+
+    pub fn is_create(&self) -> bool {
+        self.to.is_none()
+    }
+
+    /// Address that signed the transaction.
+    pub fn sender(&self) -> Result<Address, TxError> {
+        self.signature.recover(&self.signing_hash)
+    }
+}
+
+/// Reads the fields of a transaction in order.
+struct Fields<'a> {
+    items: std::slice::Iter<'a, Item>,
+}
+
+impl<'a> Fields<'a> {
+    /// Fails unless `item` is a list of exactly `len` items.
+    fn new(item: &'a Item, len: usize) -> Result<Fields<'a>, RlpError> {
+        let items = item.as_list()?;
+        if items.len() != len {
+            return Err(RlpError::WrongLength {
+                expected: len,
+                got: items.len(),
+            });
+        }
+        Ok(Fields {
+            items: items.iter(),
+        })
+    }
+
+    fn next<T: rlp::Decode>(&mut self) -> Result<T, RlpError> {
+        self.items.next().expect("length checked").decode()
+    }
+
+    /// Recipient, empty for a contract creation.
+    fn to(&mut self) -> Result<Option<Address>, RlpError> {
+        let bytes: Vec<u8> = self.next()?;
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        Address::try_from(bytes.as_slice())
+            .map(Some)
+            .map_err(|_| RlpError::WrongLength {
+                expected: 20,
+                got: bytes.len(),
+            })
+    }
+
+    fn access_list(&mut self) -> Result<AccessList, RlpError> {
+        let entries: Vec<(Address, Vec<[u8; 32]>)> = self.next()?;
+        Ok(entries
+            .into_iter()
+            .map(|(address, keys)| (address, keys.into_iter().map(U256::from_be_bytes).collect()))
+            .collect())
+    }
+
+    fn signature(&mut self) -> Result<Signature, RlpError> {
+        Ok(Signature {
+            y_parity: self.next()?,
+            r: self.next()?,
+            s: self.next()?,
+        })
+    }
+}
+
+/// `[nonce, gas_price, gas_limit, to, value, data, v, r, s]`, signed without the
+/// signature, or with `[chain_id, 0, 0]` in its place under EIP-155.
+fn decode_legacy(bytes: &[u8]) -> Result<SignedTransaction, TxError> {
+    let item = rlp::decode_item(bytes)?;
+    let mut fields = Fields::new(&item, 9)?;
+    let nonce = fields.next()?;
+    let gas_price = fields.next()?;
+    let gas_limit = fields.next()?;
+    let to = fields.to()?;
+    let value = fields.next()?;
+    let data = fields.next()?;
+    let v: u64 = fields.next()?;
+    let (chain_id, y_parity) = match v {
+        27 | 28 => (None, v == 28),
+        35.. => (Some((v - 35) / 2), (v - 35) % 2 == 1),
+        _ => return Err(TxError::InvalidV(v)),
+    };
+    let signature = Signature {
+        y_parity,
+        r: fields.next()?,
+        s: fields.next()?,
+    };
+
+    let unsigned = &item.as_list()?[..6];
+    let mut preimage = Vec::new();
+    rlp::encode_list_with(&mut preimage, |out| {
+        for field in unsigned {
+            field.encode(out);
+        }
+        if let Some(chain_id) = chain_id {
+            chain_id.encode(out);
+            0u64.encode(out);
+            0u64.encode(out);
+        }
+    });
+    Ok(SignedTransaction {
+        tx_type: TxType::Legacy,
+        chain_id,
+        nonce,
+        gas_price: Some(gas_price),
+        max_priority_fee_per_gas: None,
+        max_fee_per_gas: None,
+        gas_limit,
+        to,
+        value,
+        data,
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: None,
+        blob_hashes: Vec::new(),
+        authorizations: Vec::new(),
+        signature,
+        hash: keccak256(bytes),
+        signing_hash: keccak256(&preimage),
+    })
+}
+
+fn decode_typed(tx_type: TxType, item: &Item) -> Result<SignedTransaction, TxError> {
+    let len = match tx_type {
+        TxType::Legacy => unreachable!("legacy transactions have no type byte"),
+        TxType::AccessList => 11,
+        TxType::DynamicFee => 12,
+        TxType::Blob => 14,
+        TxType::SetCode => 13,
+    };
+    let mut fields = Fields::new(item, len)?;
+    let chain_id = fields.next()?;
+    let nonce = fields.next()?;
+    let (gas_price, max_priority_fee_per_gas, max_fee_per_gas) = match tx_type {
+        TxType::AccessList => (Some(fields.next()?), None, None),
+        _ => (None, Some(fields.next()?), Some(fields.next()?)),
+    };
+    let gas_limit = fields.next()?;
+    let to = fields.to()?;
+    if to.is_none() && matches!(tx_type, TxType::Blob | TxType::SetCode) {
+        return Err(TxError::MissingRecipient(tx_type));
+    }
+    let value = fields.next()?;
+    let data = fields.next()?;
+    let access_list = fields.access_list()?;
+    let (max_fee_per_blob_gas, blob_hashes) = match tx_type {
+        TxType::Blob => {
+            let fee = fields.next()?;
+            let hashes: Vec<[u8; 32]> = fields.next()?;
+            (
+                Some(fee),
+                hashes.into_iter().map(U256::from_be_bytes).collect(),
+            )
+        }
+        _ => (None, Vec::new()),
+    };
+    let authorizations = match tx_type {
+        TxType::SetCode => fields
+            .next::<Vec<Item>>()?
+            .iter()
+            .map(decode_authorization)
+            .collect::<Result<_, _>>()?,
+        _ => Vec::new(),
+    };
+    let signature = fields.signature()?;
+
+    let type_byte = tx_type.byte().expect("typed transaction");
+    let mut canonical = vec![type_byte];
+    item.encode(&mut canonical);
+    let mut preimage = vec![type_byte];
+    rlp::encode_list_with(&mut preimage, |out| {
+        for field in &item.as_list().expect("checked by Fields::new")[..len - 3] {
+            field.encode(out);
+        }
+    });
+    Ok(SignedTransaction {
+        tx_type,
+        chain_id: Some(chain_id),
+        nonce,
+        gas_price,
+        max_priority_fee_per_gas,
+        max_fee_per_gas,
+        gas_limit,
+        to,
+        value,
+        data,
+        access_list,
+        max_fee_per_blob_gas,
+        blob_hashes,
+        authorizations,
+        signature,
+        hash: keccak256(&canonical),
+        signing_hash: keccak256(&preimage),
+    })
+}
+
+/// `[chain_id, address, nonce, y_parity, r, s]`.
+fn decode_authorization(item: &Item) -> Result<Authorization, RlpError> {
+    let mut fields = Fields::new(item, 6)?;
+    Ok(Authorization {
+        chain_id: fields.next()?,
+        address: fields.next()?,
+        nonce: fields.next()?,
+        signature: fields.signature()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[cfg(feature = "crypto")]
+    const KEY: [u8; 32] = [0x46; 32];
+    #[cfg(feature = "crypto")]
+    const SENDER: Address = hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+
+    /// Signs the unsigned `fields` of a typed transaction with [`KEY`] and returns
+    /// the raw transaction.
+    fn sign(tx_type: u8, fields: &[Item]) -> Vec<u8> {
+        let mut preimage = vec![tx_type];
+        fields.encode(&mut preimage);
+        let (y_parity, r, s) = signature(&keccak256(&preimage));
+        let mut raw = vec![tx_type];
+        rlp::encode_list_with(&mut raw, |out| {
+            fields.iter().for_each(|field| field.encode(out));
+            y_parity.encode(out);
+            r.encode(out);
+            s.encode(out);
+        });
+        raw
+    }
+
+    /// Signature of `hash` by [`KEY`], as `(y_parity, r, s)`.
+    #[cfg(feature = "crypto")]
+    fn signature(hash: &[u8; 32]) -> (bool, U256, U256) {
+        let key = k256::ecdsa::SigningKey::from_bytes(&KEY.into()).unwrap();
+        let (signature, recovery_id) = key.sign_prehash_recoverable(hash).unwrap();
+        let bytes = signature.to_bytes();
+        (
+            recovery_id.is_y_odd(),
+            U256::from_be_slice(&bytes[..32]),
+            U256::from_be_slice(&bytes[32..]),
+        )
+    }
+
+    /// Placeholder signature. Decoding does not check it, and signers cannot be
+    /// recovered without the `crypto` feature.
+    #[cfg(not(feature = "crypto"))]
+    fn signature(_hash: &[u8; 32]) -> (bool, U256, U256) {
+        (false, U256::ONE, U256::ONE)
+    }
+
+    /// Checks that a transaction signed by [`sign`] recovers to the sender of [`KEY`].
+    fn assert_signer(signer: Result<Address, TxError>) {
+        #[cfg(feature = "crypto")]
+        assert_eq!(signer, Ok(SENDER));
+        #[cfg(not(feature = "crypto"))]
+        assert_eq!(signer, Err(TxError::RecoveryUnavailable));
+    }
+
+    fn item<T: rlp::Encode + ?Sized>(value: &T) -> Item {
+        rlp::decode_item(&rlp::encode(value)).unwrap()
+    }
+
+    #[test]
+    fn legacy() {
+        // The example of EIP-155.
+        let raw = hex!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000"
+            "8025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f"
+            "761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+        let tx = SignedTransaction::decode(&raw).unwrap();
+        assert_eq!(tx.tx_type, TxType::Legacy);
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.nonce, 9);
+        assert_eq!(tx.gas_price, Some(U256::from(20_000_000_000u64)));
+        assert_eq!(tx.gas_limit, 21000);
+        assert_eq!(tx.to, Some([0x35; 20]));
+        assert_eq!(tx.value, U256::from(1_000_000_000_000_000_000u64));
+        assert!(!tx.signature.y_parity);
+        assert_eq!(
+            tx.signing_hash,
+            hex!("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+        );
+        assert_signer(tx.sender());
+        assert_eq!(tx.hash, keccak256(&raw));
+
+        let mut unprotected = raw;
+        unprotected[raw.len() - 67] = 27;
+        assert_eq!(
+            SignedTransaction::decode(&unprotected).unwrap().chain_id,
+            None
+        );
+        unprotected[raw.len() - 67] = 30;
+        assert_eq!(
+            SignedTransaction::decode(&unprotected),
+            Err(TxError::InvalidV(30))
+        );
+    }
+
+    #[test]
+    fn typed() {
+        let access_list = item(&vec![([0xaa; 20], vec![[0; 32], [1; 32]])]);
+        let common = [
+            item(&1u64),
+            item(&7u64),
+            item(&U256::from(1_000_000_000u64)),
+            item(&U256::from(30_000_000_000u64)),
+            item(&50_000u64),
+            item(&[0xbb; 20]),
+            item(&5u64),
+            item(&hex!("c0ffee")[..]),
+            access_list,
+        ];
+
+        let tx = SignedTransaction::decode(&sign(0x02, &common)).unwrap();
+        assert_eq!(tx.tx_type, TxType::DynamicFee);
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.nonce, 7);
+        assert_eq!(tx.gas_price, None);
+        assert_eq!(
+            tx.max_priority_fee_per_gas,
+            Some(U256::from(1_000_000_000u64))
+        );
+        assert_eq!(tx.max_fee_per_gas, Some(U256::from(30_000_000_000u64)));
+        assert_eq!(tx.gas_limit, 50_000);
+        assert_eq!(tx.to, Some([0xbb; 20]));
+        assert_eq!(tx.data, hex!("c0ffee"));
+        assert_eq!(
+            tx.access_list,
+            vec![([0xaa; 20], vec![U256::ZERO, U256::from_be_bytes([1; 32])])]
+        );
+        assert_signer(tx.sender());
+
+        // Access list transactions have a single gas price.
+        let mut fields = common.to_vec();
+        fields.remove(3);
+        let tx = SignedTransaction::decode(&sign(0x01, &fields)).unwrap();
+        assert_eq!(tx.tx_type, TxType::AccessList);
+        assert_eq!(tx.gas_price, Some(U256::from(1_000_000_000u64)));
+        assert_signer(tx.sender());
+
+        let mut fields = common.to_vec();
+        fields.push(item(&2u64));
+        fields.push(item(&vec![[0x01; 32]]));
+        let raw = sign(0x03, &fields);
+        let tx = SignedTransaction::decode(&raw).unwrap();
+        assert_eq!(tx.max_fee_per_blob_gas, Some(U256::from(2u64)));
+        assert_eq!(tx.blob_hashes, vec![U256::from_be_bytes([0x01; 32])]);
+        assert_signer(tx.sender());
+        assert_eq!(tx.hash, keccak256(&raw));
+        // The network form wraps the transaction with its blobs and proofs.
+        let mut wrapped = vec![0x03];
+        rlp::encode_list_with(&mut wrapped, |out| {
+            rlp::decode_item(&raw[1..]).unwrap().encode(out);
+            vec![vec![0u8; 4]].encode(out);
+            vec![[0u8; 48]].encode(out);
+            vec![[0u8; 48]].encode(out);
+        });
+        assert_eq!(SignedTransaction::decode(&wrapped), Ok(tx));
+
+        let authorization = sign(0x05, &[item(&0u64), item(&[0xdd; 20]), item(&3u64)]);
+        let mut fields = common.to_vec();
+        fields.push(Item::List(vec![
+            rlp::decode_item(&authorization[1..]).unwrap()
+        ]));
+        let tx = SignedTransaction::decode(&sign(0x04, &fields)).unwrap();
+        assert_eq!(tx.tx_type, TxType::SetCode);
+        assert_eq!(tx.authorizations.len(), 1);
+        assert_eq!(tx.authorizations[0].address, [0xdd; 20]);
+        assert_eq!(tx.authorizations[0].nonce, 3);
+        assert_signer(tx.authorizations[0].authority());
+        assert_signer(tx.sender());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(SignedTransaction::decode(&[]), Err(TxError::Empty));
+        assert_eq!(
+            SignedTransaction::decode(&hex!("05c0")),
+            Err(TxError::UnknownType(0x05))
+        );
+        assert_eq!(
+            SignedTransaction::decode(&hex!("02c0")),
+            Err(TxError::Rlp(RlpError::WrongLength {
+                expected: 12,
+                got: 0
+            }))
+        );
+
+        let mut fields = vec![item(&1u64); 9];
+        fields[5] = item(&[0u8; 0][..]);
+        fields[8] = item(&Vec::<Item>::new());
+        let tx = SignedTransaction::decode(&sign(0x02, &fields)).unwrap();
+        assert!(tx.is_create());
+        fields.push(item(&Vec::<Item>::new()));
+        assert_eq!(
+            SignedTransaction::decode(&sign(0x04, &fields)),
+            Err(TxError::MissingRecipient(TxType::SetCode))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn high_s() {
+        // The same signature with the other `s` is rejected (EIP-2).
+        let mut fields = vec![item(&1u64); 9];
+        fields[5] = item(&[0xbb; 20]);
+        fields[8] = item(&Vec::<Item>::new());
+        let mut tx = SignedTransaction::decode(&sign(0x02, &fields)).unwrap();
+        assert_eq!(tx.sender(), Ok(SENDER));
+        let order = U256::from_be_bytes(hex!(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141"
+        ));
+        tx.signature.s = order - tx.signature.s;
+        tx.signature.y_parity = !tx.signature.y_parity;
+        assert_eq!(tx.sender(), Err(TxError::InvalidSignature));
+    }
+}
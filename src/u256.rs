@@ -0,0 +1,682 @@
+use crate::hex;
+use std::cmp::Ordering;
+
+/// 256-bit EVM word.
+///
+/// Arithmetic wraps modulo 2^256 like the EVM does, division and remainder by zero
+/// return zero, and the signed operations read the word as two's complement. The
+/// `std::ops` operators follow the same rules, so `a / U256::ZERO` is zero rather than
+/// a panic.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct U256([u64; 4]);
+
+impl U256 {
+    pub const ZERO: U256 = U256([0; 4]);
+    pub const ONE: U256 = U256([1, 0, 0, 0]);
+    pub const MAX: U256 = U256([u64::MAX; 4]);
+
+    /// Word from little-endian 64-bit limbs.
+    pub const fn from_limbs(limbs: [u64; 4]) -> U256 {
+        U256(limbs)
+    }
+
+    /// Little-endian 64-bit limbs.
+    pub const fn limbs(&self) -> [u64; 4] {
+        self.0
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 32]) -> U256 {
+        let mut limbs = [0; 4];
+        for (index, chunk) in bytes.chunks_exact(8).enumerate() {
+            limbs[3 - index] = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        U256(limbs)
+    }
+
+    /// Word from up to 32 big-endian bytes, left-padded with zeroes like `PUSH` data.
+    /// Only the last 32 bytes of a longer slice are used.
+    pub fn from_be_slice(bytes: &[u8]) -> U256 {
+        let bytes = &bytes[bytes.len().saturating_sub(32)..];
+        let mut padded = [0; 32];
+        padded[32 - bytes.len()..].copy_from_slice(bytes);
+        U256::from_be_bytes(padded)
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (index, chunk) in bytes.chunks_exact_mut(8).enumerate() {
+            chunk.copy_from_slice(&self.0[3 - index].to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    /// Lowest 64 bits, discarding the rest.
+    pub fn low_u64(&self) -> u64 {
+        self.0[0]
+    }
+
+    /// Value as `u64`, or `None` if it does not fit.
+    pub fn to_u64(&self) -> Option<u64> {
+        (self.0[1..] == [0; 3]).then_some(self.0[0])
+    }
+
+    /// Value as `usize`, or `None` if it does not fit.
+    pub fn to_usize(&self) -> Option<usize> {
+        self.to_u64().and_then(|value| usize::try_from(value).ok())
+    }
+
+    /// Number of significant bits.
+    pub fn bits(&self) -> usize {
+        256 - self.leading_zeros() as usize
+    }
+
+    pub fn leading_zeros(&self) -> u32 {
+        let mut zeros = 0;
+        for limb in self.0.iter().rev() {
+            zeros += limb.leading_zeros();
+            if *limb != 0 {
+                break;
+            }
+        }
+        zeros
+    }
+
+    pub fn bit(&self, index: usize) -> bool {
+        index < 256 && self.0[index / 64] >> (index % 64) & 1 == 1
+    }
+
+    pub fn overflowing_add(self, other: U256) -> (U256, bool) {
+        let mut limbs = [0; 4];
+        let mut carry = false;
+        for (index, limb) in limbs.iter_mut().enumerate() {
+            let (sum, c1) = self.0[index].overflowing_add(other.0[index]);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c1 || c2;
+        }
+        (U256(limbs), carry)
+    }
+
+    pub fn overflowing_sub(self, other: U256) -> (U256, bool) {
+        let mut limbs = [0; 4];
+        let mut borrow = false;
+        for (index, limb) in limbs.iter_mut().enumerate() {
+            let (diff, b1) = self.0[index].overflowing_sub(other.0[index]);
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            *limb = diff;
+            borrow = b1 || b2;
+        }
+        (U256(limbs), borrow)
+    }
+
+    /// Product modulo 2^256 and whether it overflowed.
+    pub fn overflowing_mul(self, other: U256) -> (U256, bool) {
+        let product = full_mul(&self, &other);
+        let mut low = [0; 4];
+        low.copy_from_slice(&product[..4]);
+        (U256(low), product[4..] != [0; 4])
+    }
+
+    pub fn checked_add(self, other: U256) -> Option<U256> {
+        match self.overflowing_add(other) {
+            (sum, false) => Some(sum),
+            _ => None,
+        }
+    }
+
+    pub fn checked_sub(self, other: U256) -> Option<U256> {
+        match self.overflowing_sub(other) {
+            (diff, false) => Some(diff),
+            _ => None,
+        }
+    }
+
+    pub fn checked_mul(self, other: U256) -> Option<U256> {
+        match self.overflowing_mul(other) {
+            (product, false) => Some(product),
+            _ => None,
+        }
+    }
+
+    /// Quotient and remainder, both zero when dividing by zero.
+    pub fn div_rem(self, divisor: U256) -> (U256, U256) {
+        if divisor.is_zero() {
+            return (U256::ZERO, U256::ZERO);
+        }
+        if let (Some(a), Some(b)) = (self.to_u128(), divisor.to_u128()) {
+            return (U256::from(a / b), U256::from(a % b));
+        }
+        div_rem_limbs(&self.0, divisor)
+    }
+
+    /// `SDIV`: signed division rounding towards zero. `MIN / -1` wraps to `MIN`.
+    pub fn sdiv(self, divisor: U256) -> U256 {
+        let quotient = self.abs() / divisor.abs();
+        if self.is_negative() != divisor.is_negative() {
+            quotient.wrapping_neg()
+        } else {
+            quotient
+        }
+    }
+
+    /// `SMOD`: signed remainder taking the sign of the dividend.
+    pub fn smod(self, divisor: U256) -> U256 {
+        let remainder = self.abs() % divisor.abs();
+        if self.is_negative() {
+            remainder.wrapping_neg()
+        } else {
+            remainder
+        }
+    }
+
+    /// `ADDMOD`: `(self + other) % modulus` without wrapping the sum.
+    pub fn addmod(self, other: U256, modulus: U256) -> U256 {
+        if modulus.is_zero() {
+            return U256::ZERO;
+        }
+        let (sum, carry) = self.overflowing_add(other);
+        let mut limbs = [0; 8];
+        limbs[..4].copy_from_slice(&sum.0);
+        limbs[4] = carry as u64;
+        div_rem_limbs(&limbs, modulus).1
+    }
+
+    /// `MULMOD`: `(self * other) % modulus` on the full 512-bit product.
+    pub fn mulmod(self, other: U256, modulus: U256) -> U256 {
+        if modulus.is_zero() {
+            return U256::ZERO;
+        }
+        div_rem_limbs(&full_mul(&self, &other), modulus).1
+    }
+
+    /// `EXP`: `self` raised to `exponent`, modulo 2^256.
+    pub fn pow(self, exponent: U256) -> U256 {
+        let mut result = U256::ONE;
+        let mut base = self;
+        for index in 0..exponent.bits() {
+            if exponent.bit(index) {
+                result = result * base;
+            }
+            base = base * base;
+        }
+        result
+    }
+
+    /// `SIGNEXTEND`: extends the sign bit of byte `byte` (counting from the least
+    /// significant byte) to the whole word.
+    pub fn signextend(self, byte: U256) -> U256 {
+        let byte = match byte.to_usize() {
+            Some(byte) if byte < 31 => byte,
+            _ => return self,
+        };
+        let bit = byte * 8 + 7;
+        let mask = (U256::ONE << (bit + 1)) - U256::ONE;
+        if self.bit(bit) {
+            self | !mask
+        } else {
+            self & mask
+        }
+    }
+
+    /// `BYTE`: byte `index` of the word, counting from the most significant byte.
+    pub fn byte(self, index: U256) -> U256 {
+        match index.to_usize() {
+            Some(index) if index < 32 => U256::from(self.to_be_bytes()[index] as u64),
+            _ => U256::ZERO,
+        }
+    }
+
+    /// `SHL` with the word operand order: shifts of 256 bits or more give zero.
+    pub fn shl_word(self, shift: U256) -> U256 {
+        self << shift.to_usize().unwrap_or(usize::MAX)
+    }
+
+    /// `SHR` with the word operand order: shifts of 256 bits or more give zero.
+    pub fn shr_word(self, shift: U256) -> U256 {
+        self >> shift.to_usize().unwrap_or(usize::MAX)
+    }
+
+    /// `SAR`: arithmetic shift right, filling with the sign bit.
+    pub fn sar(self, shift: U256) -> U256 {
+        let shift = shift.to_usize().unwrap_or(usize::MAX);
+        if !self.is_negative() {
+            return self >> shift;
+        }
+        if shift >= 256 {
+            return U256::MAX;
+        }
+        !(!self >> shift)
+    }
+
+    /// Whether the word is negative when read as two's complement.
+    pub fn is_negative(&self) -> bool {
+        self.0[3] >> 63 == 1
+    }
+
+    /// Two's complement negation.
+    pub fn wrapping_neg(self) -> U256 {
+        U256::ZERO - self
+    }
+
+    /// Absolute value of the two's complement reading. `MIN` stays `MIN`.
+    pub fn abs(self) -> U256 {
+        if self.is_negative() {
+            self.wrapping_neg()
+        } else {
+            self
+        }
+    }
+
+    /// `SLT`/`SGT` ordering of the two's complement reading.
+    pub fn signed_cmp(&self, other: &U256) -> Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => self.cmp(other),
+        }
+    }
+
+    fn to_u128(self) -> Option<u128> {
+        (self.0[2..] == [0; 2]).then(|| (self.0[1] as u128) << 64 | self.0[0] as u128)
+    }
+
+    /// Quotient and remainder of a division by a small divisor, used for formatting.
+    fn div_rem_u64(self, divisor: u64) -> (U256, u64) {
+        let mut limbs = [0; 4];
+        let mut remainder = 0u128;
+        for index in (0..4).rev() {
+            let value = remainder << 64 | self.0[index] as u128;
+            limbs[index] = (value / divisor as u128) as u64;
+            remainder = value % divisor as u128;
+        }
+        (U256(limbs), remainder as u64)
+    }
+}
+
+/// 512-bit product as little-endian limbs.
+fn full_mul(a: &U256, b: &U256) -> [u64; 8] {
+    let mut product = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let value = a.0[i] as u128 * b.0[j] as u128 + product[i + j] as u128 + carry;
+            product[i + j] = value as u64;
+            carry = value >> 64;
+        }
+        product[i + 4] = carry as u64;
+    }
+    product
+}
+
+/// Binary long division of a little-endian number by a non-zero word. Quotient bits
+/// beyond 256 are dropped, which only matters for 512-bit dividends whose quotient
+/// is not used.
+fn div_rem_limbs(dividend: &[u64], divisor: U256) -> (U256, U256) {
+    let mut quotient = U256::ZERO;
+    let mut remainder = U256::ZERO;
+    for index in (0..dividend.len() * 64).rev() {
+        let carry = remainder.is_negative();
+        remainder = remainder << 1;
+        remainder.0[0] |= dividend[index / 64] >> (index % 64) & 1;
+        // With the carry the true remainder is 2^256 + remainder, which is at least
+        // the divisor, and the wrapping subtraction yields the correct value.
+        if carry || remainder >= divisor {
+            remainder = remainder - divisor;
+            if index < 256 {
+                quotient.0[index / 64] |= 1 << (index % 64);
+            }
+        }
+    }
+    (quotient, remainder)
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        U256([value, 0, 0, 0])
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        U256([value as u64, (value >> 64) as u64, 0, 0])
+    }
+}
+
+impl From<usize> for U256 {
+    fn from(value: usize) -> Self {
+        U256::from(value as u64)
+    }
+}
+
+impl From<bool> for U256 {
+    fn from(value: bool) -> Self {
+        U256::from(value as u64)
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::ops::Add for U256 {
+    type Output = U256;
+
+    fn add(self, other: U256) -> U256 {
+        self.overflowing_add(other).0
+    }
+}
+
+impl std::ops::Sub for U256 {
+    type Output = U256;
+
+    fn sub(self, other: U256) -> U256 {
+        self.overflowing_sub(other).0
+    }
+}
+
+impl std::ops::Mul for U256 {
+    type Output = U256;
+
+    fn mul(self, other: U256) -> U256 {
+        self.overflowing_mul(other).0
+    }
+}
+
+impl std::ops::Div for U256 {
+    type Output = U256;
+
+    fn div(self, other: U256) -> U256 {
+        self.div_rem(other).0
+    }
+}
+
+impl std::ops::Rem for U256 {
+    type Output = U256;
+
+    fn rem(self, other: U256) -> U256 {
+        self.div_rem(other).1
+    }
+}
+
+impl std::ops::BitAnd for U256 {
+    type Output = U256;
+
+    fn bitand(self, other: U256) -> U256 {
+        U256(std::array::from_fn(|index| self.0[index] & other.0[index]))
+    }
+}
+
+impl std::ops::BitOr for U256 {
+    type Output = U256;
+
+    fn bitor(self, other: U256) -> U256 {
+        U256(std::array::from_fn(|index| self.0[index] | other.0[index]))
+    }
+}
+
+impl std::ops::BitXor for U256 {
+    type Output = U256;
+
+    fn bitxor(self, other: U256) -> U256 {
+        U256(std::array::from_fn(|index| self.0[index] ^ other.0[index]))
+    }
+}
+
+impl std::ops::Not for U256 {
+    type Output = U256;
+
+    fn not(self) -> U256 {
+        U256(self.0.map(|limb| !limb))
+    }
+}
+
+impl std::ops::Shl<usize> for U256 {
+    type Output = U256;
+
+    /// Shifts left, giving zero for shifts of 256 bits or more.
+    fn shl(self, shift: usize) -> U256 {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let (limbs, bits) = (shift / 64, shift % 64);
+        let mut result = [0; 4];
+        for (index, limb) in result.iter_mut().enumerate().skip(limbs) {
+            *limb = self.0[index - limbs] << bits;
+            if bits > 0 && index > limbs {
+                *limb |= self.0[index - limbs - 1] >> (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl std::ops::Shr<usize> for U256 {
+    type Output = U256;
+
+    /// Shifts right, giving zero for shifts of 256 bits or more.
+    fn shr(self, shift: usize) -> U256 {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let (limbs, bits) = (shift / 64, shift % 64);
+        let mut result = [0; 4];
+        for (index, limb) in result.iter_mut().enumerate().take(4 - limbs) {
+            *limb = self.0[index + limbs] >> bits;
+            if bits > 0 && index + limbs < 3 {
+                *limb |= self.0[index + limbs + 1] << (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl std::fmt::Display for U256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut chunks = Vec::new();
+        let mut value = *self;
+        loop {
+            let (quotient, remainder) = value.div_rem_u64(CHUNK);
+            chunks.push(remainder);
+            value = quotient;
+            if value.is_zero() {
+                break;
+            }
+        }
+        let mut digits = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:019}", chunk));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+impl std::fmt::LowerHex for U256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = hex::encode(&self.to_be_bytes());
+        let digits = digits.trim_start_matches('0');
+        f.pad_integral(true, "0x", if digits.is_empty() { "0" } else { digits })
+    }
+}
+
+impl std::fmt::Debug for U256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+/// Error returned when a string is not a 256-bit integer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseU256Error(pub String);
+
+impl std::fmt::Display for ParseU256Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid 256-bit integer `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ParseU256Error {}
+
+impl std::str::FromStr for U256 {
+    type Err = ParseU256Error;
+
+    /// Parses a decimal number, or a hexadecimal one with a `0x` prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseU256Error(s.to_string());
+        let (digits, radix) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(digits) => (digits, 16),
+            None => (s, 10),
+        };
+        if digits.is_empty() {
+            return Err(error());
+        }
+        let radix_word = U256::from(radix as u64);
+        let mut value = U256::ZERO;
+        for c in digits.chars() {
+            let digit = c.to_digit(radix).ok_or_else(error)?;
+            value = value
+                .checked_mul(radix_word)
+                .and_then(|value| value.checked_add(U256::from(digit as u64)))
+                .ok_or_else(error)?;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(s: &str) -> U256 {
+        s.parse().unwrap()
+    }
+
+    /// `-value` in two's complement.
+    fn neg(value: u64) -> U256 {
+        U256::from(value).wrapping_neg()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(word(max), U256::MAX);
+        assert_eq!(U256::MAX.to_string(), max);
+        assert_eq!(word(&format!("{:#x}", U256::MAX)), U256::MAX);
+        assert_eq!(word("0x2a"), U256::from(42u64));
+        assert_eq!(format!("{:x}", U256::ZERO), "0");
+        assert_eq!(format!("{:?}", U256::from(255u64)), "0xff");
+        assert_eq!(
+            U256::from(10_000_000_000_000_000_000u128).to_string(),
+            "10000000000000000000"
+        );
+        assert!(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+                .parse::<U256>()
+                .is_err()
+        );
+        assert!("0x".parse::<U256>().is_err());
+        assert!("12a".parse::<U256>().is_err());
+    }
+
+    #[test]
+    fn bytes() {
+        let mut bytes = [0; 32];
+        bytes[0] = 0x80;
+        bytes[31] = 0x01;
+        let value = U256::from_be_bytes(bytes);
+        assert_eq!(value.to_be_bytes(), bytes);
+        assert!(value.is_negative());
+        assert_eq!(U256::from_be_slice(&[0x01, 0x00]), U256::from(256u64));
+        assert_eq!(U256::from(256u64).to_usize(), Some(256));
+        assert_eq!(U256::MAX.to_u64(), None);
+    }
+
+    #[test]
+    fn wrapping_arithmetic() {
+        assert_eq!(U256::MAX + U256::ONE, U256::ZERO);
+        assert_eq!(U256::ZERO - U256::ONE, U256::MAX);
+        assert_eq!(U256::MAX * U256::MAX, U256::ONE);
+        assert_eq!(
+            word("0x10000000000000000") * word("0x10000000000000000"),
+            word("0x100000000000000000000000000000000")
+        );
+        assert_eq!(
+            U256::MAX / word("0x100000000000000000000000000000000"),
+            word("0xffffffffffffffffffffffffffffffff")
+        );
+        assert_eq!(U256::MAX % word("1000000007"), word("792845265"));
+        assert_eq!(
+            word("0x1234567890abcdef1234567890abcdef1234567890") % word("0x1000000000000000000"),
+            word("0x90abcdef1234567890")
+        );
+        assert_eq!(U256::from(7u64) / U256::ZERO, U256::ZERO);
+        assert_eq!(U256::from(7u64) % U256::ZERO, U256::ZERO);
+    }
+
+    #[test]
+    fn signed_arithmetic() {
+        assert_eq!(neg(8).sdiv(U256::from(3u64)), neg(2));
+        assert_eq!(neg(8).smod(U256::from(3u64)), neg(2));
+        assert_eq!(U256::from(8u64).smod(neg(3)), U256::from(2u64));
+        let min = U256::ONE << 255;
+        assert_eq!(min.sdiv(U256::MAX), min);
+        assert_eq!(neg(1).sdiv(U256::ZERO), U256::ZERO);
+        assert_eq!(neg(1).signed_cmp(&U256::ONE), Ordering::Less);
+        assert_eq!(U256::ONE.signed_cmp(&neg(1)), Ordering::Greater);
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        assert_eq!(
+            U256::MAX.addmod(U256::from(2u64), U256::from(2u64)),
+            U256::ONE
+        );
+        assert_eq!(
+            U256::MAX.mulmod(U256::MAX, U256::from(12u64)),
+            U256::from(9u64)
+        );
+        assert_eq!(
+            U256::MAX.mulmod(U256::MAX, U256::MAX - U256::ONE),
+            U256::ONE
+        );
+        assert_eq!(U256::ONE.addmod(U256::ONE, U256::ZERO), U256::ZERO);
+        assert_eq!(U256::from(2u64).pow(U256::from(255u64)), U256::ONE << 255);
+        assert_eq!(U256::from(2u64).pow(U256::from(256u64)), U256::ZERO);
+        assert_eq!(U256::from(3u64).pow(U256::ZERO), U256::ONE);
+        assert_eq!(U256::from(3u64).pow(U256::from(5u64)), U256::from(243u64));
+    }
+
+    #[test]
+    fn bits_and_shifts() {
+        assert_eq!(U256::from(0xffu64).signextend(U256::ZERO), U256::MAX);
+        assert_eq!(
+            U256::from(0x7fu64).signextend(U256::ZERO),
+            U256::from(0x7fu64)
+        );
+        assert_eq!(U256::from(0x1ff80u64).signextend(U256::ZERO), neg(0x80));
+        assert_eq!(U256::MAX.signextend(U256::from(31u64)), U256::MAX);
+        assert_eq!(word("0x1122").byte(U256::from(30u64)), U256::from(0x11u64));
+        assert_eq!(word("0x1122").byte(U256::from(32u64)), U256::ZERO);
+        assert_eq!(U256::ONE.shl_word(U256::from(256u64)), U256::ZERO);
+        assert_eq!(U256::MAX.shr_word(U256::from(255u64)), U256::ONE);
+        assert_eq!((U256::ONE << 100) >> 36, U256::ONE << 64);
+        assert_eq!(neg(16).sar(U256::from(2u64)), neg(4));
+        assert_eq!(neg(1).sar(U256::MAX), U256::MAX);
+        assert_eq!(U256::from(16u64).sar(U256::from(2u64)), U256::from(4u64));
+        assert_eq!(U256::MAX.bits(), 256);
+        assert_eq!((U256::ONE << 64).leading_zeros(), 191);
+    }
+}
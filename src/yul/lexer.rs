@@ -63,7 +63,7 @@ pub fn parse(input: &'static str) -> Vec<Token> {
                     match c {
                         '/' => {
                             let mut comment = String::new();
-                            while let Some(c) = chars.next() {
+                            for c in chars.by_ref() {
                                 if c == '\n' {
                                     break;
                                 }
@@ -93,7 +93,7 @@ pub fn parse(input: &'static str) -> Vec<Token> {
             }
             ' ' | '\t' | '\r' => {
                 let mut whitespace = String::new();
-                while let Some(c) = chars.next() {
+                for c in chars.by_ref() {
                     if c != ' ' && c != '\t' && c != '\r' {
                         break;
                     }
@@ -116,7 +116,7 @@ pub fn parse(input: &'static str) -> Vec<Token> {
             ']' => Token::new(TokenKind::CloseBracket, 1),
             '"' => {
                 let mut value = String::new();
-                while let Some(c) = chars.next() {
+                for c in chars.by_ref() {
                     if c == '"' {
                         break;
                     }
@@ -135,7 +135,7 @@ pub fn parse(input: &'static str) -> Vec<Token> {
                 let mut value = String::new();
                 value.push(c);
                 let mut base_specified = false;
-                while let Some(c) = chars.next() {
+                for c in chars.by_ref() {
                     if c.is_ascii_hexdigit() || c.is_ascii_digit() || (c == 'x' && !base_specified)
                     {
                         value.push(c);
@@ -164,7 +164,7 @@ pub fn parse(input: &'static str) -> Vec<Token> {
                 if c.is_ascii_alphabetic() {
                     let mut value = String::new();
                     value.push(c);
-                    while let Some(c) = chars.next() {
+                    for c in chars.by_ref() {
                         if c.is_ascii_alphabetic() {
                             value.push(c);
                         } else {
@@ -177,7 +177,7 @@ pub fn parse(input: &'static str) -> Vec<Token> {
                     let mut value = String::new();
                     let mut is_hex = false;
                     value.push(c);
-                    while let Some(c) = chars.next() {
+                    for c in chars.by_ref() {
                         if c.is_ascii_digit() || c.is_ascii_hexdigit() || (c == 'x' && !is_hex) {
                             value.push(c);
                             if c == 'x' {